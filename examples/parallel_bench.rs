@@ -0,0 +1,33 @@
+// Compares root-parallel and shared-tree search at several thread counts.
+// Run with: cargo run --release --example parallel_bench
+use connect_four::games::connect4::bitboard::BitBoard;
+use connect_four::mcts::mcts::{MCTS, ParallelMode};
+use connect_four::mcts::SearchSession;
+
+const TIME_LIMIT_MS: u64 = 2000;
+const THREAD_COUNTS: [usize; 3] = [4, 8, 16];
+
+fn main() {
+    // A quiet opening position where neither side has a forced line yet
+    let mut board = BitBoard::new();
+    for col in [3, 3, 2, 4] {
+        board.make_move(col);
+    }
+
+    println!("{:<14} {:>8} {:>14} {:>12} {:>10}", "mode", "threads", "visits/sec", "max depth", "best move");
+    for mode in [ParallelMode::RootParallel, ParallelMode::SharedTree] {
+        for num_threads in THREAD_COUNTS {
+            let mcts = MCTS::new(1.414, num_threads, TIME_LIMIT_MS, 10).with_parallel_mode(mode);
+            let stats = mcts.analyze(&mut SearchSession::new(), board);
+            let visits_per_sec = stats.total_visits as f64 / stats.elapsed.as_secs_f64();
+            println!(
+                "{:<14} {:>8} {:>14.0} {:>12} {:>10}",
+                format!("{:?}", mode),
+                num_threads,
+                visits_per_sec,
+                stats.max_depth,
+                stats.best_move,
+            );
+        }
+    }
+}
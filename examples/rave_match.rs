@@ -0,0 +1,42 @@
+// Plays RAVE against plain UCB1 at equal thinking time, alternating who moves first.
+// Run with: cargo run --release --example rave_match [games] [time_ms] [equivalence]
+use std::env;
+use connect_four::games::connect4::bitboard::BitBoard;
+use connect_four::mcts::mcts::MCTS;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let games: usize = args.get(1).and_then(|arg| arg.parse().ok()).unwrap_or(20);
+    let time_ms: u64 = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(200);
+    let equivalence: f32 = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or(300.0);
+
+    let rave = MCTS::new(1.414, 4, time_ms, 10).with_rave(equivalence);
+    let ucb1 = MCTS::new(1.414, 4, time_ms, 10);
+
+    let (mut wins, mut draws, mut losses) = (0, 0, 0);
+    for game in 0..games {
+        // RAVE plays first in even games
+        let rave_player = if game % 2 == 0 { 1 } else { 2 };
+        let mut board = BitBoard::new();
+        let result = loop {
+            let player = board.get_current_player();
+            let mcts = if player == rave_player { &rave } else { &ucb1 };
+            let col = mcts.search(board);
+            if let Some(result) = board.make_move(col) {
+                break result;
+            }
+        };
+
+        let rave_result = if rave_player == 1 { result } else { -result };
+        match rave_result {
+            1 => wins += 1,
+            0 => draws += 1,
+            _ => losses += 1,
+        }
+        println!("Game {}: RAVE {} ({} wins, {} draws, {} losses)", game + 1,
+            ["lost", "drew", "won"][(rave_result + 1) as usize], wins, draws, losses);
+    }
+
+    let score = (wins as f64 + 0.5 * draws as f64) / games as f64;
+    println!("RAVE scored {:.1}% against UCB1 at {} ms per move", score * 100.0, time_ms);
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use connect_four::bot::{connect_four_bot, Bot, SearchOptions};
+use connect_four::games::connect4::popout::PopOutBoard;
+use connect_four::games::connect4::rules::Rules;
+use connect_four::player::Player;
+
+// Command-line arguments after the subcommand: positional values and `--flag value` pairs
+pub struct Args {
+    positional: Vec<String>,
+    flags: HashMap<String, String>,
+}
+
+impl Args {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut positional = Vec::new();
+        let mut flags = HashMap::new();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = args.next().ok_or(format!("--{} needs a value", name))?;
+                    flags.insert(name.to_string(), value);
+                }
+                None => positional.push(arg),
+            }
+        }
+        Ok(Self { positional, flags })
+    }
+
+    // The positional argument at `index`, which the command requires
+    pub fn positional(&self, index: usize, name: &str) -> Result<&str, String> {
+        self.positional.get(index)
+            .map(|arg| arg.as_str())
+            .ok_or(format!("missing <{}>", name))
+    }
+
+    pub fn positionals(&self) -> &[String] {
+        &self.positional
+    }
+
+    // Removes and parses `--name`, or returns `default` if it was not given
+    pub fn take<T: FromStr>(&mut self, name: &str, default: T) -> Result<T, String> {
+        match self.flags.remove(name) {
+            Some(value) => value.parse().map_err(|_| format!("invalid value '{}' for --{}", value, name)),
+            None => Ok(default),
+        }
+    }
+
+    pub fn take_string(&mut self, name: &str, default: &str) -> String {
+        self.flags.remove(name).unwrap_or_else(|| default.to_string())
+    }
+
+    // Removes the board flags `--size <columns>x<rows>`, `--connect <n>` and `--variant classic|popout`,
+    // standard by default
+    pub fn take_rules(&mut self) -> Result<Rules, String> {
+        let size = self.take_string("size", "7x6");
+        let connect = self.take("connect", 4usize)?;
+        let popout = match self.take_string("variant", "classic").as_str() {
+            "classic" => false,
+            "popout" => true,
+            other => return Err(format!("unknown --variant '{}', expected classic or popout", other)),
+        };
+        Rules::parse(&size, connect).map(|rules| rules.with_popout(popout)).map_err(|error| error.to_string())
+    }
+
+    // Removes the search flags shared by every command that runs MCTS
+    pub fn take_search(&mut self) -> Result<SearchOptions, String> {
+        let settings = SearchOptions::NAMES.iter()
+            .filter_map(|&name| Some((name.to_string(), self.flags.remove(name)?)))
+            .collect();
+        SearchOptions::from_settings(&settings)
+    }
+
+    // Fails if any flag of `names` was given, for flags that `other` replaces
+    pub fn refuse_with(&self, names: &[&str], other: &str) -> Result<(), String> {
+        match names.iter().find(|&&name| self.flags.contains_key(name)) {
+            Some(name) => Err(format!("--{} cannot be combined with --{}", name, other)),
+            None => Ok(()),
+        }
+    }
+
+    // Fails on flags that no part of the command took
+    pub fn finish(self) -> Result<(), String> {
+        match self.flags.keys().next() {
+            Some(name) => Err(format!("unknown flag --{}", name)),
+            None => Ok(()),
+        }
+    }
+}
+
+// Splits a bot spec such as "mcts:time=200,exploration=1.0" into the kind of bot and its search options
+pub fn parse_bot_spec(spec: &str) -> Result<(BotKind, SearchOptions), String> {
+    let (name, options) = spec.split_once(':').unwrap_or((spec, ""));
+    let mut flags = Vec::new();
+    for option in options.split(',').filter(|option| !option.is_empty()) {
+        let (key, value) = option.split_once('=').ok_or(format!("'{}' in '{}' is not key=value", option, spec))?;
+        flags.push(format!("--{}", key));
+        flags.push(value.to_string());
+    }
+    let mut args = Args::parse(flags.into_iter())?;
+    let search = args.take_search()?;
+    args.finish().map_err(|error| format!("{} in '{}'", error, spec))?;
+    Ok((BotKind::parse(name)?, search))
+}
+
+// The bots the command line offers. Names are checked here, so that building a bot cannot fail
+// and nothing is built just to see whether a name is right.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BotKind {
+    Random,
+    Mcts,
+    Perfect,
+}
+
+impl BotKind {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "random" => Ok(BotKind::Random),
+            "mcts" => Ok(BotKind::Mcts),
+            "perfect" => Ok(BotKind::Perfect),
+            other => Err(format!("unknown bot '{}', expected random, mcts or perfect", other)),
+        }
+    }
+
+    // The bot for games under `rules`
+    pub fn build(self, rules: &Rules, search: &SearchOptions) -> Box<dyn Bot<PopOutBoard>> {
+        let player = match self {
+            BotKind::Random => Player::RandomBot,
+            BotKind::Mcts => Player::MctsBot,
+            BotKind::Perfect => Player::PerfectBot,
+        };
+        connect_four_bot(player, rules, search).expect("every kind of bot is a bot player")
+    }
+}
+
+// Builds a bot by name for games under `rules`
+pub fn make_bot(name: &str, rules: &Rules, search: &SearchOptions) -> Result<Box<dyn Bot<PopOutBoard>>, String> {
+    Ok(BotKind::parse(name)?.build(rules, search))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Args {
+        Args::parse(line.split_whitespace().map(str::to_string)).unwrap()
+    }
+
+    #[test]
+    fn test_flags_and_positionals() {
+        let mut args = parse("4453 --time 50 --bot perfect");
+        assert_eq!(args.positional(0, "position"), Ok("4453"));
+        assert!(args.positional(1, "other").is_err());
+        assert_eq!(args.take("time", 1000u64), Ok(50));
+        assert_eq!(args.take("threads", 4usize), Ok(4));
+        assert_eq!(args.take_string("bot", "mcts"), "perfect");
+        assert!(args.finish().is_ok());
+    }
+
+    #[test]
+    fn test_board_flags() {
+        assert_eq!(parse("").take_rules(), Ok(Rules::STANDARD));
+        assert_eq!(parse("--size 9x7 --connect 5").take_rules(), Rules::new(9, 7, 5).map_err(|error| error.to_string()));
+        assert!(parse("--size 9by7").take_rules().is_err());
+        assert!(parse("--size 6x5 --connect 7").take_rules().is_err());
+        assert_eq!(parse("--variant popout").take_rules(), Ok(Rules::STANDARD.with_popout(true)));
+        assert!(parse("--variant popin").take_rules().is_err());
+    }
+
+    #[test]
+    fn test_bad_flags_are_reported() {
+        assert!(Args::parse(["--time".to_string()].into_iter()).is_err());
+        assert!(parse("--time soon").take("time", 1000u64).is_err());
+        assert!(parse("--mode sideways").finish().is_err());
+        assert!(parse("--mode sideways").take_search().is_err());
+    }
+
+    #[test]
+    fn test_bot_specs() {
+        assert_eq!(parse_bot_spec("random").map(|(kind, _)| kind), Ok(BotKind::Random));
+        assert!(parse_bot_spec("mcts:time=50,exploration=0.7,mode=shared").is_ok());
+        assert!(parse_bot_spec("mcts:time").is_err());
+        assert!(parse_bot_spec("mcts:depth=3").is_err());
+        assert!(parse_bot_spec("alphazero").is_err());
+    }
+
+    #[test]
+    fn test_search_flags() {
+        let mut args = parse("--time 50 --mode shared --playout tactical --rave 300 --bot perfect");
+        let settings = args.take_search().unwrap().to_settings();
+        assert_eq!(settings["time"], "50");
+        assert_eq!(settings["mode"], "shared");
+        assert_eq!(settings["threads"], "4");
+        assert_eq!(args.take_string("bot", "mcts"), "perfect");
+        assert!(args.finish().is_ok());
+    }
+}
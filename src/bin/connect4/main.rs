@@ -0,0 +1,497 @@
+// Headless front end: play, analyze, solve, self-play and run tournaments from a terminal, or play in a
+// full-screen terminal UI.
+// Run with: cargo run --release --bin connect4 -- <command> [arguments] [--flag value ...]
+mod args;
+mod tui;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+use connect_four::bot::SearchOptions;
+use connect_four::games::connect4::bitboard::BitBoard;
+use connect_four::games::connect4::notation::{format_grid, format_move_list, parse_position_with};
+use connect_four::games::connect4::popout::{Move, PopOutBoard};
+use connect_four::games::connect4::rules::Rules;
+use connect_four::games::connect4::solver::Solver;
+use connect_four::mcts::mcts::SearchStats;
+use connect_four::mcts::{perspective, SearchSession};
+use connect_four::player::Player;
+use connect_four::replay::{Annotator, Evaluator};
+use connect_four::saved_game::{unix_time, SavedGame, SavedPlayer};
+use connect_four::tournament::{Entrant, Format, Sprt, Tournament};
+use args::{make_bot, parse_bot_spec, Args};
+
+const USAGE: &str = "\
+usage: connect4 <command> [arguments] [--flag value ...]
+
+commands:
+  tui                  full-screen terminal game with the same player choices as the window
+  play                 play against a bot in the terminal
+                         --bot random|mcts|perfect (mcts)  --first human|bot (human)
+                         --position <position> (the empty board)
+                         --save <file.json> (write the game when it ends or you quit)
+                         --load <file.json> (continue a saved game with its players, board and bot settings;
+                           cannot be combined with --bot, --first, --position, the board or the search flags)
+  analyze <position>   search a position with MCTS and print the statistics of every column
+  solve <position>     solve a position exactly
+  review <file.json>   evaluate every move of a saved game and mark the mistakes
+                         --evaluator solver|mcts (solver)  --time <ms per position with mcts> (1000)
+                         --skip <opening moves left out> (8 with the solver, which is slow on near-empty boards)
+  selfplay             let two bots play each other
+                         --p1 <bot> (mcts)  --p2 <bot> (mcts)  --games <n> (1)
+  tournament <bot>...  play matches between bots and report Elo differences
+                         --format roundrobin|gauntlet (roundrobin)  --games <n per pairing> (20)
+                         --concurrency <n> (1)  --openings <random plies> (2)
+                         --sprt <elo0>,<elo1> (off, tests the first bot)  --report <file.json>
+                       bots are random, perfect or mcts, the latter two with search options, e.g. mcts:time=200,exploration=1.0
+
+positions are move sequences such as 4453 or grids such as \"7/7/7/7/3o3/2oxx2 x\"
+
+board flags (play, analyze, solve, selfplay, tournament):
+  --size <columns>x<rows> (7x6, up to 9 columns)  --connect <n> (4)
+  --variant classic|popout (classic)
+  the solver handles classic boards of up to 64 cells and sentinels, (rows + 1) * columns; perfect
+  bots search with MCTS as the search flags say on larger ones and in PopOut, where moves such as p4
+  pop a disc of column 4
+
+search flags (play, analyze, selfplay):
+  --time <ms> (1000)  --threads <n> (4)  --exploration <c> (1.414)  --simulations <n> (10)
+  --mode root|shared (root)  --selection visits|value|secure (visits)
+  --playout random|tactical (random, PopOut always random)  --rave <equivalence> (off)";
+
+fn main() -> ExitCode {
+    let mut argv = env::args().skip(1);
+    let command = argv.next();
+    let result = Args::parse(argv).and_then(|args| match command.as_deref() {
+        Some("tui") => args.finish().and_then(|()| tui::run()),
+        Some("play") => play(args),
+        Some("analyze") => analyze(args),
+        Some("solve") => solve(args),
+        Some("review") => review(args),
+        Some("selfplay") => selfplay(args),
+        Some("tournament") => tournament(args),
+        Some("help") | Some("--help") | None => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(other) => Err(format!("unknown command '{}'\n\n{}", other, USAGE)),
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Flags of `play` that the game given with `--load` decides
+const LOADED_FLAGS: [&str; 6] = ["bot", "first", "position", "size", "connect", "variant"];
+
+fn play(mut args: Args) -> Result<(), String> {
+    let load_path = args.take_string("load", "");
+    if !load_path.is_empty() {
+        args.refuse_with(&LOADED_FLAGS, "load")?;
+        args.refuse_with(&SearchOptions::NAMES, "load")?;
+    }
+    let mut search = args.take_search()?;
+    let mut bot_name = args.take_string("bot", "mcts");
+    let mut human_player = match args.take_string("first", "human").as_str() {
+        "human" => 1,
+        "bot" => 2,
+        other => return Err(format!("unknown --first '{}', expected human or bot", other)),
+    };
+    let rules = args.take_rules()?;
+    let position = args.take_string("position", "");
+    let save_path = args.take_string("save", "");
+    args.finish()?;
+
+    let mut start = if position.is_empty() {
+        BitBoard::with_rules(rules)
+    } else {
+        read_position(rules, &position)?
+    };
+    let mut moves = Vec::new();
+    let mut started = unix_time();
+    if !load_path.is_empty() {
+        // The saved game decides who plays, on which board and how the bot searches
+        let game = SavedGame::load(Path::new(&load_path)).map_err(|error| error.to_string())?;
+        let human_index = game.players.iter().position(|saved| saved.player == Player::Human);
+        let bot = game.players.iter().find(|saved| saved.player.is_bot());
+        let (Some(human_index), Some(bot)) = (human_index, bot) else {
+            return Err(format!("{} is not a game between a human and a bot", load_path));
+        };
+        human_player = human_index as u8 + 1;
+        bot_name = bot.player.name().to_string();
+        search = SearchOptions::from_settings(&bot.settings)?;
+        start = game.get_start_position();
+        moves = game.get_moves();
+        started = game.started;
+    }
+    let mut bot = make_bot(&bot_name, &start.get_rules(), &search)?;
+
+    let bot_player = Player::ALL.into_iter().find(|player| player.name() == bot_name).unwrap();
+    let bot_settings = if bot_player.searches() { search.to_settings() } else { BTreeMap::new() };
+    let mut players = [
+        SavedPlayer { player: Player::Human, settings: BTreeMap::new() },
+        SavedPlayer { player: bot_player, settings: bot_settings },
+    ];
+    if human_player == 2 {
+        players.reverse();
+    }
+    let save = |moves: &[Move]| -> Result<(), String> {
+        if save_path.is_empty() {
+            return Ok(());
+        }
+        SavedGame::from_position(players.clone(), &start, moves, started).save(Path::new(&save_path)).map_err(|error| error.to_string())?;
+        println!("saved to {}", save_path);
+        Ok(())
+    };
+
+    let mut game = PopOutBoard::new(start);
+    let mut result = start.get_result();
+    for &mv in &moves {
+        result = game.make_move(mv);
+    }
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        let board = *game.get_position();
+        print_board(&board);
+        if let Some(result) = result {
+            match perspective(result, human_player) {
+                1.. => println!("You win!"),
+                0 => println!("Draw."),
+                _ => println!("{} wins.", bot_name),
+            }
+            return save(&moves);
+        }
+
+        let player = board.get_current_player();
+        let mv = if player == human_player {
+            let columns = board.get_rules().columns;
+            if board.get_rules().popout {
+                print!("Your move (1-{} to drop, p1-p{} to pop, q to quit): ", columns, columns);
+            } else {
+                print!("Your move (1-{}, q to quit): ", columns);
+            }
+            io::stdout().flush().map_err(|error| error.to_string())?;
+            let line = match lines.next() {
+                Some(line) => line.map_err(|error| error.to_string())?,
+                None => return save(&moves),
+            };
+            let line = line.trim();
+            if line == "q" {
+                return save(&moves);
+            }
+            match parse_move(line).filter(|&mv| game.is_valid_move(mv)) {
+                Some(mv) => mv,
+                None => {
+                    println!("'{}' is not a playable move", line);
+                    continue;
+                }
+            }
+        } else {
+            let mv = bot.make_move(&game).ok_or("the bot found no move")?;
+            println!("{} plays {}", bot_name, mv);
+            mv
+        };
+        moves.push(mv);
+        result = game.make_move(mv);
+    }
+}
+
+// Reads a column numbered from 1, or a pop such as "p4"
+fn parse_move(text: &str) -> Option<Move> {
+    let (pop, column) = match text.strip_prefix('p') {
+        Some(column) => (true, column),
+        None => (false, text),
+    };
+    let col = column.parse::<usize>().ok()?.checked_sub(1)?;
+    Some(if pop { Move::Pop(col) } else { Move::Drop(col) })
+}
+
+fn analyze(mut args: Args) -> Result<(), String> {
+    let rules = args.take_rules()?;
+    let board = read_position(rules, args.positional(0, "position")?)?;
+    let search = args.take_search()?;
+    args.finish()?;
+
+    if rules.popout {
+        let stats = search.build_popout().analyze(&mut SearchSession::new(), PopOutBoard::new(board));
+        print_analysis(&board, &stats, |mv| mv.to_string());
+    } else {
+        let stats = search.build().analyze(&mut SearchSession::new(), board);
+        print_analysis(&board, &stats, |col| (col + 1).to_string());
+    }
+    Ok(())
+}
+
+// Prints the board and the statistics of a search of it, with moves written by `name`
+fn print_analysis<M: Copy + Ord>(board: &BitBoard, stats: &SearchStats<M>, name: impl Fn(M) -> String) {
+    let player = board.get_current_player();
+    print_board(board);
+
+    let mut children = stats.children.clone();
+    children.sort_by_key(|child| child.action);
+    println!("  move     visits    value  proven");
+    for child in &children {
+        let proven = match child.result.map(|result| perspective(result, player)) {
+            Some(1..) => "win",
+            Some(0) => "draw",
+            Some(_) => "loss",
+            None => "",
+        };
+        println!("{:>6} {:>10} {:>8.3}  {}", name(child.action), child.visits, child.value(player), proven);
+    }
+    let best_line: String = stats.best_line.iter().map(|&mv| name(mv)).collect();
+    println!("best move: {}, best line: {}", name(stats.best_move), best_line);
+    println!("visits: {}, max depth: {}, time: {:.2?}", stats.total_visits, stats.max_depth, stats.elapsed);
+}
+
+fn solve(mut args: Args) -> Result<(), String> {
+    let rules = args.take_rules()?;
+    let board = read_position(rules, args.positional(0, "position")?)?;
+    args.finish()?;
+    if !Solver::supports(&rules) {
+        return Err(format!("the solver cannot handle {} boards", rules));
+    }
+    print_board(&board);
+
+    let start = Instant::now();
+    let mut solver = Solver::new();
+    let solutions = solver.analyze(&board);
+    println!("column  solution");
+    for (col, solution) in solutions.iter().enumerate() {
+        if let Some(solution) = solution {
+            println!("{:>6}  {:?}", col + 1, solution);
+        }
+    }
+    match solver.best_move(&board) {
+        Some(col) => println!("position: {:?}, best move: {}", solver.solve(&board), col + 1),
+        None => println!("the position has no moves left"),
+    }
+    println!("nodes: {}, time: {:.2?}", solver.get_explored_nodes(), start.elapsed());
+    Ok(())
+}
+
+fn review(mut args: Args) -> Result<(), String> {
+    let path = args.positional(0, "saved game")?.to_string();
+    let evaluator = match args.take_string("evaluator", "solver").as_str() {
+        "solver" => Evaluator::Solver,
+        "mcts" => Evaluator::Mcts { time_ms: args.take("time", 1000u64)? },
+        other => return Err(format!("unknown --evaluator '{}', expected solver or mcts", other)),
+    };
+    let skip = args.take("skip", if evaluator == Evaluator::Solver { 8 } else { 0 })?;
+    args.finish()?;
+    let game = SavedGame::load(Path::new(&path)).map_err(|error| error.to_string())?;
+    if game.rules.popout {
+        return Err("PopOut games cannot be reviewed, neither evaluator knows pops".to_string());
+    }
+    if evaluator == Evaluator::Solver && !Solver::supports(&game.rules) {
+        return Err(format!("the solver cannot handle {} boards, try --evaluator mcts", game.rules));
+    }
+    println!("{} (player 1) vs {} (player 2): {}", game.players[0].player.name(), game.players[1].player.name(), game.moves);
+
+    // Last position first, as the replay screen does, so the solver's table fills up before the hard openings
+    let moves: Vec<usize> = game.get_moves().iter().map(Move::column).collect();
+    let mut annotator = Annotator::new(evaluator);
+    let never = AtomicBool::new(false);
+    let mut board = game.get_start_position();
+    let first_player = board.get_current_player() as usize;
+    for &col in &moves {
+        board.make_move(col);
+    }
+    let mut annotations = Vec::with_capacity(moves.len());
+    for &col in moves.iter().skip(skip).rev() {
+        board.unmake_move(col);
+        annotations.push(annotator.annotate(&board, Some(col), &never).ok_or("the evaluator found no move")?);
+    }
+    annotations.reverse();
+
+    let mut mistakes = [0; 2];
+    for ((index, col), annotation) in moves.iter().enumerate().skip(skip).zip(annotations) {
+        let player = if index % 2 == 0 { first_player } else { 3 - first_player };
+        let played = annotation.played.map_or("-".to_string(), |played| played.to_string());
+        let mark = if annotation.is_mistake() {
+            mistakes[player - 1] += 1;
+            "  mistake"
+        } else {
+            ""
+        };
+        println!("{:>3}. player {} plays {}: {} (best {}: {}){}",
+            index + 1, player, col + 1, played, annotation.best_move + 1, annotation.best, mark);
+    }
+    println!("mistakes: player 1 {}, player 2 {}", mistakes[0], mistakes[1]);
+    Ok(())
+}
+
+fn selfplay(mut args: Args) -> Result<(), String> {
+    let search = args.take_search()?;
+    let names = [args.take_string("p1", "mcts"), args.take_string("p2", "mcts")];
+    let games = args.take("games", 1usize)?;
+    let rules = args.take_rules()?;
+    args.finish()?;
+
+    let mut bots = [make_bot(&names[0], &rules, &search)?, make_bot(&names[1], &rules, &search)?];
+    let mut wins = [0; 2];
+    let mut draws = 0;
+    for game in 0..games {
+        let mut board = PopOutBoard::new(BitBoard::with_rules(rules));
+        let mut moves = Vec::new();
+        let result = loop {
+            let player = board.get_current_player();
+            let mv = bots[player as usize - 1].make_move(&board).ok_or("a bot found no move")?;
+            moves.push(mv);
+            if let Some(result) = board.make_move(mv) {
+                break result;
+            }
+        };
+
+        let outcome = match result {
+            1 => { wins[0] += 1; "player 1 wins" }
+            -1 => { wins[1] += 1; "player 2 wins" }
+            _ => { draws += 1; "draw" }
+        };
+        println!("game {}: {} ({}) {}", game + 1, format_move_list(&moves), outcome, format_grid(board.get_position()));
+    }
+    println!("{} (player 1) {} - {} draws - {} {} (player 2)", names[0], wins[0], draws, wins[1], names[1]);
+    Ok(())
+}
+
+fn tournament(mut args: Args) -> Result<(), String> {
+    let format = match args.take_string("format", "roundrobin").as_str() {
+        "roundrobin" => Format::RoundRobin,
+        "gauntlet" => Format::Gauntlet,
+        other => return Err(format!("unknown --format '{}', expected roundrobin or gauntlet", other)),
+    };
+    let games = args.take("games", 20usize)?;
+    let concurrency = args.take("concurrency", 1usize)?;
+    let openings = args.take("openings", 2usize)?;
+    let sprt = match args.take_string("sprt", "").as_str() {
+        "" => None,
+        bounds => {
+            let (elo0, elo1) = bounds.split_once(',')
+                .and_then(|(elo0, elo1)| Some((elo0.parse().ok()?, elo1.parse().ok()?)))
+                .ok_or(format!("--sprt expects <elo0>,<elo1>, found '{}'", bounds))?;
+            Some(Sprt::new(elo0, elo1))
+        }
+    };
+    let report_path = args.take_string("report", "");
+    let rules = args.take_rules()?;
+
+    let names = args.positionals().to_vec();
+    let mut entrants = Vec::new();
+    for spec in &names {
+        let (kind, search) = parse_bot_spec(spec)?;
+        entrants.push(Entrant::new(spec, move |rules| kind.build(rules, &search)));
+    }
+    args.finish()?;
+    if entrants.len() < 2 {
+        return Err("a tournament needs at least two bots".to_string());
+    }
+
+    let mut tournament = Tournament::new(entrants)
+        .with_format(format)
+        .with_rules(rules)
+        .with_games_per_pairing(games)
+        .with_concurrency(concurrency)
+        .with_opening_moves(openings);
+    if let Some(sprt) = sprt {
+        tournament = tournament.with_sprt(sprt);
+    }
+
+    let report = tournament.run(|game| {
+        let outcome = ["player 2 wins", "draw", "player 1 wins"][(game.result + 1) as usize];
+        println!("{} vs {}: {} ({})", names[game.player1], names[game.player2], game.moves, outcome);
+    }).map_err(|error| error.to_string())?;
+
+    println!();
+    println!("{:<40} {:>6} {:>6} {:>6} {:>8}", "bot", "wins", "draws", "losses", "points");
+    for standing in &report.standings {
+        println!("{:<40} {:>6} {:>6} {:>6} {:>8.1}", standing.name, standing.score.wins,
+            standing.score.draws, standing.score.losses, standing.points);
+    }
+    println!();
+    for pairing in &report.pairings {
+        let elo = match pairing.elo {
+            Some(elo) => format!("{:+.0} +/- {:.0}", elo.elo, elo.error),
+            None => "-".to_string(),
+        };
+        println!("{} vs {}: {}-{}-{}, Elo {}", report.entrants[pairing.entrant], report.entrants[pairing.opponent],
+            pairing.score.wins, pairing.score.draws, pairing.score.losses, elo);
+    }
+    if let Some(sprt) = &report.sprt {
+        let decision = match sprt.decision {
+            Some(decision) => format!("{:?}", decision),
+            None => "undecided".to_string(),
+        };
+        println!("SPRT [{}, {}]: LLR {:.2} ({:.2}, {:.2}) {}", sprt.test.elo0, sprt.test.elo1, sprt.llr,
+            sprt.lower_bound, sprt.upper_bound, decision);
+    }
+
+    if !report_path.is_empty() {
+        fs::write(&report_path, report.to_json()).map_err(|error| format!("cannot write {}: {}", report_path, error))?;
+        println!("report written to {}", report_path);
+    }
+    Ok(())
+}
+
+// Reads a position to play or search from, refusing one where the game is already over
+fn read_position(rules: Rules, text: &str) -> Result<BitBoard, String> {
+    let board = parse_position_with(rules, text).map_err(|error| error.to_string())?;
+    match board.get_result() {
+        Some(1) => Err("the game is already over, player 1 has won".to_string()),
+        Some(-1) => Err("the game is already over, player 2 has won".to_string()),
+        Some(_) => Err("the game is already over, it is a draw".to_string()),
+        None => Ok(board),
+    }
+}
+
+fn print_board(board: &BitBoard) {
+    let rules = board.get_rules();
+    for row in (0..rules.rows).rev() {
+        let cells: Vec<&str> = (0..rules.columns)
+            .map(|col| [".", "x", "o"][board.get_player_at(row, col) as usize])
+            .collect();
+        println!("{}", cells.join(" "));
+    }
+    let numbers: Vec<String> = (1..=rules.columns).map(|col| col.to_string()).collect();
+    println!("{}", numbers.join(" "));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finished_positions_are_refused() {
+        // Player 1's vertical four in column 1, as a sequence and as a grid
+        let error = read_position(Rules::STANDARD, "1212121").unwrap_err();
+        assert!(error.contains("player 1 has won"), "{}", error);
+        assert!(read_position(Rules::STANDARD, "7/7/7/7/ooo4/xxxx3 o").is_err());
+
+        // A full board without a line
+        let error = read_position(Rules::new(4, 2, 3).unwrap(), "oxox/xoxo x").unwrap_err();
+        assert!(error.contains("draw"), "{}", error);
+
+        assert!(read_position(Rules::STANDARD, "121212").is_ok());
+    }
+
+    // The flags are refused before the file is read, so no saved game is needed
+    #[test]
+    fn test_flags_the_loaded_game_decides_are_refused() {
+        let args = |line: &str| Args::parse(line.split_whitespace().map(str::to_string)).unwrap();
+        for flag in ["--bot random", "--first bot", "--position 44", "--size 9x7", "--variant popout", "--time 50"] {
+            let error = play(args(&format!("--load missing.json {}", flag))).unwrap_err();
+            assert!(error.ends_with("cannot be combined with --load"), "{}", error);
+        }
+    }
+}
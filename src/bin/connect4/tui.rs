@@ -0,0 +1,281 @@
+// Full-screen terminal frontend, the counterpart of the ggez window for sessions without a display.
+// Game rules and bots come from `GameController`; this module only draws and reads keys.
+use std::io::{self, Stdout, Write};
+use std::time::Duration;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Attribute, Color, Print, SetAttribute, SetForegroundColor, ResetColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use connect_four::config::{BOARD_SIZES, CONNECT_LENGTHS, VARIANTS};
+use connect_four::controller::GameController;
+use connect_four::games::connect4::bitboard::BitBoard;
+use connect_four::games::connect4::notation::format_move_list;
+use connect_four::games::connect4::rules::Rules;
+use connect_four::player::Player;
+
+#[derive(PartialEq, Clone, Copy)]
+enum Screen {
+    Menu,
+    Game,
+    GameOver,
+}
+
+// Rows of the menu: the two player choices, the board size, the connect length, the variant and the start entry
+const MENU_ROWS: usize = 6;
+const START_ROW: usize = 5;
+
+struct Tui {
+    controller: GameController,
+    screen: Screen,
+    player_choices: [usize; 2],  // Indices into `Player::ALL`
+    size_choice: usize,          // Index into `BOARD_SIZES`
+    connect_choice: usize,       // Index into `CONNECT_LENGTHS`
+    variant_choice: usize,       // Index into `VARIANTS`
+    menu_row: usize,
+    cursor_col: usize,           // Column a human move drops into
+}
+
+// Puts the terminal into raw mode on the alternate screen and restores it when dropped, even on errors
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new(stdout: &mut Stdout) -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout, EnterAlternateScreen, Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+pub fn run() -> Result<(), String> {
+    let mut stdout = io::stdout();
+    let _guard = TerminalGuard::new(&mut stdout).map_err(|error| error.to_string())?;
+    let mut tui = Tui {
+        controller: GameController::new(),
+        screen: Screen::Menu,
+        player_choices: [0, 2],
+        size_choice: 0,
+        connect_choice: 0,
+        variant_choice: 0,
+        menu_row: 0,
+        cursor_col: 0,
+    };
+    tui.run(&mut stdout).map_err(|error| error.to_string())
+}
+
+impl Tui {
+    fn run(&mut self, stdout: &mut Stdout) -> io::Result<()> {
+        let mut redraw = true;
+        loop {
+            if redraw {
+                self.draw(stdout)?;
+            }
+            redraw = true;
+
+            // Bots think in the background while keys are still read, so Esc and q work during a search
+            if self.screen == Screen::Game && !self.controller.is_human_turn() {
+                if self.controller.poll_bot_move().is_some() {
+                    self.update_screen();
+                    continue;
+                }
+                if !event::poll(Duration::from_millis(20))? {
+                    redraw = false;
+                    continue;
+                }
+            }
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            if key.code == KeyCode::Char('q') {
+                return Ok(());
+            }
+            match self.screen {
+                Screen::Menu => self.handle_menu_key(key.code),
+                Screen::Game => self.handle_game_key(key.code),
+                Screen::GameOver => match key.code {
+                    KeyCode::Enter | KeyCode::Char('n') | KeyCode::Esc => self.screen = Screen::Menu,
+                    KeyCode::Char('u') => {
+                        self.controller.undo();
+                        self.screen = Screen::Game;
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    fn handle_menu_key(&mut self, key: KeyCode) {
+        // Number of options of the choice on the selected row
+        let count = match self.menu_row {
+            0 | 1 => Player::ALL.len(),
+            2 => BOARD_SIZES.len(),
+            3 => CONNECT_LENGTHS.len(),
+            4 => VARIANTS.len(),
+            _ => 0,
+        };
+        match key {
+            KeyCode::Left | KeyCode::Right if count > 0 => {
+                let step = if key == KeyCode::Left { count - 1 } else { 1 };
+                let choice = match self.menu_row {
+                    0 | 1 => &mut self.player_choices[self.menu_row],
+                    2 => &mut self.size_choice,
+                    3 => &mut self.connect_choice,
+                    _ => &mut self.variant_choice,
+                };
+                *choice = (*choice + step) % count;
+            }
+            KeyCode::Up => self.menu_row = (self.menu_row + MENU_ROWS - 1) % MENU_ROWS,
+            KeyCode::Down | KeyCode::Tab => self.menu_row = (self.menu_row + 1) % MENU_ROWS,
+            KeyCode::Enter => {
+                let rules = self.selected_rules();
+                self.controller.start_from(self.player_choices.map(|choice| Player::ALL[choice]), &BitBoard::with_rules(rules));
+                self.cursor_col = (rules.columns - 1) / 2;
+                self.screen = Screen::Game;
+            }
+            _ => {}
+        }
+    }
+
+    fn selected_rules(&self) -> Rules {
+        let (columns, rows) = BOARD_SIZES[self.size_choice];
+        Rules::new(columns, rows, CONNECT_LENGTHS[self.connect_choice])
+            .expect("Every size offered has room for every length")
+            .with_popout(VARIANTS[self.variant_choice].1)
+    }
+
+    fn handle_game_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Left => self.cursor_col = self.cursor_col.saturating_sub(1),
+            KeyCode::Right => self.cursor_col = (self.cursor_col + 1).min(self.controller.get_rules().columns - 1),
+            KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Down => {
+                self.controller.play_column(self.cursor_col);
+            }
+            KeyCode::Char('p') | KeyCode::Up => {
+                self.controller.pop_column(self.cursor_col);
+            }
+            KeyCode::Char(digit @ '1'..='9') => {
+                let col = digit as usize - '1' as usize;
+                if col < self.controller.get_rules().columns {
+                    self.cursor_col = col;
+                    self.controller.play_column(col);
+                }
+            }
+            KeyCode::Char('u') => {
+                self.controller.undo();
+            }
+            KeyCode::Char('r') => {
+                self.controller.redo();
+            }
+            KeyCode::Esc => {
+                self.controller.cancel_bot_move();
+                self.screen = Screen::Menu;
+            }
+            _ => {}
+        }
+        self.update_screen();
+    }
+
+    // Moves on to the game over screen once the game has ended
+    fn update_screen(&mut self) {
+        if self.screen == Screen::Game && self.controller.is_game_over() {
+            self.screen = Screen::GameOver;
+        }
+    }
+
+    fn draw(&self, stdout: &mut Stdout) -> io::Result<()> {
+        queue!(stdout, Clear(ClearType::All), MoveTo(0, 0))?;
+        match self.screen {
+            Screen::Menu => self.draw_menu(stdout)?,
+            Screen::Game | Screen::GameOver => self.draw_game(stdout)?,
+        }
+        stdout.flush()
+    }
+
+    fn draw_menu(&self, stdout: &mut Stdout) -> io::Result<()> {
+        line(stdout, 0, "Connect Four")?;
+        for (row, &choice) in self.player_choices.iter().enumerate() {
+            let text = format!("Player {}:  < {} >", row + 1, Player::ALL[choice].label());
+            self.draw_menu_entry(stdout, row, &text)?;
+        }
+        let (columns, rows) = BOARD_SIZES[self.size_choice];
+        self.draw_menu_entry(stdout, 2, &format!("Board:     < {}x{} >", columns, rows))?;
+        self.draw_menu_entry(stdout, 3, &format!("Connect:   < {} >", CONNECT_LENGTHS[self.connect_choice]))?;
+        self.draw_menu_entry(stdout, 4, &format!("Variant:   < {} >", VARIANTS[self.variant_choice].0))?;
+        self.draw_menu_entry(stdout, START_ROW, "Start Game")?;
+        line(stdout, 10, "Up/Down: choose   Left/Right: change   Enter: start   q: quit")
+    }
+
+    fn draw_menu_entry(&self, stdout: &mut Stdout, row: usize, text: &str) -> io::Result<()> {
+        queue!(stdout, MoveTo(2, 2 + row as u16))?;
+        if row == self.menu_row {
+            queue!(stdout, SetAttribute(Attribute::Reverse), Print(text), SetAttribute(Attribute::Reset))
+        } else {
+            queue!(stdout, Print(text))
+        }
+    }
+
+    fn draw_game(&self, stdout: &mut Stdout) -> io::Result<()> {
+        let player_number = self.controller.get_current_player_number();
+        let status = if self.screen == Screen::GameOver {
+            match self.controller.get_winner() {
+                Some(winner) => format!("Player {} Wins!", winner),
+                None => "Game Draw!".to_string(),
+            }
+        } else if self.controller.is_human_turn() {
+            format!("Current Turn: Player {} ({})", player_number, self.controller.get_current_player_type().label())
+        } else {
+            format!("Current Turn: Player {} ({}) is thinking...", player_number, self.controller.get_current_player_type().label())
+        };
+        line(stdout, 0, &status)?;
+
+        // Drop marker above the selected column
+        if self.controller.is_human_turn() {
+            queue!(stdout, MoveTo(2 + 3 * self.cursor_col as u16, 2), SetForegroundColor(disc_color(player_number)), Print("▼"), ResetColor)?;
+        }
+
+        // Board, top row first
+        let board = self.controller.get_board();
+        let rules = board.get_rules();
+        for row in (0..rules.rows).rev() {
+            let y = 3 + (rules.rows - 1 - row) as u16;
+            queue!(stdout, MoveTo(0, y), SetForegroundColor(Color::Blue), Print("│"))?;
+            for col in 0..rules.columns {
+                match board.get_player_number(row, col) {
+                    0 => queue!(stdout, SetForegroundColor(Color::DarkGrey), Print(" · "))?,
+                    player => queue!(stdout, SetForegroundColor(disc_color(player)), Print(" ● "))?,
+                }
+            }
+            queue!(stdout, SetForegroundColor(Color::Blue), Print("│"), ResetColor)?;
+        }
+        let numbers: String = (1..=rules.columns).map(|col| format!(" {} ", col)).collect();
+        line(stdout, 3 + rules.rows as u16, &format!(" {}", numbers))?;
+        line(stdout, 5 + rules.rows as u16, &format!("Moves: {}", format_move_list(self.controller.get_moves())))?;
+
+        let help = if self.screen == Screen::GameOver {
+            "Enter: new game   u: undo   q: quit".to_string()
+        } else {
+            let pop = if rules.popout { "p/Up: pop   " } else { "" };
+            format!("Left/Right: move   Enter/Down: drop   1-{}: drop in column   {}u/r: undo/redo   Esc: menu   q: quit", rules.columns, pop)
+        };
+        line(stdout, 7 + rules.rows as u16, &help)
+    }
+}
+
+fn disc_color(player_number: u8) -> Color {
+    if player_number == 1 { Color::Red } else { Color::Yellow }
+}
+
+fn line(stdout: &mut Stdout, y: u16, text: &str) -> io::Result<()> {
+    queue!(stdout, MoveTo(0, y), Print(text))
+}
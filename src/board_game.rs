@@ -0,0 +1,208 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use crate::bot::{Bot, MctsBot, RandomBot};
+use crate::games::{Game, Outcome};
+use crate::mcts::mcts::MCTS;
+use crate::player::Player;
+
+// Thinking time of the MCTS bot
+const MCTS_TIME_MS: u64 = 1000;
+
+// Turn order of a game of any `Game` between two players, for the games that have no connect
+// four style controller with saves and replays. Bots think on a background thread like
+// `GameController`'s. There is no solver for these games, so the perfect bot searches with MCTS.
+pub struct BoardGame<G: Game + 'static> {
+    game: G,
+    player_types: [Player; 2],
+    moves: Vec<G::Move>,          // Moves played so far
+    outcome: Option<G::Outcome>,  // Set once the game is over
+    mcts_bot: Arc<Mutex<MctsBot<G>>>,  // Keeps its search tree between turns
+    pending_move: Option<PendingMove<G::Move>>,
+}
+
+// A bot move being computed on a background thread
+struct PendingMove<M> {
+    handle: JoinHandle<Option<M>>,
+    stop: Arc<AtomicBool>,  // Set to make the bot give up
+}
+
+impl<G: Game + 'static> BoardGame<G> {
+    pub fn new(player_types: [Player; 2], game: G) -> Self {
+        Self::with_mcts(player_types, game, MCTS::new(1.414, 4, MCTS_TIME_MS, 10))
+    }
+
+    // A game whose MCTS bot searches with an already configured `MCTS`, e.g. with a playout policy
+    pub fn with_mcts(player_types: [Player; 2], game: G, mcts: MCTS<G>) -> Self {
+        Self {
+            game,
+            player_types,
+            moves: Vec::new(),
+            outcome: None,
+            mcts_bot: Arc::new(Mutex::new(MctsBot::with_mcts(mcts))),
+            pending_move: None,
+        }
+    }
+
+    pub fn get_game(&self) -> &G {
+        &self.game
+    }
+
+    pub fn get_moves(&self) -> &[G::Move] {
+        &self.moves
+    }
+
+    pub fn get_current_player_number(&self) -> u8 {
+        self.game.get_current_player()
+    }
+
+    pub fn get_current_player_type(&self) -> Player {
+        self.player_types[self.get_current_player_number() as usize - 1]
+    }
+
+    pub fn get_outcome(&self) -> Option<G::Outcome> {
+        self.outcome
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    // The winner once the game is over, None while it goes on or after a draw
+    pub fn get_winner(&self) -> Option<u8> {
+        match self.outcome?.reward() {
+            reward if reward > 0 => Some(1),
+            reward if reward < 0 => Some(2),
+            _ => None,
+        }
+    }
+
+    pub fn is_human_turn(&self) -> bool {
+        !self.is_game_over() && self.get_current_player_type() == Player::Human
+    }
+
+    pub fn is_thinking(&self) -> bool {
+        self.pending_move.is_some()
+    }
+
+    // Plays `mv` for the current player. Returns false if the game is over or the move is not valid.
+    pub fn play_move(&mut self, mv: G::Move) -> bool {
+        if self.is_game_over() || !self.game.is_valid_move(mv) {
+            return false;
+        }
+        self.outcome = self.game.make_move(mv);
+        self.moves.push(mv);
+        true
+    }
+
+    // Lets the current player move if it is a bot, blocking while it thinks. Returns the move played.
+    pub fn play_bot_move(&mut self) -> Option<G::Move> {
+        self.start_thinking();
+        let pending = self.pending_move.take()?;
+        self.finish_thinking(pending)
+    }
+
+    // Starts the current bot thinking in the background if it is not already, and plays its move
+    // once it is ready. Never blocks, so frontends can call it every frame. Returns the move played.
+    pub fn poll_bot_move(&mut self) -> Option<G::Move> {
+        self.start_thinking();
+        if !self.pending_move.as_ref()?.handle.is_finished() {
+            return None;
+        }
+        let pending = self.pending_move.take()?;
+        self.finish_thinking(pending)
+    }
+
+    // Stops a bot that is thinking in the background and waits for its thread to end
+    pub fn cancel_bot_move(&mut self) {
+        if let Some(pending) = self.pending_move.take() {
+            pending.stop.store(true, Ordering::Relaxed);
+            let _ = pending.handle.join();
+        }
+    }
+
+    fn start_thinking(&mut self) {
+        if self.is_game_over() || self.is_thinking() {
+            return;
+        }
+        let player = self.get_current_player_type();
+        if player == Player::Human {
+            return;
+        }
+
+        let game = self.game.clone();
+        let mcts_bot = Arc::clone(&self.mcts_bot);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            if player == Player::RandomBot {
+                return RandomBot.choose_move(&game, &thread_stop);
+            }
+            mcts_bot.lock().unwrap().choose_move(&game, &thread_stop)
+        });
+        self.pending_move = Some(PendingMove { handle, stop });
+    }
+
+    fn finish_thinking(&mut self, pending: PendingMove<G::Move>) -> Option<G::Move> {
+        let mv = pending.handle.join().expect("Bot thread panicked")?;
+        if self.play_move(mv) { Some(mv) } else { None }
+    }
+}
+
+impl<G: Game + 'static> Drop for BoardGame<G> {
+    fn drop(&mut self) {
+        self.cancel_bot_move();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::gomoku::GomokuBoard;
+    use crate::games::grid::Cell;
+    use crate::games::mnk::MnkBoard;
+
+    #[test]
+    fn test_human_moves() {
+        let mut game = BoardGame::new([Player::Human, Player::Human], MnkBoard::tic_tac_toe());
+        assert!(game.play_move(Cell::new(1, 1)));
+        assert!(!game.play_move(Cell::new(1, 1)));
+        assert!(!game.play_move(Cell::new(3, 0)));
+        for cell in [Cell::new(0, 0), Cell::new(0, 2), Cell::new(1, 0), Cell::new(2, 0)] {
+            assert!(game.play_move(cell));
+        }
+        assert_eq!(game.get_winner(), Some(1));
+        assert!(!game.is_human_turn());
+        assert!(!game.play_move(Cell::new(2, 2)));
+        assert_eq!(game.get_moves().len(), 5);
+    }
+
+    // Gomoku's search only looks near the stones, but players may go anywhere
+    #[test]
+    fn test_moves_the_search_leaves_out() {
+        let mut game = BoardGame::new([Player::Human, Player::Human], GomokuBoard::new());
+        assert!(game.play_move(Cell::new(0, 0)));
+        assert!(!game.get_game().get_valid_moves().contains(&Cell::new(14, 14)));
+        assert!(game.play_move(Cell::new(14, 14)));
+    }
+
+    #[test]
+    fn test_bots_finish_the_game() {
+        let mut game = BoardGame::with_mcts([Player::RandomBot, Player::MctsBot], MnkBoard::tic_tac_toe(), MCTS::new(1.414, 1, 200, 10));
+        while !game.is_game_over() {
+            assert!(game.play_bot_move().is_some());
+        }
+        // MCTS never loses tic-tac-toe to random moves
+        assert_ne!(game.get_winner(), Some(1));
+    }
+
+    #[test]
+    fn test_cancel_thinking_bot() {
+        let mut game = BoardGame::with_mcts([Player::MctsBot, Player::Human], MnkBoard::tic_tac_toe(), MCTS::new(1.414, 1, 60_000, 10));
+        assert_eq!(game.poll_bot_move(), None);
+        assert!(game.is_thinking());
+        game.cancel_bot_move();
+        assert!(!game.is_thinking());
+        assert!(game.get_moves().is_empty());
+    }
+}
@@ -1,46 +1,149 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use rand::Rng;
-use crate::games::connect4::board::Board;
 use crate::games::connect4::bitboard::BitBoard;
-use crate::mcts::mcts::MCTS;
+use crate::games::connect4::popout::{Move, PopOutBoard};
+use crate::games::connect4::rules::Rules;
+use crate::games::connect4::solver::Solver;
+use crate::games::Game;
+use crate::mcts::mcts::{SearchStats, MCTS};
+use crate::mcts::SearchSession;
+use crate::player::Player;
 
-pub trait Bot {
-    fn make_move(&self, board: &Board, player_number: u8) -> Option<usize>;
+mod search;
+
+pub use search::SearchOptions;
+
+// A player of a `Game`. Bots are `Send` so that frontends can let them think on a background thread.
+pub trait Bot<G: Game>: Send {
+    // Picks a move for the current player of `game`, or gives up and returns None soon after `stop` is set.
+    // Bots that always answer quickly may ignore `stop`.
+    fn choose_move(&mut self, game: &G, stop: &AtomicBool) -> Option<G::Move>;
+
+    // Like `choose_move`, but never gives up
+    fn make_move(&mut self, game: &G) -> Option<G::Move> {
+        self.choose_move(game, &AtomicBool::new(false))
+    }
+}
+
+impl<G: Game, B: Bot<G> + ?Sized> Bot<G> for Box<B> {
+    fn choose_move(&mut self, game: &G, stop: &AtomicBool) -> Option<G::Move> {
+        (**self).choose_move(game, stop)
+    }
 }
 
 pub struct RandomBot;
 
-impl Bot for RandomBot {
-    fn make_move(&self, board: &Board, _player_number: u8) -> Option<usize> {
-        let mut valid_cols = Vec::new();
-        for col in 0..crate::config::GRID_COLS {
-            if board.can_play_column(col) {
-                valid_cols.push(col);
-            }
-        }
+impl<G: Game> Bot<G> for RandomBot {
+    fn choose_move(&mut self, game: &G, _stop: &AtomicBool) -> Option<G::Move> {
+        let moves = game.get_valid_moves();
+        (!moves.is_empty()).then(|| moves[rand::thread_rng().gen_range(0..moves.len())])
+    }
+}
 
-        if valid_cols.is_empty() {
-            None
-        } else {
-            Some(valid_cols[rand::thread_rng().gen_range(0..valid_cols.len())])
+// Statistics of the search a bot is running, or None while it is not searching
+pub type SearchProgress<M = usize> = Arc<Mutex<Option<SearchStats<M>>>>;
+
+pub struct MctsBot<G: Game> {
+    mcts: MCTS<G>,
+    session: SearchSession<G>,  // Search tree kept between turns
+    progress: SearchProgress<G::Move>,
+}
+
+impl<G: Game> MctsBot<G> {
+    pub fn new(simulation_time_ms: u64) -> Self {
+        Self::with_mcts(MCTS::new(1.414, 4, simulation_time_ms, 10)) // Example parameters
+    }
+
+    // A bot searching with an already configured `MCTS`
+    pub fn with_mcts(mcts: MCTS<G>) -> Self {
+        Self {
+            mcts,
+            session: SearchSession::new(),
+            progress: Arc::new(Mutex::new(None)),
         }
     }
+
+    // Shared view of the running search, updated while the bot thinks on another thread
+    pub fn get_progress(&self) -> SearchProgress<G::Move> {
+        Arc::clone(&self.progress)
+    }
 }
 
-pub struct MctsBot {
-    mcts: MCTS,
+impl<G: Game> Bot<G> for MctsBot<G> {
+    fn choose_move(&mut self, game: &G, stop: &AtomicBool) -> Option<G::Move> {
+        let stats = self.mcts.analyze_with_progress(&mut self.session, game.clone(), stop, |stats| {
+            *self.progress.lock().unwrap() = Some(stats.clone());
+        });
+        *self.progress.lock().unwrap() = None;
+        if stop.load(Ordering::Relaxed) { None } else { Some(stats.best_move) }
+    }
 }
 
-impl MctsBot {
-    pub fn new(simulation_time_ms: u64) -> Self {
+// Plays perfectly by solving the position exactly every turn.
+// Positions with only a few discs can take a long time to solve.
+// On boards the solver does not handle it searches with MCTS instead.
+pub struct PerfectBot {
+    solver: Solver,                       // Transposition table kept between turns
+    search: SearchOptions,                // How the fallback searches
+    fallback: Option<MctsBot<BitBoard>>,  // Created on the first unsupported board
+}
+
+impl PerfectBot {
+    pub fn new() -> Self {
+        Self::with_search(SearchOptions::default())
+    }
+
+    // A bot that searches as `search` says on boards the solver does not handle
+    pub fn with_search(search: SearchOptions) -> Self {
         Self {
-            mcts: MCTS::new(1.414, 4, simulation_time_ms, 10), // Example parameters
+            solver: Solver::new(),
+            search,
+            fallback: None,
+        }
+    }
+}
+
+impl Default for PerfectBot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bot<BitBoard> for PerfectBot {
+    fn choose_move(&mut self, board: &BitBoard, stop: &AtomicBool) -> Option<usize> {
+        if !Solver::supports(&board.get_rules()) {
+            let search = &self.search;
+            return self.fallback.get_or_insert_with(|| MctsBot::with_mcts(search.build())).choose_move(board, stop);
+        }
+        if *board == BitBoard::new() {
+            return Some(board.get_rules().columns / 2);  // The center of the standard board is the known winning opening
         }
+        self.solver.best_move_until(board, stop)
     }
 }
 
-impl Bot for MctsBot {
-    fn make_move(&self, board: &Board, player_number: u8) -> Option<usize> {
-        let bitboard = BitBoard::from_board(board, player_number);
-        Some(self.mcts.search(bitboard, player_number))
+// Plays a classic game kept as a `PopOutBoard`, the way frontends keep every connect four game,
+// with a bot for `BitBoard`. It only ever drops discs, so the rules must not allow pops.
+pub struct ClassicBot<B>(pub B);
+
+impl<B: Bot<BitBoard>> Bot<PopOutBoard> for ClassicBot<B> {
+    fn choose_move(&mut self, game: &PopOutBoard, stop: &AtomicBool) -> Option<Move> {
+        debug_assert!(!game.get_position().get_rules().popout, "a classic bot cannot play PopOut");
+        self.0.choose_move(game.get_position(), stop).map(Move::Drop)
     }
-}
\ No newline at end of file
+}
+
+// The bot playing as `player` in connect four games under `rules`, or None for a human.
+// Classic games get the bots for `BitBoard`, which can solve positions and use tactical playouts.
+// In PopOut, which the solver does not know, the perfect bot searches like the MCTS bot.
+pub fn connect_four_bot(player: Player, rules: &Rules, search: &SearchOptions) -> Option<Box<dyn Bot<PopOutBoard>>> {
+    let bot: Box<dyn Bot<PopOutBoard>> = match (player, rules.popout) {
+        (Player::Human, _) => return None,
+        (Player::RandomBot, _) => Box::new(RandomBot),
+        (Player::MctsBot, false) => Box::new(ClassicBot(MctsBot::with_mcts(search.build()))),
+        (Player::MctsBot | Player::PerfectBot, true) => Box::new(MctsBot::with_mcts(search.build_popout())),
+        (Player::PerfectBot, false) => Box::new(ClassicBot(PerfectBot::with_search(search.clone()))),
+    };
+    Some(bot)
+}
@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use crate::games::connect4::bitboard::BitBoard;
+use crate::games::connect4::playout::TacticalPlayout;
+use crate::games::connect4::popout::PopOutBoard;
+use crate::games::Game;
+use crate::mcts::mcts::{ParallelMode, MCTS};
+use crate::mcts::MoveSelection;
+
+// How an MCTS bot searches. Saved games and the command line name the options like the
+// search flags: time, threads, exploration, simulations, mode, selection, playout and rave.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchOptions {
+    time_ms: u64,
+    threads: usize,
+    exploration: f32,
+    simulations: u32,
+    mode: ParallelMode,
+    selection: MoveSelection,
+    tactical_playouts: bool,
+    rave: Option<f32>,
+}
+
+impl SearchOptions {
+    pub const NAMES: [&'static str; 8] = ["time", "threads", "exploration", "simulations", "mode", "selection", "playout", "rave"];
+
+    // Options from settings such as those of a saved game. Missing ones keep their default.
+    pub fn from_settings(settings: &BTreeMap<String, String>) -> Result<Self, String> {
+        if let Some(name) = settings.keys().find(|name| !Self::NAMES.contains(&name.as_str())) {
+            return Err(format!("unknown search setting '{}'", name));
+        }
+        let text = |name: &str, default: &'static str| settings.get(name).map_or(default, String::as_str);
+
+        let mode = match text("mode", "root") {
+            "root" => ParallelMode::RootParallel,
+            "shared" => ParallelMode::SharedTree,
+            other => return Err(format!("unknown mode '{}', expected root or shared", other)),
+        };
+        let selection = match text("selection", "visits") {
+            "visits" => MoveSelection::MostVisits,
+            "value" => MoveSelection::HighestValue,
+            "secure" => MoveSelection::SecureValue,
+            other => return Err(format!("unknown selection '{}', expected visits, value or secure", other)),
+        };
+        let tactical_playouts = match text("playout", "random") {
+            "random" => false,
+            "tactical" => true,
+            other => return Err(format!("unknown playout '{}', expected random or tactical", other)),
+        };
+        let rave = number(settings, "rave", 0.0f32)?;
+
+        Ok(Self {
+            time_ms: positive(settings, "time", 1000)?,
+            threads: positive(settings, "threads", 4)?,
+            exploration: number(settings, "exploration", 1.414)?,
+            simulations: positive(settings, "simulations", 10)?,
+            mode,
+            selection,
+            tactical_playouts,
+            rave: if rave > 0.0 { Some(rave) } else { None },
+        })
+    }
+
+    // Every option by name, for saving them with a game
+    pub fn to_settings(&self) -> BTreeMap<String, String> {
+        let mode = match self.mode {
+            ParallelMode::RootParallel => "root",
+            ParallelMode::SharedTree => "shared",
+        };
+        let selection = match self.selection {
+            MoveSelection::MostVisits => "visits",
+            MoveSelection::HighestValue => "value",
+            MoveSelection::SecureValue => "secure",
+        };
+        let settings = [
+            ("time", self.time_ms.to_string()),
+            ("threads", self.threads.to_string()),
+            ("exploration", self.exploration.to_string()),
+            ("simulations", self.simulations.to_string()),
+            ("mode", mode.to_string()),
+            ("selection", selection.to_string()),
+            ("playout", if self.tactical_playouts { "tactical" } else { "random" }.to_string()),
+            ("rave", self.rave.unwrap_or(0.0).to_string()),
+        ];
+        settings.into_iter().map(|(name, value)| (name.to_string(), value)).collect()
+    }
+
+    pub fn build(&self) -> MCTS<BitBoard> {
+        let mcts = self.configure(MCTS::new(self.exploration, self.threads, self.time_ms, self.simulations));
+        if self.tactical_playouts { mcts.with_playout_policy(TacticalPlayout) } else { mcts }
+    }
+
+    // The search for PopOut games, whose playouts are always random
+    pub fn build_popout(&self) -> MCTS<PopOutBoard> {
+        self.configure(MCTS::new(self.exploration, self.threads, self.time_ms, self.simulations))
+    }
+
+    fn configure<G: Game>(&self, mcts: MCTS<G>) -> MCTS<G> {
+        let mcts = mcts.with_parallel_mode(self.mode).with_move_selection(self.selection);
+        match self.rave {
+            Some(equivalence) => mcts.with_rave(equivalence),
+            None => mcts,
+        }
+    }
+}
+
+// The parameters of `MctsBot::new` with a thinking time of one second
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self::from_settings(&BTreeMap::new()).unwrap()
+    }
+}
+
+fn number<T: FromStr>(settings: &BTreeMap<String, String>, name: &str, default: T) -> Result<T, String> {
+    match settings.get(name) {
+        Some(value) => value.parse().map_err(|_| format!("invalid value '{}' for {}", value, name)),
+        None => Ok(default),
+    }
+}
+
+// Like `number`, but refuses 0, with which the search would do nothing and still name a move
+fn positive<T: FromStr + Default + PartialEq>(settings: &BTreeMap<String, String>, name: &str, default: T) -> Result<T, String> {
+    let value = number(settings, name, default)?;
+    if value == T::default() {
+        return Err(format!("{} must be at least 1", name));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|&(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let search = SearchOptions::from_settings(&settings(&[("time", "50"), ("mode", "shared"), ("rave", "300")])).unwrap();
+        assert_eq!(search.to_settings()["time"], "50");
+        assert_eq!(search.to_settings()["threads"], "4");
+        assert_eq!(SearchOptions::from_settings(&search.to_settings()), Ok(search));
+        assert_eq!(SearchOptions::default().to_settings()["time"], "1000");
+    }
+
+    #[test]
+    fn test_bad_settings_are_reported() {
+        assert!(SearchOptions::from_settings(&settings(&[("time", "soon")])).is_err());
+        assert!(SearchOptions::from_settings(&settings(&[("mode", "sideways")])).is_err());
+        assert!(SearchOptions::from_settings(&settings(&[("depth", "3")])).is_err());
+        for name in ["time", "threads", "simulations"] {
+            assert_eq!(SearchOptions::from_settings(&settings(&[(name, "0")])), Err(format!("{} must be at least 1", name)));
+        }
+    }
+}
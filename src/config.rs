@@ -1,7 +1,13 @@
 pub const CELL_SIZE: f32 = 300.0;
 pub const GRID_COLS: usize = 7;
 pub const GRID_ROWS: usize = 6;
+pub const BOARD_SIZES: [(usize, usize); 4] = [(7, 6), (6, 5), (8, 7), (9, 7)];  // (columns, rows) offered by the menus
+pub const CONNECT_LENGTHS: [usize; 3] = [4, 3, 5];                           // Standard first
+pub const VARIANTS: [(&str, bool); 2] = [("Classic", false), ("PopOut", true)];  // Names and whether discs can be popped
 pub const SCREEN_WIDTH: f32 = CELL_SIZE * GRID_COLS as f32;
 pub const SCREEN_HEIGHT: f32 = CELL_SIZE * GRID_ROWS as f32;
+pub const PANEL_WIDTH: f32 = 460.0;  // Move history panel to the right of the board
+pub const WINDOW_WIDTH: f32 = SCREEN_WIDTH + PANEL_WIDTH;
 pub const BUTTON_WIDTH: f32 = 200.0;
-pub const BUTTON_HEIGHT: f32 = 50.0;
\ No newline at end of file
+pub const BUTTON_HEIGHT: f32 = 50.0;
+pub const SAVE_DIR: &str = "saves";  // Where the window keeps saved games, one file per game
\ No newline at end of file
@@ -0,0 +1,664 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use crate::bot::{connect_four_bot, Bot, ClassicBot, MctsBot, RandomBot, SearchOptions, SearchProgress};
+use crate::games::connect4::bitboard::BitBoard;
+use crate::games::connect4::board::Board;
+use crate::games::connect4::popout::{Move, PopOutBoard};
+use crate::games::connect4::rules::Rules;
+use crate::mcts::mcts::SearchStats;
+use crate::player::Player;
+use crate::saved_game::{unix_time, SavedGame, SavedPlayer};
+
+// Rules and turn order of a game between two players, shared by every frontend.
+// Frontends only draw the state and forward human moves; bots move through `poll_bot_move`,
+// which lets them think on a background thread, or the blocking `play_bot_move`.
+pub struct GameController {
+    board: Board,
+    game: PopOutBoard,          // The position with the history that PopOut's repetition draws need
+    current_player_number: u8,  // 1 or 2
+    player_types: [Player; 2],  // Stores if each player is Human or a type of Bot
+    game_over: bool,
+    winner: Option<u8>,  // 1, 2, or None for draw
+    start_position: BitBoard,  // The board before the first move
+    moves: Vec<Move>,       // Moves played so far
+    redo_moves: Vec<Move>,  // Undone moves, the next one to redo last
+    started: u64,            // Unix time the game started
+    random_bot: Arc<Mutex<RandomBot>>,
+    searches: [SearchOptions; 2],               // How each player's bot searches, see `Player::searches`
+    mcts_bots: [Option<SearchingBot>; 2],      // Created when first needed, with the player's search
+    perfect_bots: [Option<Arc<Mutex<dyn Bot<PopOutBoard>>>>; 2],  // Created when first needed, their tables are large
+    pending_move: Option<PendingMove>,
+}
+
+// What the bot that is thinking has found so far
+#[derive(Clone, Debug)]
+pub struct BotAnalysis {
+    pub player_number: u8,  // The player the bot moves for
+    pub stats: SearchStats<usize>,
+}
+
+// An MCTS bot with the view of its search, which can be read while the bot thinks
+struct SearchingBot {
+    bot: Arc<Mutex<dyn Bot<PopOutBoard>>>,
+    progress: Option<SearchProgress>,  // None in PopOut, whose moves are not all columns to drop into
+}
+
+// A bot move being computed on a background thread
+struct PendingMove {
+    handle: JoinHandle<Option<Move>>,
+    stop: Arc<AtomicBool>,  // Set to make the bot give up
+}
+
+impl GameController {
+    pub fn new() -> Self {
+        Self {
+            board: Board::new(),
+            game: PopOutBoard::new(BitBoard::new()),
+            current_player_number: 1,
+            player_types: [Player::Human, Player::Human],
+            game_over: false,
+            winner: None,
+            start_position: BitBoard::new(),
+            moves: Vec::new(),
+            redo_moves: Vec::new(),
+            started: unix_time(),
+            random_bot: Arc::new(Mutex::new(RandomBot)),
+            searches: [SearchOptions::default(), SearchOptions::default()],
+            mcts_bots: [None, None],
+            perfect_bots: [None, None],
+            pending_move: None,
+        }
+    }
+
+    // Clears the board for a new game between `player_types`, cancelling any bot that is still thinking
+    pub fn start(&mut self, player_types: [Player; 2]) {
+        self.start_from(player_types, &BitBoard::new());
+    }
+
+    // Starts a game from a set-up position, which must not be over yet, on the board of its rules
+    pub fn start_from(&mut self, player_types: [Player; 2], position: &BitBoard) {
+        self.cancel_bot_move();
+        if position.get_rules().popout != self.get_rules().popout {
+            // Classic and PopOut games have bots of their own
+            self.mcts_bots = [None, None];
+            self.perfect_bots = [None, None];
+        }
+        self.player_types = player_types;
+        self.board = position.to_board();
+        self.game = PopOutBoard::new(*position);
+        self.current_player_number = position.get_current_player();
+        self.start_position = *position;
+        self.game_over = false;
+        self.winner = None;
+        self.moves.clear();
+        self.redo_moves.clear();
+        self.started = unix_time();
+    }
+
+    // Records the game so far, with the settings of the bots that play it
+    pub fn to_saved_game(&self) -> SavedGame {
+        let players = [0, 1].map(|index| {
+            let player = self.player_types[index];
+            let settings = if player.searches() { self.searches[index].to_settings() } else { Default::default() };
+            SavedPlayer { player, settings }
+        });
+        SavedGame::from_position(players, &self.start_position, &self.moves, self.started)
+    }
+
+    // Continues a saved game, with the MCTS bots searching as the game's settings say.
+    // Fails without changing anything if those settings are not valid.
+    pub fn load(&mut self, game: &SavedGame) -> Result<(), String> {
+        let mut searches = self.searches.clone();
+        for (search, saved) in searches.iter_mut().zip(&game.players) {
+            if saved.player.searches() {
+                *search = SearchOptions::from_settings(&saved.settings)?;
+            }
+        }
+        self.start_from([game.players[0].player, game.players[1].player], &game.get_start_position());
+        for (player_number, search) in [1, 2].into_iter().zip(searches) {
+            self.set_search(player_number, search);
+        }
+        self.started = game.started;
+        for mv in game.get_moves() {
+            self.apply_move(mv);
+        }
+        Ok(())
+    }
+
+    // How `player_number`'s bot searches when it is the MCTS bot, or the perfect bot away from the solver
+    pub fn get_search(&self, player_number: u8) -> &SearchOptions {
+        &self.searches[(player_number - 1) as usize]
+    }
+
+    // Changes how `player_number` searches from its next move on
+    pub fn set_search(&mut self, player_number: u8, search: SearchOptions) {
+        let index = (player_number - 1) as usize;
+        if self.searches[index] != search {
+            self.searches[index] = search;
+            self.mcts_bots[index] = None;
+            self.perfect_bots[index] = None;
+        }
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    // Board size, connect length and variant of the game
+    pub fn get_rules(&self) -> Rules {
+        self.start_position.get_rules()
+    }
+
+    // The position the game started from
+    pub fn get_start_position(&self) -> &BitBoard {
+        &self.start_position
+    }
+
+    // Moves played in this game, in order
+    pub fn get_moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn get_current_player_number(&self) -> u8 {
+        self.current_player_number
+    }
+
+    pub fn get_current_player_type(&self) -> Player {
+        self.player_types[(self.current_player_number - 1) as usize]
+    }
+
+    pub fn get_player_type(&self, player_number: u8) -> Player {
+        self.player_types[(player_number - 1) as usize]
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game_over
+    }
+
+    pub fn get_winner(&self) -> Option<u8> {
+        self.winner
+    }
+
+    // Whether the game waits for a human to pick a column
+    pub fn is_human_turn(&self) -> bool {
+        !self.game_over && !self.get_current_player_type().is_bot()
+    }
+
+    // Whether either player is a human
+    fn has_human(&self) -> bool {
+        self.player_types.contains(&Player::Human)
+    }
+
+    // Whether a bot is computing its move in the background
+    pub fn is_thinking(&self) -> bool {
+        self.pending_move.is_some()
+    }
+
+    // Live search statistics while an MCTS bot is thinking
+    pub fn get_analysis(&self) -> Option<BotAnalysis> {
+        if !self.is_thinking() || self.get_current_player_type() != Player::MctsBot {
+            return None;
+        }
+        let mcts_bot = self.mcts_bots[(self.current_player_number - 1) as usize].as_ref()?;
+        let stats = mcts_bot.progress.as_ref()?.lock().unwrap().clone()?;
+        Some(BotAnalysis { player_number: self.current_player_number, stats })
+    }
+
+    // Drops a disc for the current player. Returns false if the column cannot be played.
+    pub fn play_column(&mut self, col: usize) -> bool {
+        self.play_move(Move::Drop(col))
+    }
+
+    // Pops the current player's disc off the bottom of `col` in PopOut. Returns false if that is not allowed.
+    pub fn pop_column(&mut self, col: usize) -> bool {
+        self.play_move(Move::Pop(col))
+    }
+
+    // Plays a move for the current player. Returns false if it is not valid.
+    pub fn play_move(&mut self, mv: Move) -> bool {
+        if self.is_thinking() || !self.apply_move(mv) {
+            return false;
+        }
+        self.redo_moves.clear();
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.moves.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_moves.is_empty()
+    }
+
+    // Takes back moves until a human is to move again, so that against a bot the human's last move
+    // is undone together with the bot's answer. Between two bots it takes back a single move.
+    // Cancels a thinking bot. Returns false if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        self.cancel_bot_move();
+        if !self.take_back() {
+            return false;
+        }
+        while self.has_human() && !self.is_human_turn() && self.take_back() {}
+        true
+    }
+
+    // Replays undone moves until a human is to move again, or a single move between two bots.
+    // Returns false if there was nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        self.cancel_bot_move();
+        let Some(mv) = self.redo_moves.pop() else {
+            return false;
+        };
+        self.apply_move(mv);
+        while self.has_human() && !self.game_over && !self.is_human_turn() {
+            let Some(mv) = self.redo_moves.pop() else {
+                break;
+            };
+            self.apply_move(mv);
+        }
+        true
+    }
+
+    // Plays `mv` for the current player and records it
+    fn apply_move(&mut self, mv: Move) -> bool {
+        if self.game_over || !self.game.is_valid_move(mv) {
+            return false;
+        }
+        let result = self.game.make_move(mv);
+        self.board = self.game.get_position().to_board();
+        self.moves.push(mv);
+
+        match result {
+            // A pop can complete a line of the opponent, so the result names the winner.
+            // The player who ended the game stays the current one.
+            Some(result) => {
+                self.game_over = true;
+                self.winner = match result {
+                    1 => Some(1),
+                    -1 => Some(2),
+                    _ => None,
+                };
+            }
+            None => self.current_player_number = self.game.get_current_player(),
+        }
+        true
+    }
+
+    // Undoes the last move and keeps it for `redo`. Pops cannot be taken back on the board, so the
+    // game is played again from the start without the move.
+    fn take_back(&mut self) -> bool {
+        let Some(mv) = self.moves.pop() else {
+            return false;
+        };
+        self.redo_moves.push(mv);
+        self.game = PopOutBoard::new(self.start_position);
+        for &mv in &self.moves {
+            self.game.make_move(mv);
+        }
+        self.board = self.game.get_position().to_board();
+        self.current_player_number = self.game.get_current_player();
+        self.game_over = false;
+        self.winner = None;
+        true
+    }
+
+    // Lets the current player move if it is a bot, blocking while it thinks. Returns the move played.
+    pub fn play_bot_move(&mut self) -> Option<Move> {
+        self.start_thinking();
+        let pending = self.pending_move.take()?;
+        self.finish_thinking(pending)
+    }
+
+    // Starts the current bot thinking in the background if it is not already, and plays its move
+    // once it is ready. Never blocks, so frontends can call it every frame. Returns the move played.
+    pub fn poll_bot_move(&mut self) -> Option<Move> {
+        self.start_thinking();
+        if !self.pending_move.as_ref()?.handle.is_finished() {
+            return None;
+        }
+        let pending = self.pending_move.take()?;
+        self.finish_thinking(pending)
+    }
+
+    // Stops a bot that is thinking in the background and waits for its thread to end
+    pub fn cancel_bot_move(&mut self) {
+        if let Some(pending) = self.pending_move.take() {
+            pending.stop.store(true, Ordering::Relaxed);
+            let _ = pending.handle.join();
+        }
+    }
+
+    fn start_thinking(&mut self) {
+        if self.game_over || self.is_thinking() {
+            return;
+        }
+        let rules = self.get_rules();
+        let index = (self.current_player_number - 1) as usize;
+        let search = &self.searches[index];
+        let bot: Arc<Mutex<dyn Bot<PopOutBoard>>> = match self.get_current_player_type() {
+            Player::RandomBot => self.random_bot.clone(),
+            Player::MctsBot => {
+                let mcts_bot = self.mcts_bots[index].get_or_insert_with(|| {
+                    if rules.popout {
+                        SearchingBot { bot: Arc::new(Mutex::new(MctsBot::with_mcts(search.build_popout()))), progress: None }
+                    } else {
+                        let bot = MctsBot::with_mcts(search.build());
+                        SearchingBot { progress: Some(bot.get_progress()), bot: Arc::new(Mutex::new(ClassicBot(bot))) }
+                    }
+                });
+                mcts_bot.bot.clone()
+            }
+            Player::PerfectBot => self.perfect_bots[index].get_or_insert_with(|| {
+                Arc::new(Mutex::new(connect_four_bot(Player::PerfectBot, &rules, search).unwrap()))
+            }).clone(),
+            Player::Human => return,
+        };
+
+        let game = self.game.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            bot.lock().unwrap().choose_move(&game, &thread_stop)
+        });
+        self.pending_move = Some(PendingMove { handle, stop });
+    }
+
+    fn finish_thinking(&mut self, pending: PendingMove) -> Option<Move> {
+        let mv = pending.handle.join().expect("Bot thread panicked")?;
+        if self.play_move(mv) { Some(mv) } else { None }
+    }
+}
+
+impl Drop for GameController {
+    fn drop(&mut self) {
+        self.cancel_bot_move();
+    }
+}
+
+impl Default for GameController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use super::*;
+    use crate::games::connect4::notation::{parse_grid, parse_grid_with};
+
+    #[test]
+    fn test_turns_and_win() {
+        let mut controller = GameController::new();
+        controller.start([Player::Human, Player::Human]);
+        for col in [0, 1, 0, 1, 0, 1] {
+            assert!(controller.is_human_turn());
+            assert!(controller.play_column(col));
+        }
+        assert_eq!(controller.get_current_player_number(), 1);
+        assert!(controller.play_column(0));
+        assert!(controller.is_game_over());
+        assert_eq!(controller.get_winner(), Some(1));
+        assert!(!controller.play_column(2));
+    }
+
+    #[test]
+    fn test_bot_moves_only_on_its_turn() {
+        let mut controller = GameController::new();
+        controller.start([Player::Human, Player::RandomBot]);
+        assert_eq!(controller.play_bot_move(), None);
+        assert!(controller.play_column(3));
+        assert!(!controller.is_human_turn());
+        assert!(controller.play_bot_move().is_some());
+        assert_eq!(controller.get_current_player_number(), 1);
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let mut controller = GameController::new();
+        controller.start([Player::Human, Player::Human]);
+        for col in [0, 1, 0, 1, 0, 1, 0] {
+            assert!(controller.play_column(col));
+        }
+        assert!(controller.is_game_over());
+
+        assert!(controller.undo());
+        assert!(!controller.is_game_over());
+        assert_eq!(controller.get_current_player_number(), 1);
+        assert_eq!(controller.get_moves(), &[0, 1, 0, 1, 0, 1].map(Move::Drop));
+        assert!(controller.undo());
+        assert_eq!(controller.get_current_player_number(), 2);
+        assert_eq!(controller.get_board().get_player_number(2, 1), 0);
+
+        assert!(controller.redo());
+        assert!(controller.redo());
+        assert!(controller.is_game_over());
+        assert_eq!(controller.get_winner(), Some(1));
+        assert!(!controller.redo());
+
+        // A new move drops the moves that could have been redone
+        assert!(controller.undo());
+        assert!(controller.play_column(3));
+        assert!(!controller.can_redo());
+        assert_eq!(controller.get_moves(), &[0, 1, 0, 1, 0, 1, 3].map(Move::Drop));
+    }
+
+    #[test]
+    fn test_undo_against_bot_returns_to_human_turn() {
+        let mut controller = GameController::new();
+        controller.start([Player::Human, Player::RandomBot]);
+        assert!(!controller.undo());
+        assert!(controller.play_column(3));
+        let reply = controller.play_bot_move().unwrap();
+        assert!(controller.play_column(2));
+
+        // Undoing while the bot has yet to answer takes back only the human's move
+        assert!(controller.undo());
+        assert_eq!(controller.get_moves(), &[Move::Drop(3), reply]);
+        assert!(controller.is_human_turn());
+
+        // Otherwise the bot's answer goes too
+        assert!(controller.undo());
+        assert!(controller.get_moves().is_empty());
+        assert!(controller.is_human_turn());
+
+        assert!(controller.redo());
+        assert_eq!(controller.get_moves(), &[Move::Drop(3), reply]);
+        assert!(controller.is_human_turn());
+        assert!(controller.redo());
+        assert_eq!(controller.get_moves(), &[Move::Drop(3), reply, Move::Drop(2)]);
+        assert!(!controller.is_human_turn());
+    }
+
+    #[test]
+    fn test_undo_between_bots_takes_back_one_move() {
+        let mut controller = GameController::new();
+        controller.start([Player::RandomBot, Player::RandomBot]);
+        for _ in 0..4 {
+            assert!(controller.play_bot_move().is_some());
+        }
+        let moves = controller.get_moves().to_vec();
+
+        assert!(controller.undo());
+        assert_eq!(controller.get_moves(), &moves[..3]);
+        assert_eq!(controller.get_current_player_number(), 2);
+        assert!(controller.undo());
+        assert_eq!(controller.get_moves(), &moves[..2]);
+
+        assert!(controller.redo());
+        assert_eq!(controller.get_moves(), &moves[..3]);
+        assert!(controller.redo());
+        assert_eq!(controller.get_moves(), &moves[..]);
+        assert!(!controller.redo());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut controller = GameController::new();
+        controller.start([Player::Human, Player::MctsBot]);
+        for col in [3, 3, 4] {
+            controller.apply_move(Move::Drop(col));
+        }
+        let saved = controller.to_saved_game();
+        assert_eq!(saved.moves, "445");
+        assert_eq!(saved.players[1].settings["time"], "1000");
+        assert!(saved.players[0].settings.is_empty());
+
+        let mut loaded = GameController::new();
+        loaded.load(&SavedGame::from_json(&saved.to_json()).unwrap()).unwrap();
+        assert_eq!(loaded.get_moves(), &[3, 3, 4].map(Move::Drop));
+        assert_eq!(loaded.get_player_type(2), Player::MctsBot);
+        assert_eq!(loaded.get_current_player_number(), 2);
+        assert_eq!(loaded.get_board().get_player_number(0, 4), 1);
+        assert_eq!(loaded.to_saved_game().started, saved.started);
+    }
+
+    #[test]
+    fn test_load_applies_saved_bot_settings() {
+        let mut controller = GameController::new();
+        controller.start([Player::MctsBot, Player::Human]);
+        let mut saved = controller.to_saved_game();
+        saved.players[0].settings.insert("time".to_string(), "50".to_string());
+
+        let mut loaded = GameController::new();
+        loaded.load(&saved).unwrap();
+        assert_eq!(loaded.get_search(1).to_settings()["time"], "50");
+        assert_eq!(loaded.get_search(2), &SearchOptions::default());
+        assert_eq!(loaded.to_saved_game().players[0].settings["time"], "50");
+        assert!(loaded.play_bot_move().is_some());
+
+        // Bad settings leave the controller as it was
+        saved.players[0].settings.insert("mode".to_string(), "sideways".to_string());
+        assert!(loaded.load(&saved).is_err());
+        assert_eq!(loaded.get_moves().len(), 1);
+    }
+
+    #[test]
+    fn test_game_from_a_set_up_position() {
+        let position = parse_grid("7/7/7/7/7/3o3 x").unwrap();
+        let mut controller = GameController::new();
+        controller.start_from([Player::Human, Player::Human], &position);
+        assert_eq!(controller.get_current_player_number(), 1);
+        assert!(controller.play_column(3));
+        assert_eq!(controller.get_board().get_player_number(1, 3), 1);
+
+        // Undo stops at the set-up position
+        assert!(controller.undo());
+        assert!(!controller.undo());
+        assert_eq!(controller.get_board().get_player_number(0, 3), 2);
+
+        assert!(controller.play_column(4));
+        let mut loaded = GameController::new();
+        loaded.load(&SavedGame::from_json(&controller.to_saved_game().to_json()).unwrap()).unwrap();
+        assert_eq!(loaded.get_start_position(), &position);
+        assert_eq!(loaded.get_moves(), &[4].map(Move::Drop));
+        assert_eq!(loaded.get_current_player_number(), 2);
+    }
+
+    #[test]
+    fn test_bot_thinks_in_background() {
+        let mut controller = GameController::new();
+        controller.start([Player::RandomBot, Player::Human]);
+        let col = loop {
+            if let Some(mv) = controller.poll_bot_move() {
+                break mv.column();
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert!(!controller.is_thinking());
+        assert_eq!(controller.get_board().get_player_number(0, col), 1);
+        assert_eq!(controller.poll_bot_move(), None);
+        assert!(!controller.is_thinking());
+    }
+
+    #[test]
+    fn test_analysis_while_mcts_bot_thinks() {
+        let mut controller = GameController::new();
+        controller.start([Player::MctsBot, Player::Human]);
+        assert!(controller.get_analysis().is_none());
+        assert_eq!(controller.poll_bot_move(), None);
+        thread::sleep(Duration::from_millis(400));
+
+        let analysis = controller.get_analysis().unwrap();
+        assert_eq!(analysis.player_number, 1);
+        assert!(analysis.stats.total_visits > 0);
+        assert_eq!(analysis.stats.best_line[0], analysis.stats.best_move);
+        controller.cancel_bot_move();
+        assert!(controller.get_analysis().is_none());
+    }
+
+    #[test]
+    fn test_new_game_cancels_thinking_bot() {
+        let mut controller = GameController::new();
+        controller.start([Player::Human, Player::PerfectBot]);
+        // Solving a position with a single disc takes far longer than this test
+        assert!(controller.play_column(0));
+        assert_eq!(controller.poll_bot_move(), None);
+        assert!(controller.is_thinking());
+        assert!(!controller.play_column(1));
+
+        let start = Instant::now();
+        controller.start([Player::Human, Player::Human]);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(!controller.is_thinking());
+        assert!(controller.play_column(1));
+        assert_eq!(controller.get_board().get_player_number(0, 0), 0);
+    }
+
+    #[test]
+    fn test_popout_game() {
+        let rules = Rules::STANDARD.with_popout(true);
+        let mut controller = GameController::new();
+        controller.start_from([Player::Human, Player::Human], &BitBoard::with_rules(rules));
+        for mv in [Move::Drop(3), Move::Drop(3), Move::Pop(3)] {
+            assert!(controller.play_move(mv));
+        }
+        assert!(!controller.pop_column(4));
+        assert_eq!(controller.get_board().get_player_number(0, 3), 2);
+
+        // Undo takes pops back as well
+        assert!(controller.undo());
+        assert_eq!(controller.get_board().get_player_number(0, 3), 1);
+        assert_eq!(controller.get_current_player_number(), 1);
+        assert!(controller.redo());
+        assert_eq!(controller.get_moves(), &[Move::Drop(3), Move::Drop(3), Move::Pop(3)]);
+
+        // Popping can complete the opponent's line
+        let position = parse_grid_with(rules, "7/7/7/7/oxx4/xooo3 x").unwrap();
+        controller.start_from([Player::Human, Player::Human], &position);
+        assert!(controller.pop_column(0));
+        assert!(controller.is_game_over());
+        assert_eq!(controller.get_winner(), Some(2));
+
+        // Bots choose among drops and pops
+        controller.start_from([Player::RandomBot, Player::RandomBot], &BitBoard::with_rules(rules));
+        while !controller.is_game_over() {
+            assert!(controller.play_bot_move().is_some());
+        }
+    }
+
+    #[test]
+    fn test_bots_follow_the_variant() {
+        let rules = Rules::STANDARD.with_popout(true);
+        let search = SearchOptions::from_settings(&[("time".to_string(), "50".to_string())].into()).unwrap();
+        let mut controller = GameController::new();
+        controller.set_search(1, search);
+        for position in [BitBoard::new(), BitBoard::with_rules(rules), BitBoard::new()] {
+            for player in [Player::MctsBot, Player::PerfectBot] {
+                controller.start_from([player, Player::Human], &position);
+                assert!(controller.play_bot_move().is_some());
+            }
+        }
+
+        // In PopOut the perfect bot searches with the player's settings
+        controller.set_search(2, SearchOptions::from_settings(&[("time".to_string(), "1".to_string())].into()).unwrap());
+        controller.start_from([Player::Human, Player::PerfectBot], &BitBoard::with_rules(rules));
+        assert!(controller.play_column(3));
+        let start = Instant::now();
+        assert!(controller.play_bot_move().is_some());
+        assert!(start.elapsed() < Duration::from_millis(500));
+        assert_eq!(controller.to_saved_game().players[1].settings["time"], "1");
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt;
+use crate::games::connect4::bitboard::BitBoard;
+use crate::games::connect4::board::Board;
+use crate::games::connect4::rules::Rules;
+
+// Setting up positions disc by disc, to start games and analyses from them
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetupError {
+    DiscCounts { player1: usize, player2: usize },  // Counts no game can reach
+    WrongSideToMove { expected: u8 },               // The disc counts put the other player to move
+    ExistingLine { player: u8 },                    // The game would already be over
+    BoardFull,
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::DiscCounts { player1, player2 } =>
+                write!(f, "{} red and {} yellow discs cannot come from alternating moves", player1, player2),
+            SetupError::WrongSideToMove { expected } =>
+                write!(f, "the disc counts put Player {} to move", expected),
+            SetupError::ExistingLine { player } =>
+                write!(f, "Player {} already has a winning line", player),
+            SetupError::BoardFull => write!(f, "the board is full"),
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+pub struct PositionEditor {
+    board: Board,
+    side_to_move: u8,  // 1 or 2
+}
+
+impl PositionEditor {
+    pub fn new() -> Self {
+        Self::with_rules(Rules::STANDARD)
+    }
+
+    // An empty board of `rules`
+    pub fn with_rules(rules: Rules) -> Self {
+        Self { board: Board::with_rules(rules), side_to_move: 1 }
+    }
+
+    pub fn from_position(position: &BitBoard) -> Self {
+        Self { board: position.to_board(), side_to_move: position.get_current_player() }
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_side_to_move(&self) -> u8 {
+        self.side_to_move
+    }
+
+    // With as many discs of each player either one may be to move, since either could have started.
+    // Placing or removing a disc hands the move back to the player with fewer discs. In PopOut, where
+    // pops take discs away, the side picked here stays whatever the counts.
+    pub fn set_side_to_move(&mut self, player_number: u8) {
+        self.side_to_move = player_number;
+    }
+
+    // Puts a disc of `player_number` at (row, col). On an empty cell the disc falls to the lowest
+    // empty cell of the column; a disc already there changes color. Returns where the disc ended up.
+    pub fn place(&mut self, row: usize, col: usize, player_number: u8) -> Option<(usize, usize)> {
+        let rules = self.board.get_rules();
+        if row >= rules.rows || col >= rules.columns {
+            return None;
+        }
+        let placed = if self.board.cells[row][col] != 0 {
+            self.board.cells[row][col] = player_number;
+            Some((row, col))
+        } else {
+            self.board.make_move(col, player_number)
+        };
+        self.follow_disc_counts();
+        placed
+    }
+
+    // Removes the top disc of `col`. Returns where it was.
+    pub fn remove(&mut self, col: usize) -> Option<(usize, usize)> {
+        let removed = self.board.unmake_move(col);
+        self.follow_disc_counts();
+        removed
+    }
+
+    pub fn clear(&mut self) {
+        self.board = Board::with_rules(self.board.get_rules());
+        self.side_to_move = 1;
+    }
+
+    // The position to play from, if a game could have reached it and is not over yet.
+    // The disc counts are only checked without PopOut, where any counts can come up.
+    pub fn validate(&self) -> Result<BitBoard, SetupError> {
+        let rules = self.board.get_rules();
+        if !rules.popout {
+            let [player1, player2] = self.disc_counts();
+            let expected = match player1 as isize - player2 as isize {
+                0 => None,
+                1 => Some(2),
+                -1 => Some(1),
+                _ => return Err(SetupError::DiscCounts { player1, player2 }),
+            };
+            if let Some(expected) = expected.filter(|&expected| expected != self.side_to_move) {
+                return Err(SetupError::WrongSideToMove { expected });
+            }
+        }
+        for row in 0..rules.rows {
+            for col in 0..rules.columns {
+                if self.board.check_win(row, col) {
+                    return Err(SetupError::ExistingLine { player: self.board.cells[row][col] });
+                }
+            }
+        }
+        // In PopOut a full board still has moves while the side to move owns a bottom disc
+        let position = BitBoard::from_board(&self.board, self.side_to_move);
+        if self.board.is_full() && position.get_valid_pops().is_empty() {
+            return Err(SetupError::BoardFull);
+        }
+        Ok(position)
+    }
+
+    fn disc_counts(&self) -> [usize; 2] {
+        let mut counts = [0; 2];
+        for &cell in self.board.cells.iter().flatten() {
+            if cell != 0 {
+                counts[cell as usize - 1] += 1;
+            }
+        }
+        counts
+    }
+
+    // Gives the move to the player with fewer discs, or to Player 1 when both have as many.
+    // PopOut keeps the side picked.
+    fn follow_disc_counts(&mut self) {
+        if self.board.get_rules().popout {
+            return;
+        }
+        let [player1, player2] = self.disc_counts();
+        self.side_to_move = if player1 > player2 { 2 } else { 1 };
+    }
+}
+
+impl Default for PositionEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::connect4::notation::{format_grid, parse_moves, play_moves};
+
+    #[test]
+    fn test_discs_fall_and_change_color() {
+        let mut editor = PositionEditor::new();
+        assert_eq!(editor.place(4, 3, 1), Some((0, 3)));
+        assert_eq!(editor.get_side_to_move(), 2);
+        assert_eq!(editor.place(5, 3, 2), Some((1, 3)));
+        assert_eq!(editor.get_side_to_move(), 1);
+        assert_eq!(editor.place(0, 3, 2), Some((0, 3)));
+        assert_eq!(editor.validate(), Err(SetupError::DiscCounts { player1: 0, player2: 2 }));
+
+        assert_eq!(editor.remove(3), Some((1, 3)));
+        assert_eq!(editor.get_side_to_move(), 1);
+        assert_eq!(format_grid(&editor.validate().unwrap()), "7/7/7/7/7/3o3 x");
+        editor.set_side_to_move(2);
+        assert_eq!(editor.validate(), Err(SetupError::WrongSideToMove { expected: 1 }));
+        assert_eq!(editor.remove(3), Some((0, 3)));
+        assert_eq!(editor.remove(3), None);
+    }
+
+    #[test]
+    fn test_validation() {
+        let position = parse_moves("4453").unwrap();
+        let mut editor = PositionEditor::from_position(&position);
+        assert_eq!(editor.validate(), Ok(position));
+
+        // Equal counts let either side move
+        editor.set_side_to_move(2);
+        assert_eq!(editor.validate().unwrap().get_current_player(), 2);
+
+        editor.clear();
+        for col in [0, 1, 0, 1, 0, 1, 0] {
+            editor.place(Rules::STANDARD.rows - 1, col, if col == 0 { 1 } else { 2 });
+        }
+        assert_eq!(editor.validate(), Err(SetupError::ExistingLine { player: 1 }));
+
+        // Three in a row is a line in connect 3, and clearing keeps the board size
+        let mut editor = PositionEditor::with_rules(Rules::new(6, 5, 3).unwrap());
+        for col in [0, 5, 1, 5, 2] {
+            editor.place(4, col, if col == 5 { 2 } else { 1 });
+        }
+        assert_eq!(editor.validate(), Err(SetupError::ExistingLine { player: 1 }));
+        assert_eq!(editor.place(0, 6, 1), None);
+        editor.clear();
+        assert_eq!(editor.validate(), Ok(BitBoard::with_rules(Rules::new(6, 5, 3).unwrap())));
+    }
+
+    // Drop, drop and pop in column 4 leaves one yellow disc with Player 2 to move
+    #[test]
+    fn test_popout_positions_keep_the_side_picked() {
+        let rules = Rules::STANDARD.with_popout(true);
+        let mut editor = PositionEditor::with_rules(rules);
+        editor.set_side_to_move(2);
+        assert_eq!(editor.place(0, 3, 2), Some((0, 3)));
+        assert_eq!(editor.get_side_to_move(), 2);
+        assert_eq!(editor.validate(), Ok(play_moves(BitBoard::with_rules(rules), "44p4").unwrap()));
+
+        // Any counts can come up
+        editor.place(0, 4, 2);
+        editor.set_side_to_move(1);
+        assert_eq!(editor.validate().unwrap().get_current_player(), 1);
+    }
+}
@@ -1,28 +1,75 @@
+use std::fs;
+use std::path::{Path, PathBuf};
 use ggez::{Context, GameResult};
 use ggez::event::EventHandler;
 use ggez::graphics::{Canvas, Color, Text, TextFragment, Drawable, DrawParam};
+use ggez::input::keyboard::{KeyCode, KeyInput};
 use ggez::input::mouse::MouseButton;
 use ggez::mint::Point2;
 
-use crate::games::connect4::board::Board;
-use crate::player::Player;
+use connect_four::board_game::BoardGame;
+use connect_four::controller::GameController;
+use connect_four::games::connect4::bitboard::BitBoard;
+use connect_four::games::connect4::popout::Move;
+use connect_four::games::connect4::rules::Rules;
+use connect_four::games::gomoku::GomokuBoard;
+use connect_four::games::mnk::MnkBoard;
+use connect_four::games::othello::OthelloBoard;
+use connect_four::player::Player;
+use connect_four::replay::Replay;
+use connect_four::saved_game::SavedGame;
 use crate::ui::{button::Button, screen::GameScreen, drawing, dropdown::Dropdown};
-use crate::config::{SCREEN_WIDTH, SCREEN_HEIGHT, BUTTON_WIDTH, BUTTON_HEIGHT, CELL_SIZE};
-use crate::bot::{Bot, RandomBot, MctsBot};
+use crate::ui::editor_view::{EditorAction, EditorView};
+use crate::ui::replay_view::{ReplayAction, ReplayView};
+use crate::ui::board_game_view::{BoardGameAction, BoardGameScreen, BoardGameView};
+use crate::ui::gomoku_renderer::GomokuRenderer;
+use crate::ui::mnk_renderer::MnkRenderer;
+use crate::ui::othello_renderer::OthelloRenderer;
+use connect_four::config::{SCREEN_WIDTH, SCREEN_HEIGHT, BUTTON_WIDTH, BUTTON_HEIGHT, SAVE_DIR, BOARD_SIZES, CONNECT_LENGTHS, VARIANTS};
+
+// Top of the move list in the side panel, below the undo, redo and save buttons
+const MOVE_LIST_TOP: f32 = 60.0 + 2.0 * BUTTON_HEIGHT;
+
+// Games the menu offers. Connect four has every screen; the others are played on a `BoardGameView`.
+#[derive(Clone, Copy, PartialEq)]
+enum GameKind {
+    ConnectFour,
+    TicTacToe,
+    Mnk,  // On the board size and with the connect length picked in the menu
+    Gomoku { exact_five: bool },
+    Othello,
+}
+
+const GAMES: [(&str, GameKind); 6] = [
+    ("Connect Four", GameKind::ConnectFour),
+    ("Tic-tac-toe", GameKind::TicTacToe),
+    ("m,n,k-game", GameKind::Mnk),
+    ("Gomoku", GameKind::Gomoku { exact_five: false }),
+    ("Gomoku, exact 5", GameKind::Gomoku { exact_five: true }),
+    ("Othello", GameKind::Othello),
+];
 
 pub struct GameState {
-    board: Board,
-    current_player_number: u8,  // 1 or 2
-    player_types: [Player; 2],  // Stores if each player is Human or a type of Bot
-    game_over: bool,
+    controller: GameController,
     screen: GameScreen,
     start_button: Button,
     new_game_button: Button,
-    winner: Option<u8>,  // 1, 2, or None for draw
+    undo_button: Button,
+    redo_button: Button,
+    save_button: Button,
+    load_button: Button,
+    replay_button: Button,
+    replay_last_button: Button,
+    replay_view: Option<ReplayView>,  // Set while the replay screen is shown
+    setup_button: Button,
+    editor_view: Option<EditorView>,  // Set while the setup screen or an analysis from it is shown
+    board_game_view: Option<Box<dyn BoardGameScreen>>,  // Set while a game besides connect four is shown
+    game_dropdown: Dropdown<GameKind>,
     player1_dropdown: Dropdown<Player>,
     player2_dropdown: Dropdown<Player>,
-    random_bot: RandomBot,
-    mcts_bot: MctsBot,
+    size_dropdown: Dropdown<(usize, usize)>,
+    connect_dropdown: Dropdown<usize>,
+    variant_dropdown: Dropdown<bool>,  // Whether discs can be popped
 }
 
 impl GameState {
@@ -43,119 +90,330 @@ impl GameState {
             "New Game"
         );
 
-        let player_options = vec![
-            ("Human".to_string(), Player::Human),
-            ("Random Bot".to_string(), Player::RandomBot),
-            ("MCTS Bot".to_string(), Player::MctsBot),
-        ];
+        // Undo and redo side by side at the top of the move history panel
+        let undo_button = Button::new(SCREEN_WIDTH + 20.0, 20.0, BUTTON_WIDTH, BUTTON_HEIGHT, "Undo");
+        let redo_button = Button::new(SCREEN_WIDTH + 40.0 + BUTTON_WIDTH, 20.0, BUTTON_WIDTH, BUTTON_HEIGHT, "Redo");
+        let save_button = Button::new(SCREEN_WIDTH + 20.0, 40.0 + BUTTON_HEIGHT, BUTTON_WIDTH, BUTTON_HEIGHT, "Save");
 
-        // Position dropdowns side by side
+        // In the menu, where the game over screen has its new game button
+        let load_button = Button::new(
+            SCREEN_WIDTH / 2.0 - BUTTON_WIDTH / 2.0,
+            SCREEN_HEIGHT / 2.0 + BUTTON_HEIGHT + 20.0,
+            BUTTON_WIDTH,
+            BUTTON_HEIGHT,
+            "Load Last Game"
+        );
+
+        // Below the new game button and the load button
+        let replay_button = Button::new(
+            SCREEN_WIDTH / 2.0 - BUTTON_WIDTH / 2.0,
+            SCREEN_HEIGHT / 2.0 + 2.0 * (BUTTON_HEIGHT + 20.0),
+            BUTTON_WIDTH,
+            BUTTON_HEIGHT,
+            "Replay"
+        );
+        let replay_last_button = Button::new(
+            SCREEN_WIDTH / 2.0 - BUTTON_WIDTH / 2.0,
+            SCREEN_HEIGHT / 2.0 + 2.0 * (BUTTON_HEIGHT + 20.0),
+            BUTTON_WIDTH,
+            BUTTON_HEIGHT,
+            "Replay Last Game"
+        );
+        let setup_button = Button::new(
+            SCREEN_WIDTH / 2.0 - BUTTON_WIDTH / 2.0,
+            SCREEN_HEIGHT / 2.0 + 3.0 * (BUTTON_HEIGHT + 20.0),
+            BUTTON_WIDTH,
+            BUTTON_HEIGHT,
+            "Set Up Position"
+        );
+
+        let player_options: Vec<(String, Player)> = Player::ALL.iter()
+            .map(|&player| (player.label().to_string(), player))
+            .collect();
+
+        let size_options: Vec<(String, (usize, usize))> = BOARD_SIZES.iter()
+            .map(|&(columns, rows)| (format!("{}x{}", columns, rows), (columns, rows)))
+            .collect();
+        let connect_options: Vec<(String, usize)> = CONNECT_LENGTHS.iter()
+            .map(|&connect| (format!("Connect {}", connect), connect))
+            .collect();
+        let variant_options: Vec<(String, bool)> = VARIANTS.iter()
+            .map(|&(name, popout)| (name.to_string(), popout))
+            .collect();
+        let game_options: Vec<(String, GameKind)> = GAMES.iter()
+            .map(|&(name, kind)| (name.to_string(), kind))
+            .collect();
+
+        // Position dropdowns side by side: the game, the players, then the board and the variant
         let spacing = 40.0;
-        let total_width = BUTTON_WIDTH * 2.0 + spacing;
+        let total_width = BUTTON_WIDTH * 6.0 + spacing * 5.0;
         let start_x = (SCREEN_WIDTH - total_width) / 2.0;
         let y = SCREEN_HEIGHT / 3.0;
 
-        let player1_dropdown = Dropdown::new(
+        let game_dropdown = Dropdown::new(
             start_x,
             y,
             BUTTON_WIDTH,
             BUTTON_HEIGHT,
+            game_options,
+        );
+
+        let player1_dropdown = Dropdown::new(
+            start_x + BUTTON_WIDTH + spacing,
+            y,
+            BUTTON_WIDTH,
+            BUTTON_HEIGHT,
             player_options.clone(),
         );
 
         let player2_dropdown = Dropdown::new(
-            start_x + BUTTON_WIDTH + spacing,
+            start_x + 2.0 * (BUTTON_WIDTH + spacing),
             y,
             BUTTON_WIDTH,
             BUTTON_HEIGHT,
             player_options,
         );
 
+        let size_dropdown = Dropdown::new(
+            start_x + 3.0 * (BUTTON_WIDTH + spacing),
+            y,
+            BUTTON_WIDTH,
+            BUTTON_HEIGHT,
+            size_options,
+        );
+
+        let connect_dropdown = Dropdown::new(
+            start_x + 4.0 * (BUTTON_WIDTH + spacing),
+            y,
+            BUTTON_WIDTH,
+            BUTTON_HEIGHT,
+            connect_options,
+        );
+
+        let variant_dropdown = Dropdown::new(
+            start_x + 5.0 * (BUTTON_WIDTH + spacing),
+            y,
+            BUTTON_WIDTH,
+            BUTTON_HEIGHT,
+            variant_options,
+        );
+
         Self {
-            board: Board::new(),
-            current_player_number: 1,
-            player_types: [Player::Human, Player::Human],
-            game_over: false,
+            controller: GameController::new(),
             screen: GameScreen::Menu,
             start_button,
             new_game_button,
-            winner: None,
+            undo_button,
+            redo_button,
+            save_button,
+            load_button,
+            replay_button,
+            replay_last_button,
+            replay_view: None,
+            setup_button,
+            editor_view: None,
+            board_game_view: None,
+            game_dropdown,
             player1_dropdown,
             player2_dropdown,
-            random_bot: RandomBot,
-            mcts_bot: MctsBot::new(1000), // 1 second thinking time
+            size_dropdown,
+            connect_dropdown,
+            variant_dropdown,
+        }
+    }
+
+    // Board size, connect length and variant picked in the menu
+    fn selected_rules(&self) -> Rules {
+        let (columns, rows) = self.size_dropdown.selected_value();
+        Rules::new(columns, rows, self.connect_dropdown.selected_value())
+            .expect("Every size offered has room for every length")
+            .with_popout(self.variant_dropdown.selected_value())
+    }
+
+    // Opens or closes the menu dropdown under `point`, closing the others
+    fn click_dropdown(&mut self, point: Point2<f32>) {
+        let clicked = [
+            self.game_dropdown.contains(point),
+            self.player1_dropdown.contains(point),
+            self.player2_dropdown.contains(point),
+            self.size_dropdown.contains(point),
+            self.connect_dropdown.contains(point),
+            self.variant_dropdown.contains(point),
+        ];
+        let Some(index) = clicked.iter().position(|&clicked| clicked) else {
+            return;
+        };
+        match index {
+            0 => self.game_dropdown.click(point),
+            1 => self.player1_dropdown.click(point),
+            2 => self.player2_dropdown.click(point),
+            3 => self.size_dropdown.click(point),
+            4 => self.connect_dropdown.click(point),
+            _ => self.variant_dropdown.click(point),
         }
+        self.game_dropdown.is_open &= index == 0;
+        self.player1_dropdown.is_open &= index == 1;
+        self.player2_dropdown.is_open &= index == 2;
+        self.size_dropdown.is_open &= index == 3;
+        self.connect_dropdown.is_open &= index == 4;
+        self.variant_dropdown.is_open &= index == 5;
     }
 
     pub fn reset_game(&mut self) {
-        self.board = Board::new();
-        self.current_player_number = 1;
-        self.game_over = false;
-        self.winner = None;
+        self.controller.cancel_bot_move();
+        self.save_game();
         self.screen = GameScreen::Menu;
     }
 
-    fn handle_player_move(&mut self, col: usize) {
-        if let Some((row, col)) = self.board.make_move(col, self.current_player_number) {
-            if self.board.check_win(row, col) {
-                self.game_over = true;
-                self.winner = Some(self.current_player_number);
-                self.screen = GameScreen::GameOver;
-            } else if self.board.is_full() {
-                self.game_over = true;
-                self.winner = None;
-                self.screen = GameScreen::GameOver;
-            } else {
-                // Switch between player 1 and 2
-                self.current_player_number = if self.current_player_number == 1 { 2 } else { 1 };
-            }
+    fn handle_player_move(&mut self, mv: Move) {
+        if self.controller.play_move(mv) {
+            self.update_screen();
         }
     }
-    
+
     fn start_game(&mut self) {
+        let players = [self.player1_dropdown.selected_value(), self.player2_dropdown.selected_value()];
+        let view: Box<dyn BoardGameScreen> = match self.game_dropdown.selected_value() {
+            GameKind::ConnectFour => {
+                self.start_game_from(&BitBoard::with_rules(self.selected_rules()));
+                return;
+            }
+            GameKind::TicTacToe => Box::new(BoardGameView::new(BoardGame::new(players, MnkBoard::tic_tac_toe()), MnkRenderer)),
+            GameKind::Mnk => {
+                let (columns, rows) = self.size_dropdown.selected_value();
+                let board = MnkBoard::new(columns, rows, self.connect_dropdown.selected_value())
+                    .expect("Every size offered has room for every length");
+                Box::new(BoardGameView::new(BoardGame::new(players, board), MnkRenderer))
+            }
+            GameKind::Gomoku { exact_five } => {
+                let board = GomokuBoard::new().with_exact_five(exact_five);
+                Box::new(BoardGameView::new(BoardGame::new(players, board), GomokuRenderer))
+            }
+            GameKind::Othello => Box::new(BoardGameView::new(BoardGame::new(players, OthelloBoard::new()), OthelloRenderer)),
+        };
+        self.board_game_view = Some(view);
+        self.screen = GameScreen::BoardGame;
+    }
+
+    fn start_game_from(&mut self, position: &BitBoard) {
         // Set player types based on dropdown selections
-        self.player_types[0] = self.player1_dropdown.selected_value();
-        self.player_types[1] = self.player2_dropdown.selected_value();
-        
-        self.board = Board::new();
-        self.current_player_number = 1;
-        self.game_over = false;
-        self.winner = None;
+        self.controller.start_from([
+            self.player1_dropdown.selected_value(),
+            self.player2_dropdown.selected_value(),
+        ], position);
         self.screen = GameScreen::Game;
-        
-        // If first player is a bot, make their move
-        if self.player_types[0].is_bot() {
-            let col = match self.player_types[0] {
-                Player::RandomBot => self.random_bot.make_move(&self.board, self.current_player_number),
-                Player::MctsBot => self.mcts_bot.make_move(&self.board, self.current_player_number),
-                _ => None,
-            };
-            if let Some(col) = col {
-                self.handle_player_move(col);
+    }
+
+    // Writes the game to the save directory, named after its start time so that saving again replaces the file
+    fn save_game(&self) {
+        if self.controller.get_moves().is_empty() {
+            return;
+        }
+        let game = self.controller.to_saved_game();
+        let path = Path::new(SAVE_DIR).join(format!("game-{}.json", game.started));
+        if let Err(error) = game.save(&path) {
+            eprintln!("Could not save the game: {}", error);
+        }
+    }
+
+    // Shows the game that just ended on the replay screen
+    fn replay_game(&mut self) {
+        let replay = Replay::from_position(
+            [self.controller.get_player_type(1), self.controller.get_player_type(2)],
+            self.controller.get_start_position(),
+            self.controller.get_moves(),
+        );
+        self.show_replay(replay);
+    }
+
+    fn replay_last_game(&mut self) {
+        let Some(path) = last_saved_game() else {
+            eprintln!("No saved games in {}", SAVE_DIR);
+            return;
+        };
+        match SavedGame::load(&path) {
+            Ok(game) => self.show_replay(Replay::from_saved_game(&game)),
+            Err(error) => eprintln!("Could not load {}: {}", path.display(), error),
+        }
+    }
+
+    fn show_replay(&mut self, mut replay: Replay) {
+        replay.last();
+        self.replay_view = Some(ReplayView::new(replay));
+        self.screen = GameScreen::Replay;
+    }
+
+    // Continues the most recently started saved game
+    fn load_last_game(&mut self) {
+        let Some(path) = last_saved_game() else {
+            eprintln!("No saved games in {}", SAVE_DIR);
+            return;
+        };
+        match SavedGame::load(&path) {
+            Ok(game) => {
+                if let Err(error) = self.controller.load(&game) {
+                    eprintln!("Could not load {}: {}", path.display(), error);
+                    return;
+                }
+                for (dropdown, saved) in [&mut self.player1_dropdown, &mut self.player2_dropdown].into_iter().zip(&game.players) {
+                    dropdown.selected_index = Player::ALL.iter().position(|&player| player == saved.player).unwrap_or(0);
+                }
+                if let Some(index) = BOARD_SIZES.iter().position(|&size| size == (game.rules.columns, game.rules.rows)) {
+                    self.size_dropdown.selected_index = index;
+                }
+                if let Some(index) = CONNECT_LENGTHS.iter().position(|&connect| connect == game.rules.connect) {
+                    self.connect_dropdown.selected_index = index;
+                }
+                if let Some(index) = VARIANTS.iter().position(|&(_, popout)| popout == game.rules.popout) {
+                    self.variant_dropdown.selected_index = index;
+                }
+                self.screen = GameScreen::Game;
+                self.update_screen();
             }
+            Err(error) => eprintln!("Could not load {}: {}", path.display(), error),
         }
     }
 
-    fn get_current_player_type(&self) -> Player {
-        self.player_types[(self.current_player_number - 1) as usize]
+    // Takes back moves up to the last human turn, leaving the game over screen if needed
+    fn undo(&mut self) {
+        if self.controller.undo() {
+            self.screen = GameScreen::Game;
+        }
+    }
+
+    fn redo(&mut self) {
+        if self.controller.redo() {
+            self.update_screen();
+        }
+    }
+
+    // Moves on to the game over screen once the game has ended
+    fn update_screen(&mut self) {
+        if self.controller.is_game_over() {
+            self.screen = GameScreen::GameOver;
+        }
+    }
+
+    fn draw_history_panel(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        drawing::draw_button(ctx, canvas, &self.undo_button, false)?;
+        drawing::draw_button(ctx, canvas, &self.redo_button, false)?;
+        drawing::draw_button(ctx, canvas, &self.save_button, false)?;
+        drawing::draw_move_list(canvas, self.controller.get_moves(), MOVE_LIST_TOP, None, &[]);
+        Ok(())
     }
 }
 
 impl EventHandler for GameState {
     fn update(&mut self, _ctx: &mut Context) -> GameResult {
-        if self.screen == GameScreen::Game && 
-           !self.game_over && 
-           self.get_current_player_type().is_bot() {
-            // Get the current bot's move
-            let col = match self.get_current_player_type() {
-                Player::RandomBot => self.random_bot.make_move(&self.board, self.current_player_number),
-                Player::MctsBot => self.mcts_bot.make_move(&self.board, self.current_player_number),
-                _ => None,
-            };
-            
-            if let Some(col) = col {
-                self.handle_player_move(col);
-            }
+        // Bots think on a background thread, so the window keeps responding while they search
+        if self.screen == GameScreen::Game && self.controller.poll_bot_move().is_some() {
+            self.update_screen();
+        }
+        if let Some(replay_view) = &mut self.replay_view {
+            replay_view.update();
+        }
+        if let Some(board_game_view) = &mut self.board_game_view {
+            board_game_view.update();
         }
         Ok(())
     }
@@ -172,8 +430,24 @@ impl EventHandler for GameState {
                 let p2_text = Text::new(
                     TextFragment::new("Player 2:").color(Color::BLACK)
                 );
+                let board_text = Text::new(
+                    TextFragment::new("Board:").color(Color::BLACK)
+                );
+                let variant_text = Text::new(
+                    TextFragment::new("Variant:").color(Color::BLACK)
+                );
+                let game_text = Text::new(
+                    TextFragment::new("Game:").color(Color::BLACK)
+                );
                 
                 let text_y = SCREEN_HEIGHT / 3.0 - 30.0;
+                canvas.draw(
+                    &game_text,
+                    DrawParam::default().dest([
+                        self.game_dropdown.rect.x,
+                        text_y,
+                    ]),
+                );
                 canvas.draw(
                     &p1_text,
                     DrawParam::default().dest([
@@ -188,40 +462,65 @@ impl EventHandler for GameState {
                         text_y,
                     ]),
                 );
+                canvas.draw(
+                    &board_text,
+                    DrawParam::default().dest([
+                        self.size_dropdown.rect.x,
+                        text_y,
+                    ]),
+                );
+                canvas.draw(
+                    &variant_text,
+                    DrawParam::default().dest([
+                        self.variant_dropdown.rect.x,
+                        text_y,
+                    ]),
+                );
 
+                self.game_dropdown.draw(ctx, &mut canvas)?;
                 self.player1_dropdown.draw(ctx, &mut canvas)?;
                 self.player2_dropdown.draw(ctx, &mut canvas)?;
+                self.size_dropdown.draw(ctx, &mut canvas)?;
+                self.connect_dropdown.draw(ctx, &mut canvas)?;
+                self.variant_dropdown.draw(ctx, &mut canvas)?;
                 drawing::draw_button(ctx, &mut canvas, &self.start_button, false)?;
+                drawing::draw_button(ctx, &mut canvas, &self.load_button, false)?;
+                drawing::draw_button(ctx, &mut canvas, &self.replay_last_button, false)?;
+                drawing::draw_button(ctx, &mut canvas, &self.setup_button, false)?;
             }
             GameScreen::Game => {
-                drawing::draw_board(ctx, &mut canvas, &self.board)?;
+                let analysis = self.controller.get_analysis();
+                drawing::draw_board(ctx, &mut canvas, self.controller.get_board(), analysis.as_ref())?;
+                self.draw_history_panel(ctx, &mut canvas)?;
 
                 // Draw current player indicator
-                let current_type = self.get_current_player_type();
-                let player_text = format!(
+                let current_type = self.controller.get_current_player_type();
+                let mut player_text = format!(
                     "Current Turn: Player {} ({})",
-                    self.current_player_number,
-                    match current_type {
-                        Player::Human => "Human",
-                        Player::RandomBot => "Random Bot",
-                        Player::MctsBot => "MCTS Bot",
-                    }
+                    self.controller.get_current_player_number(),
+                    current_type.label()
                 );
+                if self.controller.is_thinking() {
+                    player_text.push_str(" is thinking...");
+                } else if self.controller.is_human_turn() && self.controller.get_rules().popout {
+                    player_text.push_str(" - right-click to pop");
+                }
                 let text = Text::new(TextFragment::new(player_text).color(Color::BLACK));
                 let text_dims = text.dimensions(ctx).unwrap();
                 canvas.draw(
                     &text,
                     DrawParam::default().dest([
-                        (SCREEN_WIDTH - text_dims.w as f32) / 2.0,
+                        (SCREEN_WIDTH - text_dims.w) / 2.0,
                         10.0,
                     ]),
                 );
             }
             GameScreen::GameOver => {
-                drawing::draw_board(ctx, &mut canvas, &self.board)?;
+                drawing::draw_board(ctx, &mut canvas, self.controller.get_board(), None)?;
+                self.draw_history_panel(ctx, &mut canvas)?;
 
                 // Draw game over message
-                let message = match self.winner {
+                let message = match self.controller.get_winner() {
                     Some(player_num) => format!("Player {} Wins!", player_num),
                     None => "Game Draw!".to_string(),
                 };
@@ -231,12 +530,28 @@ impl EventHandler for GameState {
                 canvas.draw(
                     &text,
                     DrawParam::default().dest([
-                        (SCREEN_WIDTH - text_dims.w as f32) / 2.0,
+                        (SCREEN_WIDTH - text_dims.w) / 2.0,
                         SCREEN_HEIGHT / 4.0,
                     ]),
                 );
 
                 drawing::draw_button(ctx, &mut canvas, &self.new_game_button, false)?;
+                drawing::draw_button(ctx, &mut canvas, &self.replay_button, false)?;
+            }
+            GameScreen::Replay => {
+                if let Some(replay_view) = &self.replay_view {
+                    replay_view.draw(ctx, &mut canvas)?;
+                }
+            }
+            GameScreen::Editor => {
+                if let Some(editor_view) = &self.editor_view {
+                    editor_view.draw(ctx, &mut canvas)?;
+                }
+            }
+            GameScreen::BoardGame => {
+                if let Some(board_game_view) = &self.board_game_view {
+                    board_game_view.draw(ctx, &mut canvas)?;
+                }
             }
         }
 
@@ -251,6 +566,12 @@ impl EventHandler for GameState {
         x: f32,
         y: f32,
     ) -> GameResult {
+        // Right-clicking a column pops its bottom disc in PopOut
+        if button == MouseButton::Right && self.screen == GameScreen::Game && self.controller.is_human_turn() {
+            if let Some((_, col)) = drawing::cell_at(&self.controller.get_rules(), Point2 { x, y }) {
+                self.handle_player_move(Move::Pop(col));
+            }
+        }
         if button == MouseButton::Left {
             let point = Point2 { x, y };
 
@@ -258,41 +579,103 @@ impl EventHandler for GameState {
                 GameScreen::Menu => {
                     if self.start_button.contains(point) {
                         self.start_game();
-                    } else if self.player1_dropdown.contains(point) {
-                        if self.player1_dropdown.is_open {
-                            if let Some(index) = self.player1_dropdown.get_option_at(point) {
-                                self.player1_dropdown.selected_index = index;
-                            }
-                            self.player1_dropdown.is_open = false;
-                        } else {
-                            self.player1_dropdown.is_open = true;
-                            self.player2_dropdown.is_open = false;
-                        }
-                    } else if self.player2_dropdown.contains(point) {
-                        if self.player2_dropdown.is_open {
-                            if let Some(index) = self.player2_dropdown.get_option_at(point) {
-                                self.player2_dropdown.selected_index = index;
-                            }
-                            self.player2_dropdown.is_open = false;
-                        } else {
-                            self.player2_dropdown.is_open = true;
-                            self.player1_dropdown.is_open = false;
-                        }
+                    } else if self.load_button.contains(point) {
+                        self.load_last_game();
+                    } else if self.replay_last_button.contains(point) {
+                        self.replay_last_game();
+                    } else if self.setup_button.contains(point) {
+                        self.editor_view = Some(EditorView::new(&BitBoard::with_rules(self.selected_rules())));
+                        self.screen = GameScreen::Editor;
+                    } else {
+                        self.click_dropdown(point);
                     }
                 }
+                GameScreen::Game | GameScreen::GameOver if self.undo_button.contains(point) => self.undo(),
+                GameScreen::Game | GameScreen::GameOver if self.redo_button.contains(point) => self.redo(),
+                GameScreen::Game | GameScreen::GameOver if self.save_button.contains(point) => self.save_game(),
                 GameScreen::Game => {
-                    if !self.game_over && !self.get_current_player_type().is_bot() {
-                        let col = (x / CELL_SIZE) as usize;
-                        self.handle_player_move(col);
+                    if self.controller.is_human_turn() {
+                        if let Some((_, col)) = drawing::cell_at(&self.controller.get_rules(), point) {
+                            self.handle_player_move(Move::Drop(col));
+                        }
                     }
                 }
                 GameScreen::GameOver => {
                     if self.new_game_button.contains(point) {
                         self.reset_game();
+                    } else if self.replay_button.contains(point) {
+                        self.replay_game();
                     }
                 }
+                GameScreen::Replay => {
+                    let action = self.replay_view.as_mut().map(|replay_view| replay_view.handle_click(point));
+                    if action == Some(ReplayAction::Close) {
+                        // An analysis goes back to the position it came from
+                        self.replay_view = None;
+                        self.screen = if self.editor_view.is_some() { GameScreen::Editor } else { GameScreen::Menu };
+                    }
+                }
+                GameScreen::Editor => {
+                    let action = self.editor_view.as_mut().map(|editor_view| editor_view.handle_click(point));
+                    match action {
+                        Some(EditorAction::Play(position)) => {
+                            self.editor_view = None;
+                            self.start_game_from(&position);
+                        }
+                        Some(EditorAction::Analyze(position)) => {
+                            let players = [self.player1_dropdown.selected_value(), self.player2_dropdown.selected_value()];
+                            self.show_replay(Replay::from_position(players, &position, &[]));
+                        }
+                        Some(EditorAction::Close) => {
+                            self.editor_view = None;
+                            self.screen = GameScreen::Menu;
+                        }
+                        Some(EditorAction::None) | None => {}
+                    }
+                }
+                GameScreen::BoardGame => {
+                    let action = self.board_game_view.as_mut().map(|board_game_view| board_game_view.handle_click(point));
+                    if action == Some(BoardGameAction::Close) {
+                        self.board_game_view = None;
+                        self.screen = GameScreen::Menu;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, _repeated: bool) -> GameResult {
+        match input.keycode {
+            // Overriding this handler replaces ggez's default of quitting on Escape
+            Some(KeyCode::Escape) => ctx.request_quit(),
+            Some(key) => {
+                if let Some(replay_view) = &mut self.replay_view {
+                    replay_view.handle_key(key);
+                }
             }
+            None => {}
         }
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn quit_event(&mut self, _ctx: &mut Context) -> GameResult<bool> {
+        // Stop a searching bot so the process can exit right away, and keep the game
+        self.controller.cancel_bot_move();
+        if let Some(board_game_view) = &mut self.board_game_view {
+            board_game_view.cancel_bot_move();
+        }
+        if matches!(self.screen, GameScreen::Game | GameScreen::GameOver) {
+            self.save_game();
+        }
+        Ok(false)
+    }
+}
+
+// The saved game with the latest start time. File names hold the start time, so they sort by it.
+fn last_saved_game() -> Option<PathBuf> {
+    fs::read_dir(SAVE_DIR).ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .max()
+}
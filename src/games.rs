@@ -1 +1,50 @@
-pub mod connect4;
\ No newline at end of file
+pub mod connect4;
+pub mod grid;
+pub mod mnk;
+pub mod gomoku;
+pub mod othello;
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Final result of a finished game.
+pub trait Outcome: Copy + Debug + Send + Sync {
+    /// Reward from player 1's point of view: 1 for a win, -1 for a loss and 0 for a draw.
+    fn reward(&self) -> i32;
+
+    /// How big player 1's win was, from -1.0 for the biggest possible loss to 1.0 for the biggest
+    /// possible win, for searches that score by margin. Games without a score keep the reward.
+    fn margin(&self) -> f32 {
+        self.reward() as f32
+    }
+}
+
+impl Outcome for i32 {
+    fn reward(&self) -> i32 {
+        *self
+    }
+}
+
+/// A deterministic, zero-sum, two-player game that the search can play.
+///
+/// Players are numbered 1 and 2, and player 1 always moves first.
+pub trait Game: Clone + PartialEq + Send + Sync {
+    type Move: Copy + Eq + Hash + Debug + Send + Sync;
+    type Outcome: Outcome;
+
+    fn get_current_player(&self) -> u8;
+
+    /// Moves for the search to consider. Games may leave out moves that are legal but not worth searching.
+    ///
+    /// The search counts a position without valid moves that `make_move` did not end as a draw.
+    /// Games where a player can be forced to pass should offer a pass move instead.
+    fn get_valid_moves(&self) -> Vec<Self::Move>;
+
+    /// Whether the current player may play `mv`, even if `get_valid_moves` leaves it out.
+    fn is_valid_move(&self, mv: Self::Move) -> bool {
+        self.get_valid_moves().contains(&mv)
+    }
+
+    /// Plays `mv` for the current player and returns the outcome if the game ended.
+    fn make_move(&mut self, mv: Self::Move) -> Option<Self::Outcome>;
+}
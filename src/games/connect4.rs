@@ -1,2 +1,7 @@
 pub mod board;
-pub mod bitboard;
\ No newline at end of file
+pub mod bitboard;
+pub mod rules;
+pub mod playout;
+pub mod solver;
+pub mod notation;
+pub mod popout;
@@ -1,325 +1,338 @@
+use crate::games::Game;
 use crate::games::connect4::board::Board;
+use crate::games::connect4::rules::{Rules, MAX_COLUMNS};
+use crate::games::grid::{has_line, shift_down, Bits};
 
-#[derive(Clone, Copy, Debug)]
+// Position as one bit set of discs per player.
+//
+// Cell (row, col) is bit `col * (rows + 1) + row`: columns follow each other with an empty
+// sentinel row on top of every column. Lines are found with plain shifts, by 1 along a column,
+// `rows + 1` along a row and `rows` or `rows + 2` along the diagonals, and a line that would wrap
+// into the next column always crosses a sentinel. Boards that fit in 64 bits use `u64` bit sets,
+// larger ones `u128`.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct BitBoard {
-    player1: u64,   // Current player's pieces
-    player2: u64,  // Opponent's pieces
-    heights: [u8; 7], // Height of each column (0-5)
+    rules: Rules,
+    discs: Discs,
+    heights: [u8; MAX_COLUMNS],  // Number of discs in each column
     current_player: u8,
 }
 
-const WINNING_MASKS: [[u64; 13]; 42] = generate_winning_masks();
-const MASK_COUNTS: [u8; 42] = generate_mask_counts();
-
-const fn generate_mask_counts() -> [u8; 42] {
-    let mut counts = [0; 42];
-    
-    let mut row = 0;
-    while row < 6 {
-        let mut col = 0;
-        while col < 7 {
-            let pos = row * 7 + col;
-            let mut count = 0;
-            
-            // Horizontal patterns
-            if col <= 3 { count += 1; }  // As leftmost
-            if col >= 1 && col <= 4 { count += 1; }  // As second
-            if col >= 2 && col <= 5 { count += 1; }  // As third
-            if col >= 3 { count += 1; }  // As rightmost
-            
-            // Vertical patterns (board height limits to 3 patterns)
-            if row <= 2 { count += 1; }  // As bottom
-            if row >= 1 && row <= 3 { count += 1; }  // As second
-            if row >= 2 && row <= 4 { count += 1; }  // As third
-            if row >= 3 { count += 1; }  // As top
-            
-            // Diagonal up-right
-            if row <= 2 && col <= 3 { count += 1; }  // As bottom-left
-            if row >= 1 && row <= 3 && col >= 1 && col <= 4 { count += 1; }  // As second
-            if row >= 2 && row <= 4 && col >= 2 && col <= 5 { count += 1; }  // As third
-            if row >= 3 && col >= 3 { count += 1; }  // As top-right
-
-            // Diagonal up-left
-            if row <= 2 && col >= 3 { count += 1; }  // As bottom-right
-            if row >= 1 && row <= 3 && col >= 2 && col <= 5 { count += 1; }  // As second
-            if row >= 2 && row <= 4 && col >= 1 && col <= 4 { count += 1; }  // As third
-            if row >= 3 && col <= 3 { count += 1; }  // As top-left
-            
-            counts[pos] = count;
-            col += 1;
-        }
-        row += 1;
-    }
-    
-    counts
+// Discs of player 1 and player 2
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Discs {
+    Small([u64; 2]),
+    Large([u128; 2]),
 }
 
-const fn generate_winning_masks() -> [[u64; 13]; 42] {
-    let mut masks = [[0; 13]; 42];
-    
-    let mut row = 0;
-    while row < 6 {
-        let mut col = 0;
-        while col < 7 {
-            let pos = row * 7 + col;
-            let mut mask_idx = 0;
-
-            // Horizontal patterns
-            if col <= 3 {
-                // As leftmost piece
-                masks[pos][mask_idx] = 0b1111u64 << (row * 7 + col);
-                mask_idx += 1;
-            }
-            if col >= 1 && col <= 4 {
-                // As second piece
-                masks[pos][mask_idx] = 0b1111u64 << (row * 7 + col - 1);
-                mask_idx += 1;
-            }
-            if col >= 2 && col <= 5 {
-                // As third piece
-                masks[pos][mask_idx] = 0b1111u64 << (row * 7 + col - 2);
-                mask_idx += 1;
-            }
-            if col >= 3 {
-                // As rightmost piece
-                masks[pos][mask_idx] = 0b1111u64 << (row * 7 + col - 3);
-                mask_idx += 1;
-            }
+// `bits` with the cells of the column starting at `column_start` moved down one row,
+// dropping the bottom cell of the column
+fn pop_column<B: Bits>(bits: B, column_start: usize, rows: usize) -> B {
+    let mask = !(!B::ZERO << rows) << column_start;
+    (bits & !mask) | (shift_down(bits & mask, 1) & mask)
+}
 
-            // Vertical patterns
-            if row <= 2 {
-                // As bottom piece
-                let mut vmask = 0u64;
-                let mut r = 0;
-                while r < 4 {
-                    vmask |= 1u64 << ((row + r) * 7 + col);
-                    r += 1;
-                }
-                masks[pos][mask_idx] = vmask;
-                mask_idx += 1;
-            }
-            if row >= 1 && row <= 3 {
-                // As second piece
-                let mut vmask = 0u64;
-                let mut r = 0;
-                while r < 4 {
-                    vmask |= 1u64 << ((row - 1 + r) * 7 + col);
-                    r += 1;
-                }
-                masks[pos][mask_idx] = vmask;
-                mask_idx += 1;
-            }
-            if row >= 2 && row <= 4 {
-                // As third piece
-                let mut vmask = 0u64;
-                let mut r = 0;
-                while r < 4 {
-                    vmask |= 1u64 << ((row - 2 + r) * 7 + col);
-                    r += 1;
-                }
-                masks[pos][mask_idx] = vmask;
-                mask_idx += 1;
-            }
+// Reverses the order of the `count` groups of `group_bits` bits in `bits`
+pub(crate) fn reverse_groups<B: Bits>(bits: B, group_bits: usize, count: usize) -> B {
+    let group_mask = !(!B::ZERO << group_bits);
+    let mut reversed = B::ZERO;
+    for group in 0..count {
+        reversed = reversed | ((bits >> (group * group_bits)) & group_mask) << ((count - 1 - group) * group_bits);
+    }
+    reversed
+}
 
-            if row >= 3 {
-                // As top piece
-                let mut vmask = 0u64;
-                let mut r = 0;
-                while r < 4 {
-                    vmask |= 1u64 << ((row - 3 + r) * 7 + col);
-                    r += 1;
-                }
-                masks[pos][mask_idx] = vmask;
-                mask_idx += 1;
-            }
+// Unique number for the discs of one column-major position: every column's mask of discs
+// plus the discs of player 1, which cannot carry into the next column
+fn position_key<B: Bits>(discs: [B; 2]) -> B {
+    (discs[0] | discs[1]) + discs[0]
+}
 
-            // Diagonal up-right patterns
-            if row <= 2 && col <= 3 {
-                // As bottom-left piece
-                let mut dmask = 0u64;
-                let mut r = 0;
-                while r < 4 {
-                    dmask |= 1u64 << ((row + r) * 7 + (col + r));
-                    r += 1;
-                }
-                masks[pos][mask_idx] = dmask;
-                mask_idx += 1;
-            }
-            if row >= 1 && row <= 3 && col >= 1 && col <= 4 {
-                // As second piece
-                let mut dmask = 0u64;
-                let mut r = 0;
-                while r < 4 {
-                    dmask |= 1u64 << ((row - 1 + r) * 7 + (col - 1 + r));
-                    r += 1;
-                }
-                masks[pos][mask_idx] = dmask;
-                mask_idx += 1;
-            }
-            if row >= 2 && row <= 4 && col >= 2 && col <= 5 {
-                // As third piece
-                let mut dmask = 0u64;
-                let mut r = 0;
-                while r < 4 {
-                    dmask |= 1u64 << ((row - 2 + r) * 7 + (col - 2 + r));
-                    r += 1;
-                }
-                masks[pos][mask_idx] = dmask;
-                mask_idx += 1;
-            }
-            if row >= 3 && col >= 3 {
-                // As top-right piece
-                let mut dmask = 0u64;
-                let mut r = 0;
-                while r < 4 {
-                    dmask |= 1u64 << ((row - 3 + r) * 7 + (col - 3 + r));
-                    r += 1;
-                }
-                masks[pos][mask_idx] = dmask;
-                mask_idx += 1;
-            }
+impl Discs {
+    fn new(rules: &Rules) -> Self {
+        if rules.fits_in_64_bits() { Discs::Small([0; 2]) } else { Discs::Large([0; 2]) }
+    }
 
-            // Diagonal up-left patterns
-            if row <= 2 && col >= 3 {
-                // As bottom-right piece
-                let mut dmask = 0u64;
-                let mut r = 0;
-                while r < 4 {
-                    dmask |= 1u64 << ((row + r) * 7 + (col - r));
-                    r += 1;
-                }
-                masks[pos][mask_idx] = dmask;
-                mask_idx += 1;
-            }
-            if row >= 1 && row <= 3 && col >= 2 && col <= 5 {
-                // As second piece
-                let mut dmask = 0u64;
-                let mut r = 0;
-                while r < 4 {
-                    dmask |= 1u64 << ((row - 1 + r) * 7 + (col + 1 - r));
-                    r += 1;
-                }
-                masks[pos][mask_idx] = dmask;
-                mask_idx += 1;
-            }
-            if row >= 2 && row <= 4 && col >= 1 && col <= 4 {
-                // As third piece
-                let mut dmask = 0u64;
-                let mut r = 0;
-                while r < 4 {
-                    dmask |= 1u64 << ((row - 2 + r) * 7 + (col + 2 - r));
-                    r += 1;
-                }
-                masks[pos][mask_idx] = dmask;
-                mask_idx += 1;
-            }
-            if row >= 3 && col <= 3 {
-                // As top-left piece
-                let mut dmask = 0u64;
-                let mut r = 0;
-                while r < 4 {
-                    dmask |= 1u64 << ((row - 3 + r) * 7 + (col + 3 - r));
-                    r += 1;
-                }
-                masks[pos][mask_idx] = dmask;
-                mask_idx += 1;
-            }
+    fn contains(&self, player: u8, bit: usize) -> bool {
+        match self {
+            Discs::Small(discs) => discs[player as usize - 1] >> bit & 1 != 0,
+            Discs::Large(discs) => discs[player as usize - 1] >> bit & 1 != 0,
+        }
+    }
 
-            // Fill remaining slots with 0s
-            while mask_idx < 13 {
-                masks[pos][mask_idx] = 0;
-                mask_idx += 1;
-            }
+    fn toggle(&mut self, player: u8, bit: usize) {
+        match self {
+            Discs::Small(discs) => discs[player as usize - 1] ^= 1 << bit,
+            Discs::Large(discs) => discs[player as usize - 1] ^= 1 << bit,
+        }
+    }
 
-            col += 1;
+    // Whether `player` has a line once it owns `bit` as well
+    fn has_line_with(&self, player: u8, bit: usize, rules: &Rules) -> bool {
+        match self {
+            Discs::Small(discs) => has_line(discs[player as usize - 1] | 1 << bit, rules.rows, rules.connect),
+            Discs::Large(discs) => has_line(discs[player as usize - 1] | 1 << bit, rules.rows, rules.connect),
+        }
+    }
+
+    fn has_line(&self, player: u8, rules: &Rules) -> bool {
+        match self {
+            Discs::Small(discs) => has_line(discs[player as usize - 1], rules.rows, rules.connect),
+            Discs::Large(discs) => has_line(discs[player as usize - 1], rules.rows, rules.connect),
+        }
+    }
+
+    fn pop(&mut self, col: usize, rules: &Rules) {
+        let column_start = col * (rules.rows + 1);
+        match self {
+            Discs::Small(discs) => *discs = discs.map(|bits| pop_column(bits, column_start, rules.rows)),
+            Discs::Large(discs) => *discs = discs.map(|bits| pop_column(bits, column_start, rules.rows)),
+        }
+    }
+
+    fn mirror(&self, rules: &Rules) -> Self {
+        let (group_bits, count) = (rules.rows + 1, rules.columns);
+        match self {
+            Discs::Small(discs) => Discs::Small(discs.map(|bits| reverse_groups(bits, group_bits, count))),
+            Discs::Large(discs) => Discs::Large(discs.map(|bits| reverse_groups(bits, group_bits, count))),
+        }
+    }
+
+    // Large boards fold their key into 64 bits, so their keys identify positions only almost surely
+    fn key(&self) -> u64 {
+        match self {
+            Discs::Small(discs) => position_key(*discs),
+            Discs::Large(discs) => {
+                let key = position_key(*discs);
+                key as u64 ^ (key >> 64) as u64
+            }
         }
-        row += 1;
     }
-    
-    masks
 }
 
 impl BitBoard {
+    // The empty standard board
+    pub fn new() -> Self {
+        Self::with_rules(Rules::STANDARD)
+    }
+
+    pub fn with_rules(rules: Rules) -> Self {
+        Self {
+            rules,
+            discs: Discs::new(&rules),
+            heights: [0; MAX_COLUMNS],
+            current_player: 1,
+        }
+    }
+
     pub fn from_board(board: &Board, current_player: u8) -> Self {
-        let mut player1 = 0u64;
-        let mut player2 = 0u64;
-        let mut heights = [0u8; 7];
-
-        for col in 0..crate::config::GRID_COLS {
-            for row in 0..crate::config::GRID_ROWS {
-                let pos = row * crate::config::GRID_COLS + col;
-                match board.cells[row][col] {
-                    1 => player1 |= 1u64 << pos,
-                    2 => player2 |= 1u64 << pos,
-                    _ => {}
-                }
-                if board.cells[row][col] != 0 {
-                    heights[col] = (row + 1) as u8; // Height is 1-based
+        let mut bitboard = Self::with_rules(board.get_rules());
+        bitboard.current_player = current_player;
+        for col in 0..bitboard.rules.columns {
+            for row in 0..bitboard.rules.rows {
+                let player = board.cells[row][col];
+                if player != 0 {
+                    bitboard.discs.toggle(player, bitboard.bit(row, col));
+                    bitboard.heights[col] = (row + 1) as u8;
                 }
             }
         }
+        bitboard
+    }
 
-        Self {
-            player1,
-            player2,
-            heights,
-            current_player,
+    pub fn to_board(&self) -> Board {
+        let mut board = Board::with_rules(self.rules);
+        for (row, cells) in board.cells.iter_mut().enumerate() {
+            for (col, cell) in cells.iter_mut().enumerate() {
+                *cell = self.get_player_at(row, col);
+            }
         }
+        board
+    }
+
+    pub fn get_rules(&self) -> Rules {
+        self.rules
     }
 
     pub fn is_valid_move(&self, col: usize) -> bool {
-        self.heights[col] < 6
+        col < self.rules.columns && (self.heights[col] as usize) < self.rules.rows
     }
 
     pub fn get_current_player(&self) -> u8 {
         self.current_player
     }
 
-    pub fn make_move(&mut self, col: usize) -> Option<i32> {
-        let row = self.heights[col];
-        let pos = row as u64 * 7 + col as u64;
-        if self.current_player == 1 {
-            self.player1 |= 1u64 << pos;
+    // Key of the position: the column-major mask of all discs plus the discs of player 1.
+    // Unique for boards that fit in 64 bits.
+    pub fn key(&self) -> u64 {
+        self.discs.key()
+    }
+
+    // The position with every column `col` moved to `columns - 1 - col`
+    pub fn mirror(&self) -> Self {
+        let mut heights = self.heights;
+        heights[..self.rules.columns].reverse();
+        Self {
+            rules: self.rules,
+            discs: self.discs.mirror(&self.rules),
+            heights,
+            current_player: self.current_player,
+        }
+    }
+
+    // The one of this position and its mirror image with the smaller key, and whether it is the mirror.
+    // Moves of the canonical position map back with `Rules::mirror_column` when it is mirrored.
+    pub fn canonical(&self) -> (Self, bool) {
+        let mirrored = self.mirror();
+        if mirrored.key() < self.key() { (mirrored, true) } else { (*self, false) }
+    }
+
+    // Key shared by the position and its mirror image
+    pub fn canonical_key(&self) -> u64 {
+        self.key().min(self.mirror().key())
+    }
+
+    // Number of discs in `col`
+    pub fn get_height(&self, col: usize) -> usize {
+        self.heights[col] as usize
+    }
+
+    // Player owning the cell, or 0 if it is empty
+    pub fn get_player_at(&self, row: usize, col: usize) -> u8 {
+        let bit = self.bit(row, col);
+        if self.discs.contains(1, bit) {
+            1
+        } else if self.discs.contains(2, bit) {
+            2
         } else {
-            self.player2 |= 1u64 << pos;
+            0
         }
+    }
+
+    pub fn make_move(&mut self, col: usize) -> Option<i32> {
         let is_winning = self.is_winning_move(col);
+        let bit = self.bit(self.heights[col] as usize, col);
+        self.discs.toggle(self.current_player, bit);
         self.heights[col] += 1;
         let mut res: Option<i32> = None;
         if is_winning {
             res = Some(3 - 2 * self.current_player as i32);
-        } else if self.is_full() {  
+        } else if self.is_full() && !self.has_pop_for(3 - self.current_player) {
+            // In PopOut the game goes on on a full board while the next player can pop
             res = Some(0);
         }
         self.current_player = 3 - self.current_player;
         res
     }
 
-    fn is_winning_move(&self, col: usize) -> bool {
-        let row = self.heights[col] as usize;
-        let pos = row * 7 + col;
-        let board = if self.current_player == 1 {
-            self.player1 | (1u64 << pos)
+    // Whether the current player may pop the bottom disc of `col`: only in PopOut, and only their own discs
+    pub fn can_pop(&self, col: usize) -> bool {
+        self.can_pop_for(col, self.current_player)
+    }
+
+    // Columns the current player can pop
+    pub fn get_valid_pops(&self) -> Vec<usize> {
+        (0..self.rules.columns).filter(|&col| self.can_pop(col)).collect()
+    }
+
+    // Removes the current player's disc from the bottom of `col`, which `can_pop` must allow, and lets
+    // the discs above it fall one row. A pop can complete lines for both players at once, and then the
+    // player who popped wins.
+    pub fn pop(&mut self, col: usize) -> Option<i32> {
+        self.discs.pop(col, &self.rules);
+        self.heights[col] -= 1;
+        let player = self.current_player;
+        self.current_player = 3 - player;
+        if self.discs.has_line(player, &self.rules) {
+            Some(3 - 2 * player as i32)
+        } else if self.discs.has_line(3 - player, &self.rules) {
+            Some(2 * player as i32 - 3)
         } else {
-            self.player2 | (1u64 << pos)
-        };
-
-        let num_patterns = MASK_COUNTS[pos] as usize;
-        for i in 0..num_patterns {
-            let mask = WINNING_MASKS[pos][i];
-            if (board & mask) == mask {
-                return true;
-            }
+            None
         }
-        false
+    }
+
+    fn can_pop_for(&self, col: usize, player: u8) -> bool {
+        self.rules.popout && col < self.rules.columns && self.heights[col] > 0
+            && self.discs.contains(player, self.bit(0, col))
+    }
+
+    fn has_pop_for(&self, player: u8) -> bool {
+        (0..self.rules.columns).any(|col| self.can_pop_for(col, player))
+    }
+
+    // Takes back the last disc dropped in `col`, which must have been the last move played
+    pub fn unmake_move(&mut self, col: usize) {
+        self.heights[col] -= 1;
+        let player = 3 - self.current_player;
+        self.discs.toggle(player, self.bit(self.heights[col] as usize, col));
+        self.current_player = player;
+    }
+
+    // Whether the current player completes a line by dropping a disc in `col`
+    pub fn is_winning_move(&self, col: usize) -> bool {
+        self.is_winning_move_for(col, self.current_player)
+    }
+
+    // Whether `player` would complete a line by dropping a disc in `col`
+    pub fn is_winning_move_for(&self, col: usize, player: u8) -> bool {
+        self.discs.has_line_with(player, self.bit(self.heights[col] as usize, col), &self.rules)
     }
 
     pub fn get_valid_moves(&self) -> Vec<usize> {
-        (0..7).filter(|&col| self.is_valid_move(col)).collect()
+        (0..self.rules.columns).filter(|&col| self.is_valid_move(col)).collect()
     }
 
     pub fn is_full(&self) -> bool {
-        self.heights.iter().all(|&h| h >= 6)
+        self.heights[..self.rules.columns].iter().all(|&h| h as usize >= self.rules.rows)
+    }
+
+    // The player who already has a line, if any, in which case the game is over. A pop can complete
+    // lines for both players, and then the player who popped, the one not to move, comes first.
+    pub fn get_winner(&self) -> Option<u8> {
+        [3 - self.current_player, self.current_player].into_iter().find(|&player| self.discs.has_line(player, &self.rules))
+    }
+
+    // Result from player 1's point of view if the game is over: a player has a line, or the board is
+    // full and the side to move cannot pop. PopOut's repetition draws depend on the history of the
+    // game and are left to `PopOutBoard`.
+    pub fn get_result(&self) -> Option<i32> {
+        match self.get_winner() {
+            Some(player) => Some(3 - 2 * player as i32),
+            None => (self.is_full() && !self.has_pop_for(self.current_player)).then_some(0),
+        }
+    }
+
+    fn bit(&self, row: usize, col: usize) -> usize {
+        col * (self.rules.rows + 1) + row
+    }
+}
+
+impl Default for BitBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game for BitBoard {
+    type Move = usize;
+    type Outcome = i32;
+
+    fn get_current_player(&self) -> u8 {
+        BitBoard::get_current_player(self)
+    }
+
+    fn get_valid_moves(&self) -> Vec<usize> {
+        BitBoard::get_valid_moves(self)
+    }
+
+    fn is_valid_move(&self, col: usize) -> bool {
+        BitBoard::is_valid_move(self, col)
+    }
+
+    fn make_move(&mut self, col: usize) -> Option<i32> {
+        BitBoard::make_move(self, col)
     }
 }
 
@@ -330,9 +343,11 @@ mod tests {
     #[test]
     fn test_new_board() {
         let board = BitBoard::new();
-        assert_eq!(board.current, 0);
-        assert_eq!(board.opponent, 0);
-        assert_eq!(board.heights, [0; 7]);
+        assert_eq!(board.discs, Discs::Small([0; 2]));
+        assert_eq!(board.heights, [0; MAX_COLUMNS]);
+        assert_eq!(board.get_current_player(), 1);
+        assert_eq!(board.get_valid_moves().len(), 7);
+        assert!(!board.is_valid_move(7));
     }
 
     #[test]
@@ -342,7 +357,7 @@ mod tests {
         
         // Fill up column 0
         for _ in 0..6 {
-            assert!(board.make_move(0));
+            assert!(board.make_move(0).is_none());
         }
         
         // Column should now be full
@@ -354,14 +369,10 @@ mod tests {
         let mut board = BitBoard::new();
         
         // Player 1: 0,1,2
-        board.make_move(0);
-        board.make_move(1);
-        board.make_move(2);
-        
         // Player 2: 6,6,6
-        board.make_move(6);
-        board.make_move(6);
-        board.make_move(6);
+        for col in [0, 6, 1, 6, 2, 6] {
+            board.make_move(col);
+        }
         
         // Test winning move for Player 1
         assert!(board.is_winning_move(3));
@@ -372,30 +383,210 @@ mod tests {
         let mut board = BitBoard::new();
         
         // Player 1: 0,0,0
-        board.make_move(0);
-        board.make_move(0);
-        board.make_move(0);
-        
         // Player 2: 1,1,1
-        board.make_move(1);
-        board.make_move(1);
-        board.make_move(1);
+        for col in [0, 1, 0, 1, 0, 1] {
+            board.make_move(col);
+        }
         
         // Test winning move for Player 1
         assert!(board.is_winning_move(0));
+        assert_eq!(board.get_winner(), None);
+        assert_eq!(board.get_result(), None);
+        board.make_move(0);
+        assert_eq!(board.get_winner(), Some(1));
+        assert_eq!(board.get_result(), Some(1));
+    }
+
+    #[test]
+    fn test_diagonal_wins() {
+        // Player 1 builds a rising diagonal from column 0 and needs column 3 to complete it
+        let mut board = BitBoard::new();
+        for col in [0, 1, 1, 2, 2, 3, 2, 3, 3, 6] {
+            board.make_move(col);
+        }
+        assert!(board.is_winning_move(3));
+        assert!(!board.is_winning_move(4));
+
+        // Three discs at the top of one column and the bottom of the next are no line
+        let mut board = BitBoard::new();
+        for col in [0, 0, 0, 6, 0, 0, 0, 1, 6, 6] {
+            board.make_move(col);
+        }
+        assert!(!board.is_winning_move_for(1, 1));
+    }
+
+    #[test]
+    fn test_other_sizes_and_lengths() {
+        // Connect 3 on a 6x5 board: two discs and a third wins
+        let mut board = BitBoard::with_rules(Rules::new(6, 5, 3).unwrap());
+        for col in [0, 5, 1, 5] {
+            assert_eq!(board.make_move(col), None);
+        }
+        assert_eq!(board.make_move(2), Some(1));
+
+        // Connect 5 needs the fifth disc
+        let mut board = BitBoard::with_rules(Rules::new(8, 7, 5).unwrap());
+        for col in [0, 7, 1, 7, 2, 7, 3, 6] {
+            assert_eq!(board.make_move(col), None);
+        }
+        assert!(board.is_winning_move(4));
+        assert!(!board.is_winning_move(5));
+
+        // 9x7 takes 72 bits and falls back to u128
+        let rules = Rules::new(9, 7, 4).unwrap();
+        let mut board = BitBoard::with_rules(rules);
+        assert!(matches!(board.discs, Discs::Large(_)));
+        for col in [8, 0, 8, 0, 8, 0] {
+            assert_eq!(board.make_move(col), None);
+        }
+        assert_eq!(board.get_player_at(2, 8), 1);
+        assert_eq!(board.make_move(8), Some(1));
+        assert_eq!(board.mirror().get_player_at(3, 0), 1);
+        assert_eq!(BitBoard::from_board(&board.to_board(), 2), board);
+
+        // A board that fills up without a line is a draw
+        let mut board = BitBoard::with_rules(Rules::new(3, 2, 3).unwrap());
+        for col in [0, 1, 2, 0, 1] {
+            assert_eq!(board.make_move(col), None);
+        }
+        assert_eq!(board.make_move(2), Some(0));
     }
 
     #[test]
-    fn test_winning_mask_counts() {
-        // Test corners (should have fewer patterns)
-        assert_eq!(MASK_COUNTS[0], 3);  // Bottom-left corner: horizontal(1) + vertical(1) + diagonal-up-right(1)
-        assert_eq!(MASK_COUNTS[1], 4);  // Bottom-left corner: horizontal(2) + vertical(1) + diagonal-up-right(1)
-        assert_eq!(MASK_COUNTS[6], 3);  // Bottom-right corner: horizontal(1) + vertical(1)
-        assert_eq!(MASK_COUNTS[35], 3); // Top-left corner: horizontal(1) + vertical(1) + diagonal-up-left(1)
-        assert_eq!(MASK_COUNTS[41], 3); // Top-right corner: horizontal(1) + vertical(1) + diagonal-up-right(1)
-
-        // Test central positions (should have more patterns)
-        let center_pos = 3 * 7 + 3;  // Position (3,3)
-        assert_eq!(MASK_COUNTS[center_pos], 13); // Should have maximum patterns
-    }
-}
\ No newline at end of file
+    fn test_pops() {
+        let rules = Rules::STANDARD.with_popout(true);
+        let mut board = BitBoard::with_rules(rules);
+        for col in [3, 3, 3, 4] {
+            board.make_move(col);
+        }
+        // Only your own bottom discs can be popped
+        assert!(board.can_pop(3));
+        assert!(!board.can_pop(4));
+        assert!(!board.can_pop(0));
+        assert_eq!(board.get_valid_pops(), vec![3]);
+        assert_eq!(board.pop(3), None);
+        assert_eq!(board.get_height(3), 2);
+        assert_eq!((board.get_player_at(0, 3), board.get_player_at(1, 3), board.get_player_at(2, 3)), (2, 1, 0));
+        assert_eq!(board.get_current_player(), 2);
+        assert_eq!(board.get_player_at(0, 4), 2);
+
+        // Classic rules never pop
+        let mut classic = BitBoard::new();
+        classic.make_move(0);
+        classic.make_move(1);
+        assert!(!classic.can_pop(0));
+
+        // A pop that lines up four for both players wins for the player who popped: player 1 pops
+        // column 0, dropping o on top of three more o in row 0 and x on top of three more x in row 1
+        let mut cells = Board::with_rules(rules);
+        cells.cells[0] = vec![1, 2, 2, 2, 1, 1, 2];
+        cells.cells[1] = vec![2, 1, 1, 1, 2, 2, 1];
+        cells.cells[2] = vec![1, 0, 0, 0, 0, 0, 0];
+        let mut board = BitBoard::from_board(&cells, 1);
+        assert!(!board.discs.has_line(1, &rules) && !board.discs.has_line(2, &rules));
+        assert_eq!(board.pop(0), Some(1));
+
+        // Completing only the opponent's line loses
+        cells.cells[1][1] = 2;
+        let mut board = BitBoard::from_board(&cells, 1);
+        assert_eq!(board.pop(0), Some(-1));
+
+        // A full board is no draw while the next player has a disc to pop
+        let mut board = BitBoard::with_rules(Rules::new(3, 2, 3).unwrap().with_popout(true));
+        for col in [0, 1, 2, 0, 1] {
+            assert_eq!(board.make_move(col), None);
+        }
+        assert_eq!(board.make_move(2), None);
+        assert_eq!(board.get_valid_pops(), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_key_identifies_positions() {
+        // Every position up to 6 plies deep, reached through every move order
+        let mut keys = std::collections::HashMap::new();
+        let mut frontier = vec![BitBoard::new()];
+        for _ in 0..6 {
+            let mut next = Vec::new();
+            for board in frontier {
+                assert_eq!(*keys.entry(board.key()).or_insert(board), board);
+                for col in board.get_valid_moves() {
+                    let mut child = board;
+                    child.make_move(col);
+                    next.push(child);
+                }
+            }
+            frontier = next;
+        }
+        // Transpositions reach the same key
+        let mut a = BitBoard::new();
+        let mut b = BitBoard::new();
+        for col in [0, 1, 2, 3] {
+            a.make_move(col);
+        }
+        for col in [2, 3, 0, 1] {
+            b.make_move(col);
+        }
+        assert_eq!(a.key(), b.key());
+    }
+
+    #[test]
+    fn test_unmake_move_restores_position() {
+        let mut board = BitBoard::new();
+        let mut history = vec![board];
+        for col in [3, 3, 2, 4, 3, 6, 0] {
+            board.make_move(col);
+            history.push(board);
+        }
+        for col in [0, 6, 3, 4, 2, 3, 3] {
+            history.pop();
+            board.unmake_move(col);
+            assert_eq!(board, *history.last().unwrap());
+            assert_eq!(board.key(), history.last().unwrap().key());
+        }
+    }
+
+    #[test]
+    fn test_mirror() {
+        let moves = [1, 0, 2, 6, 3, 6];
+        let mut board = BitBoard::new();
+        let mut mirrored = BitBoard::new();
+        for col in moves {
+            board.make_move(col);
+            mirrored.make_move(Rules::STANDARD.mirror_column(col));
+        }
+        assert_eq!(board.mirror(), mirrored);
+        assert_eq!(board.mirror().mirror(), board);
+        let mut cells = Board::new();
+        for (i, col) in moves.into_iter().enumerate() {
+            cells.make_move(col, (i % 2 + 1) as u8);
+        }
+        assert_eq!(BitBoard::from_board(&cells.mirror(), 1), mirrored);
+        assert_eq!(board.canonical_key(), mirrored.canonical_key());
+        assert_eq!(board.canonical().0, mirrored.canonical().0);
+        assert_ne!(board.canonical().1, mirrored.canonical().1);
+
+        // Threats mirror too: player 1 can only complete the bottom row in column 4
+        assert!(board.is_winning_move(4));
+        assert!(!board.is_winning_move(2));
+        assert!(mirrored.is_winning_move(Rules::STANDARD.mirror_column(4)));
+        assert!(!mirrored.is_winning_move(Rules::STANDARD.mirror_column(2)));
+
+        // Symmetric positions are their own canonical form
+        let mut symmetric = BitBoard::new();
+        for col in [3, 3, 0, 6] {
+            symmetric.make_move(col);
+        }
+        assert_eq!(symmetric.canonical(), (symmetric, false));
+    }
+
+    #[test]
+    fn test_key_from_board_matches_moves() {
+        let mut board = Board::new();
+        let mut bitboard = BitBoard::new();
+        for (i, col) in [3, 3, 4, 0, 6, 3].into_iter().enumerate() {
+            board.make_move(col, (i % 2 + 1) as u8);
+            bitboard.make_move(col);
+        }
+        assert_eq!(BitBoard::from_board(&board, 1).key(), bitboard.key());
+    }
+}
@@ -1,15 +1,34 @@
-use crate::config::{GRID_COLS, GRID_ROWS};
+use crate::games::connect4::rules::Rules;
 
 #[derive(Clone)]
 pub struct Board {
-    pub cells: [[u8; GRID_COLS]; GRID_ROWS],
+    rules: Rules,
+    pub cells: Vec<Vec<u8>>,  // cells[row][col], row 0 at the bottom
 }
 
 impl Board {
     pub fn new() -> Self {
+        Self::with_rules(Rules::STANDARD)
+    }
+
+    pub fn with_rules(rules: Rules) -> Self {
         Self {
-            cells: [[0; GRID_COLS]; GRID_ROWS],
+            rules,
+            cells: vec![vec![0; rules.columns]; rules.rows],
+        }
+    }
+
+    pub fn get_rules(&self) -> Rules {
+        self.rules
+    }
+
+    // The board with every column `col` moved to `columns - 1 - col`
+    pub fn mirror(&self) -> Self {
+        let mut cells = self.cells.clone();
+        for row in cells.iter_mut() {
+            row.reverse();
         }
+        Self { rules: self.rules, cells }
     }
 
     pub fn get_player_number(&self, row: usize, col: usize) -> u8 {
@@ -17,7 +36,7 @@ impl Board {
     }
 
     pub fn can_play_column(&self, col: usize) -> bool {
-        col < GRID_COLS && self.cells[GRID_ROWS - 1][col] == 0
+        col < self.rules.columns && self.cells[self.rules.rows - 1][col] == 0
     }
 
     pub fn make_move(&mut self, col: usize, player_number: u8) -> Option<(usize, usize)> {
@@ -26,7 +45,7 @@ impl Board {
         }
 
         // Find the lowest empty position in the column
-        for row in 0..GRID_ROWS {
+        for row in 0..self.rules.rows {
             if self.cells[row][col] == 0 {
                 self.cells[row][col] = player_number;
                 return Some((row, col));
@@ -35,71 +54,52 @@ impl Board {
         None
     }
 
+    // Removes the top disc of `col`. Returns its position, or None if the column is empty.
+    pub fn unmake_move(&mut self, col: usize) -> Option<(usize, usize)> {
+        let row = (0..self.rules.rows).rev().find(|&row| self.cells[row][col] != 0)?;
+        self.cells[row][col] = 0;
+        Some((row, col))
+    }
+
+    // Whether the disc at (row, col) is part of a line of `connect` discs
     pub fn check_win(&self, row: usize, col: usize) -> bool {
         let player = self.cells[row][col];
         if player == 0 {
             return false;
         }
 
-        // Check horizontal
-        let mut count = 0;
-        for c in 0..GRID_COLS {
-            if self.cells[row][c] == player {
-                count += 1;
-                if count == 4 {
-                    return true;
-                }
-            } else {
-                count = 0;
-            }
-        }
-
-        // Check vertical
-        count = 0;
-        for r in 0..GRID_ROWS {
-            if self.cells[r][col] == player {
-                count += 1;
-                if count == 4 {
-                    return true;
-                }
-            } else {
-                count = 0;
-            }
-        }
-
-        // Check diagonal (down-right)
-        for r in 0..GRID_ROWS-3 {
-            for c in 0..GRID_COLS-3 {
-                if self.cells[r][c] == player &&
-                   self.cells[r+1][c+1] == player &&
-                   self.cells[r+2][c+2] == player &&
-                   self.cells[r+3][c+3] == player {
-                    return true;
-                }
-            }
-        }
+        // Horizontal, vertical and both diagonals
+        [(0, 1), (1, 0), (1, 1), (1, -1)].iter().any(|&(d_row, d_col)| {
+            1 + self.count_from(row, col, d_row, d_col, player)
+                + self.count_from(row, col, -d_row, -d_col, player) >= self.rules.connect
+        })
+    }
 
-        // Check diagonal (up-right)
-        for r in 3..GRID_ROWS {
-            for c in 0..GRID_COLS-3 {
-                if self.cells[r][c] == player &&
-                   self.cells[r-1][c+1] == player &&
-                   self.cells[r-2][c+2] == player &&
-                   self.cells[r-3][c+3] == player {
-                    return true;
-                }
-            }
+    // Discs of `player` next to (row, col) going in one direction
+    fn count_from(&self, row: usize, col: usize, d_row: isize, d_col: isize, player: u8) -> usize {
+        let mut count = 0;
+        let (mut r, mut c) = (row as isize + d_row, col as isize + d_col);
+        while r >= 0 && c >= 0 && (r as usize) < self.rules.rows && (c as usize) < self.rules.columns
+            && self.cells[r as usize][c as usize] == player {
+            count += 1;
+            r += d_row;
+            c += d_col;
         }
-
-        false
+        count
     }
 
     pub fn is_full(&self) -> bool {
-        for col in 0..GRID_COLS {
+        for col in 0..self.rules.columns {
             if self.can_play_column(col) {
                 return false;
             }
         }
         true
     }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
 }
\ No newline at end of file
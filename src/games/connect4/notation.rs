@@ -0,0 +1,348 @@
+use std::fmt;
+use crate::games::connect4::bitboard::BitBoard;
+use crate::games::connect4::board::Board;
+use crate::games::connect4::popout::{Move, PopOutBoard};
+use crate::games::connect4::rules::Rules;
+
+// Text notations for connect-four positions.
+//
+// Move sequences list the 1-indexed columns in playing order, e.g. "4453". Player 1 moves first.
+// Under PopOut rules a 'p' before a column pops the bottom disc of that column instead, e.g. "4453p4".
+//
+// Grids list the rows from top to bottom separated by '/', followed by a space and the side to move.
+// 'x' is a disc of player 1, 'o' a disc of player 2 and a digit a run of that many empty cells, so the
+// position after "4453" is "7/7/7/7/3o3/2oxx2 x". Set-up positions may have been started by either
+// player, so with as many discs of each either side can be to move. Under PopOut rules pops take
+// discs away, so the disc counts say nothing about the side to move.
+//
+// Both notations leave the board size and the connect length to the rules they are read with,
+// the standard ones unless given.
+//
+// A move sequence may end on the move that ends the game, as the records of finished games do, while
+// a grid must leave the game going. `BitBoard::get_result` tells whether a position read is over.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NotationError {
+    InvalidColumn { index: usize, found: char },   // Not a column of the board
+    ColumnFull { index: usize, column: usize },
+    CannotPop { index: usize, column: usize },     // Not PopOut, or the bottom disc is not the mover's
+    GameOver { index: usize },                     // A move after the game already ended
+    RowCount(usize),
+    RowLength { row: usize, length: usize },
+    InvalidCell { row: usize, found: char },
+    FloatingDisc { row: usize, column: usize },    // A disc above an empty cell
+    MissingSideToMove,
+    InvalidSideToMove(String),
+    TrailingText(String),                          // More after the side to move
+    ExistingLine { player: char },                 // The game would already be over
+    WrongSideToMove { expected: char },            // The side to move does not match the disc counts
+    DiscCounts { player1: usize, player2: usize }, // Counts no game can reach
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::InvalidColumn { index, found } =>
+                write!(f, "move {}: '{}' is not a column of the board", index, found),
+            NotationError::ColumnFull { index, column } =>
+                write!(f, "move {}: column {} is full", index, column),
+            NotationError::CannotPop { index, column } =>
+                write!(f, "move {}: cannot pop column {}", index, column),
+            NotationError::GameOver { index } =>
+                write!(f, "move {}: the game is already over", index),
+            NotationError::RowCount(count) =>
+                write!(f, "found {} rows, not as many as the board has", count),
+            NotationError::RowLength { row, length } =>
+                write!(f, "row {} covers {} cells, not the width of the board", row, length),
+            NotationError::InvalidCell { row, found } =>
+                write!(f, "row {}: '{}' is neither 'x', 'o' nor a digit between 1 and 9", row, found),
+            NotationError::FloatingDisc { row, column } =>
+                write!(f, "row {}: the disc in column {} has an empty cell below it", row, column),
+            NotationError::MissingSideToMove =>
+                write!(f, "missing side to move after the rows"),
+            NotationError::InvalidSideToMove(found) =>
+                write!(f, "side to move must be 'x' or 'o', found '{}'", found),
+            NotationError::TrailingText(found) =>
+                write!(f, "unexpected '{}' after the side to move", found),
+            NotationError::ExistingLine { player } =>
+                write!(f, "'{}' already has a winning line", player),
+            NotationError::WrongSideToMove { expected } =>
+                write!(f, "the disc counts put '{}' to move", expected),
+            NotationError::DiscCounts { player1, player2 } =>
+                write!(f, "{} discs of 'x' and {} of 'o' cannot come from alternating moves", player1, player2),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+// Plays a move sequence such as "4453" from the empty board
+pub fn parse_moves(sequence: &str) -> Result<BitBoard, NotationError> {
+    parse_moves_with(Rules::STANDARD, sequence)
+}
+
+// Plays a move sequence from the empty board of `rules`
+pub fn parse_moves_with(rules: Rules, sequence: &str) -> Result<BitBoard, NotationError> {
+    play_moves(BitBoard::with_rules(rules), sequence)
+}
+
+// Plays a move sequence from `board`
+pub fn play_moves(board: BitBoard, sequence: &str) -> Result<BitBoard, NotationError> {
+    read_moves(board, sequence).map(|(game, _)| *game.get_position())
+}
+
+// The moves of a sequence played from `board`, checked like `play_moves` does
+pub fn parse_move_list(board: BitBoard, sequence: &str) -> Result<Vec<Move>, NotationError> {
+    read_moves(board, sequence).map(|(_, moves)| moves)
+}
+
+fn read_moves(board: BitBoard, sequence: &str) -> Result<(PopOutBoard, Vec<Move>), NotationError> {
+    let mut game = PopOutBoard::new(board);
+    let mut moves = Vec::new();
+    let mut game_over = false;
+    let mut chars = sequence.trim().chars();
+    while let Some(found) = chars.next() {
+        let index = moves.len() + 1;
+        let (pop, found) = if found == 'p' { (true, chars.next().unwrap_or(found)) } else { (false, found) };
+        let column = match found.to_digit(10) {
+            Some(digit) if (1..=board.get_rules().columns as u32).contains(&digit) => digit as usize,
+            _ => return Err(NotationError::InvalidColumn { index, found }),
+        };
+        if game_over {
+            return Err(NotationError::GameOver { index });
+        }
+        let mv = if pop { Move::Pop(column - 1) } else { Move::Drop(column - 1) };
+        if !game.is_valid_move(mv) {
+            return Err(if pop { NotationError::CannotPop { index, column } } else { NotationError::ColumnFull { index, column } });
+        }
+        game_over = game.make_move(mv).is_some();
+        moves.push(mv);
+    }
+    Ok((game, moves))
+}
+
+// Reads either notation: a grid if the text contains '/', a move sequence otherwise
+pub fn parse_position(text: &str) -> Result<BitBoard, NotationError> {
+    parse_position_with(Rules::STANDARD, text)
+}
+
+// Reads either notation for a board of `rules`
+pub fn parse_position_with(rules: Rules, text: &str) -> Result<BitBoard, NotationError> {
+    if text.contains('/') { parse_grid_with(rules, text) } else { parse_moves_with(rules, text) }
+}
+
+// Writes 0-indexed columns as a move sequence
+pub fn format_moves(moves: &[usize]) -> String {
+    moves.iter()
+        .map(|&col| char::from_digit(col as u32 + 1, 10).unwrap())
+        .collect()
+}
+
+// Writes moves as a move sequence, with pops
+pub fn format_move_list(moves: &[Move]) -> String {
+    moves.iter().map(|mv| mv.to_string()).collect()
+}
+
+// Reads a grid such as "7/7/7/7/3o3/2oxx2 x"
+pub fn parse_grid(grid: &str) -> Result<BitBoard, NotationError> {
+    parse_grid_with(Rules::STANDARD, grid)
+}
+
+// Reads a grid of a board of `rules`
+pub fn parse_grid_with(rules: Rules, grid: &str) -> Result<BitBoard, NotationError> {
+    let (width, height) = (rules.columns, rules.rows);
+    let mut parts = grid.split_whitespace();
+    let rows_part = parts.next().unwrap_or("");
+    let side = parts.next().ok_or(NotationError::MissingSideToMove)?;
+    if let Some(extra) = parts.next() {
+        return Err(NotationError::TrailingText(extra.to_string()));
+    }
+
+    let rows: Vec<&str> = rows_part.split('/').collect();
+    if rows.len() != height {
+        return Err(NotationError::RowCount(rows.len()));
+    }
+
+    let mut board = Board::with_rules(rules);  // Row 0 is the bottom row
+    let mut discs = [0; 2];
+    for (i, text) in rows.iter().enumerate() {
+        let row_number = i + 1;
+        let row = height - 1 - i;
+        let mut col = 0;
+        for found in text.chars() {
+            let run = match found {
+                'x' | 'o' => 1,
+                '1'..='9' => found.to_digit(10).unwrap() as usize,
+                _ => return Err(NotationError::InvalidCell { row: row_number, found }),
+            };
+            if col + run > width {
+                return Err(NotationError::RowLength { row: row_number, length: col + run });
+            }
+            if found == 'x' || found == 'o' {
+                let player = if found == 'x' { 1 } else { 2 };
+                board.cells[row][col] = player;
+                discs[player as usize - 1] += 1;
+            }
+            col += run;
+        }
+        if col != width {
+            return Err(NotationError::RowLength { row: row_number, length: col });
+        }
+    }
+
+    for row in 1..height {
+        for col in 0..width {
+            if board.cells[row][col] != 0 && board.cells[row - 1][col] == 0 {
+                return Err(NotationError::FloatingDisc { row: height - row, column: col + 1 });
+            }
+        }
+    }
+
+    // The player with fewer discs is to move, unless pops have changed the counts
+    let expected = if rules.popout || discs[0] == discs[1] {
+        None
+    } else if discs[0] == discs[1] + 1 {
+        Some('o')
+    } else if discs[1] == discs[0] + 1 {
+        Some('x')
+    } else {
+        return Err(NotationError::DiscCounts { player1: discs[0], player2: discs[1] });
+    };
+    if side != "x" && side != "o" {
+        return Err(NotationError::InvalidSideToMove(side.to_string()));
+    }
+    if let Some(expected) = expected.filter(|&expected| !side.starts_with(expected)) {
+        return Err(NotationError::WrongSideToMove { expected });
+    }
+    let current_player = if side == "x" { 1 } else { 2 };
+    let position = BitBoard::from_board(&board, current_player);
+    if let Some(player) = position.get_winner() {
+        return Err(NotationError::ExistingLine { player: if player == 1 { 'x' } else { 'o' } });
+    }
+    Ok(position)
+}
+
+// Writes the position as a grid
+pub fn format_grid(board: &BitBoard) -> String {
+    let rules = board.get_rules();
+    let mut rows = Vec::with_capacity(rules.rows);
+    for row in (0..rules.rows).rev() {
+        let mut text = String::new();
+        let mut empty = 0;
+        for col in 0..rules.columns {
+            let player = board.get_player_at(row, col);
+            if player == 0 {
+                empty += 1;
+                continue;
+            }
+            if empty > 0 {
+                text.push_str(&empty.to_string());
+                empty = 0;
+            }
+            text.push(if player == 1 { 'x' } else { 'o' });
+        }
+        if empty > 0 {
+            text.push_str(&empty.to_string());
+        }
+        rows.push(text);
+    }
+    let side = if board.get_current_player() == 1 { 'x' } else { 'o' };
+    format!("{} {}", rows.join("/"), side)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moves_round_trip() {
+        let board = parse_moves("4453").unwrap();
+        assert_eq!(format_grid(&board), "7/7/7/7/3o3/2oxx2 x");
+        assert_eq!(format_moves(&[3, 3, 4, 2]), "4453");
+        assert_eq!(parse_moves(&format_moves(&[3, 3, 4, 2])).unwrap(), board);
+        assert_eq!(parse_moves("").unwrap(), BitBoard::new());
+        assert_eq!(parse_position("7/7/7/7/3o3/2oxx2 x").unwrap(), board);
+        assert_eq!(parse_position("4453").unwrap(), board);
+    }
+
+    #[test]
+    fn test_grid_round_trip() {
+        for sequence in ["", "4", "4453", "2252576253462244111563365343671351441"] {
+            let board = parse_moves(sequence).unwrap();
+            let grid = format_grid(&board);
+            assert_eq!(parse_grid(&grid).unwrap(), board, "{}", grid);
+
+            // Through `Board` as well
+            let cells = board.to_board();
+            assert_eq!(BitBoard::from_board(&cells, board.get_current_player()), board);
+        }
+    }
+
+    #[test]
+    fn test_move_errors() {
+        assert_eq!(parse_moves("448"), Err(NotationError::InvalidColumn { index: 3, found: '8' }));
+        assert_eq!(parse_moves("40"), Err(NotationError::InvalidColumn { index: 2, found: '0' }));
+        assert_eq!(parse_moves("1111111"), Err(NotationError::ColumnFull { index: 7, column: 1 }));
+        assert_eq!(parse_moves("12121215"), Err(NotationError::GameOver { index: 8 }));
+
+        // A sequence may end the game, and the position says so
+        assert_eq!(parse_moves("1212121").unwrap().get_result(), Some(1));
+        assert_eq!(parse_moves("121212").unwrap().get_result(), None);
+    }
+
+    #[test]
+    fn test_grid_errors() {
+        assert_eq!(parse_grid("7/7/7/7/7/7"), Err(NotationError::MissingSideToMove));
+        assert_eq!(parse_grid("7/7/7/7/7 x"), Err(NotationError::RowCount(5)));
+        assert_eq!(parse_grid("7/7/7/7/7/6 x"), Err(NotationError::RowLength { row: 6, length: 6 }));
+        assert_eq!(parse_grid("7/7/7/7/7/x7 o"), Err(NotationError::RowLength { row: 6, length: 8 }));
+        assert_eq!(parse_grid("7/7/7/7/7/3a3 x"), Err(NotationError::InvalidCell { row: 6, found: 'a' }));
+        assert_eq!(parse_grid("7/7/7/7/x6/6o x"), Err(NotationError::FloatingDisc { row: 5, column: 1 }));
+        assert_eq!(parse_grid("7/7/7/7/7/3x3 x"), Err(NotationError::WrongSideToMove { expected: 'o' }));
+        assert_eq!(parse_grid("7/7/7/7/7/2xx3 o"), Err(NotationError::DiscCounts { player1: 2, player2: 0 }));
+        assert_eq!(parse_grid("7/7/7/7/7/7 y"), Err(NotationError::InvalidSideToMove("y".to_string())));
+        assert_eq!(parse_grid("7/7/7/7/7/3o3 o"), Err(NotationError::WrongSideToMove { expected: 'x' }));
+        assert_eq!(parse_grid("7/7/7/7/7/7 x 4"), Err(NotationError::TrailingText("4".to_string())));
+        assert_eq!(parse_grid("7/7/7/7/ooo4/xxxx3 o"), Err(NotationError::ExistingLine { player: 'x' }));
+    }
+
+    #[test]
+    fn test_positions_started_by_player_2() {
+        let board = parse_grid("7/7/7/7/7/3o3 x").unwrap();
+        assert_eq!(board.get_current_player(), 1);
+        assert_eq!(parse_grid("7/7/7/7/7/2ox3 o").unwrap().get_current_player(), 2);
+        assert_eq!(format_grid(&board), "7/7/7/7/7/3o3 x");
+
+        let after = play_moves(board, "4").unwrap();
+        assert_eq!(format_grid(&after), "7/7/7/7/3x3/3o3 o");
+        assert_eq!(play_moves(board, "9"), Err(NotationError::InvalidColumn { index: 1, found: '9' }));
+    }
+
+    #[test]
+    fn test_other_sizes() {
+        let rules = Rules::new(9, 7, 5).unwrap();
+        let board = parse_moves_with(rules, "5598").unwrap();
+        assert_eq!(format_grid(&board), "9/9/9/9/9/4o4/4x2ox x");
+        assert_eq!(parse_position_with(rules, &format_grid(&board)).unwrap(), board);
+        assert_eq!(parse_grid("9/9/9/9/9/4o4/4x2ox x"), Err(NotationError::RowCount(7)));
+
+        let rules = Rules::new(6, 5, 3).unwrap();
+        assert_eq!(parse_moves_with(rules, "17"), Err(NotationError::InvalidColumn { index: 2, found: '7' }));
+        assert_eq!(parse_grid_with(rules, "6/6/6/6/2x3 o").unwrap(), parse_moves_with(rules, "3").unwrap());
+        assert_eq!(parse_grid_with(rules, "6/6/6/6/7 x"), Err(NotationError::RowLength { row: 5, length: 7 }));
+    }
+
+    #[test]
+    fn test_pops() {
+        let start = BitBoard::with_rules(Rules::STANDARD.with_popout(true));
+        let moves = parse_move_list(start, "44p4").unwrap();
+        assert_eq!(moves, vec![Move::Drop(3), Move::Drop(3), Move::Pop(3)]);
+        assert_eq!(format_move_list(&moves), "44p4");
+        assert_eq!(format_grid(&play_moves(start, "44p4").unwrap()), "7/7/7/7/7/3o3 o");
+        assert_eq!(parse_grid_with(start.get_rules(), "7/7/7/7/7/3o3 o"), play_moves(start, "44p4"));
+
+        assert_eq!(play_moves(start, "4p4"), Err(NotationError::CannotPop { index: 2, column: 4 }));
+        assert_eq!(play_moves(start, "4p"), Err(NotationError::InvalidColumn { index: 2, found: 'p' }));
+        assert_eq!(parse_moves("44p4"), Err(NotationError::CannotPop { index: 3, column: 4 }));
+    }
+}
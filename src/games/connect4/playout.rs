@@ -0,0 +1,61 @@
+use crate::games::connect4::bitboard::BitBoard;
+use crate::mcts::playout::{PlayoutPolicy, RandomPlayout};
+
+// Takes an immediate win if there is one, otherwise blocks the opponent's immediate win,
+// otherwise plays a random move
+pub struct TacticalPlayout;
+
+impl PlayoutPolicy<BitBoard> for TacticalPlayout {
+    fn choose_move(&self, board: &BitBoard, valid_moves: &[usize]) -> usize {
+        if let Some(&col) = valid_moves.iter().find(|&&col| board.is_winning_move(col)) {
+            return col;
+        }
+
+        let opponent = 3 - board.get_current_player();
+        if let Some(&col) = valid_moves.iter().find(|&&col| board.is_winning_move_for(col, opponent)) {
+            return col;
+        }
+
+        RandomPlayout.choose_move(board, valid_moves)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcts::mcts::MCTS;
+
+    fn board_after(moves: &[usize]) -> BitBoard {
+        let mut board = BitBoard::new();
+        for &col in moves {
+            board.make_move(col);
+        }
+        board
+    }
+
+    #[test]
+    fn test_takes_win_before_blocking() {
+        // Player 1 can win on the bottom row, player 2 threatens to win in column 6
+        let board = board_after(&[0, 6, 1, 6, 2, 6]);
+        let valid_moves = board.get_valid_moves();
+        for _ in 0..20 {
+            assert_eq!(TacticalPlayout.choose_move(&board, &valid_moves), 3);
+        }
+    }
+
+    #[test]
+    fn test_blocks_opponent_win() {
+        let board = board_after(&[5, 0, 6, 0, 6, 0]);
+        let valid_moves = board.get_valid_moves();
+        for _ in 0..20 {
+            assert_eq!(TacticalPlayout.choose_move(&board, &valid_moves), 0);
+        }
+    }
+
+    #[test]
+    fn test_mcts_with_tactical_playouts() {
+        let mcts = MCTS::new(1.414, 2, 300, 10).with_playout_policy(TacticalPlayout);
+        let board = board_after(&[5, 0, 6, 0, 6, 0]);
+        assert_eq!(mcts.search(board), 0, "Failed to find blocking move");
+    }
+}
@@ -0,0 +1,187 @@
+use std::fmt;
+use crate::games::Game;
+use crate::games::connect4::bitboard::BitBoard;
+
+// Connect four with PopOut moves.
+//
+// Besides dropping a disc, a player may pop one of their own discs off the bottom of a column.
+// The game ends when a move completes a line, when the player to move has no move left, or as a
+// draw when a position comes up for the third time, which pops make possible. With classic rules
+// there are only drops and no position repeats, so the game plays exactly like `BitBoard`.
+
+// One turn: a disc dropped into a column or popped off its bottom, 0-indexed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Move {
+    Drop(usize),
+    Pop(usize),
+}
+
+impl Move {
+    pub fn column(&self) -> usize {
+        match *self {
+            Move::Drop(col) | Move::Pop(col) => col,
+        }
+    }
+}
+
+// Columns numbered from 1 as in move sequences, with pops marked: "4" drops into column 4, "p4" pops it
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Move::Drop(col) => write!(f, "{}", col + 1),
+            Move::Pop(col) => write!(f, "p{}", col + 1),
+        }
+    }
+}
+
+// Number of times a position may come up before the game is drawn
+const REPETITIONS: usize = 3;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PopOutBoard {
+    position: BitBoard,
+    history: Vec<(u64, u8)>,  // Key and player to move of every position so far, the current one last
+}
+
+impl PopOutBoard {
+    // A game starting from `position`, under the rules of the position
+    pub fn new(position: BitBoard) -> Self {
+        Self {
+            history: vec![(position.key(), position.get_current_player())],
+            position,
+        }
+    }
+
+    pub fn get_position(&self) -> &BitBoard {
+        &self.position
+    }
+
+    pub fn get_current_player(&self) -> u8 {
+        self.position.get_current_player()
+    }
+
+    pub fn is_valid_move(&self, mv: Move) -> bool {
+        match mv {
+            Move::Drop(col) => self.position.is_valid_move(col),
+            Move::Pop(col) => self.position.can_pop(col),
+        }
+    }
+
+    // Drops first, then pops
+    pub fn get_valid_moves(&self) -> Vec<Move> {
+        let drops = self.position.get_valid_moves().into_iter().map(Move::Drop);
+        drops.chain(self.position.get_valid_pops().into_iter().map(Move::Pop)).collect()
+    }
+
+    // Plays `mv`, which must be valid, and returns the result from player 1's point of view if the game ended
+    pub fn make_move(&mut self, mv: Move) -> Option<i32> {
+        let result = match mv {
+            Move::Drop(col) => self.position.make_move(col),
+            Move::Pop(col) => self.position.pop(col),
+        };
+        if result.is_some() {
+            return result;
+        }
+        let entry = (self.position.key(), self.position.get_current_player());
+        self.history.push(entry);
+        if self.history.iter().filter(|&&seen| seen == entry).count() >= REPETITIONS {
+            return Some(0);
+        }
+        None
+    }
+}
+
+impl Game for PopOutBoard {
+    type Move = Move;
+    type Outcome = i32;
+
+    fn get_current_player(&self) -> u8 {
+        PopOutBoard::get_current_player(self)
+    }
+
+    fn get_valid_moves(&self) -> Vec<Move> {
+        PopOutBoard::get_valid_moves(self)
+    }
+
+    fn is_valid_move(&self, mv: Move) -> bool {
+        PopOutBoard::is_valid_move(self, mv)
+    }
+
+    fn make_move(&mut self, mv: Move) -> Option<i32> {
+        PopOutBoard::make_move(self, mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::connect4::board::Board;
+    use crate::games::connect4::rules::Rules;
+    use crate::mcts::mcts::MCTS;
+
+    fn popout() -> PopOutBoard {
+        PopOutBoard::new(BitBoard::with_rules(Rules::STANDARD.with_popout(true)))
+    }
+
+    #[test]
+    fn test_moves() {
+        let mut game = popout();
+        assert_eq!(game.get_valid_moves().len(), 7);
+        game.make_move(Move::Drop(3));
+        game.make_move(Move::Drop(4));
+        assert_eq!(game.get_valid_moves(), [0, 1, 2, 3, 4, 5, 6].map(Move::Drop).into_iter().chain([Move::Pop(3)]).collect::<Vec<_>>());
+        assert!(!game.is_valid_move(Move::Pop(4)));
+        assert_eq!(Move::Pop(3).to_string(), "p4");
+        assert_eq!(Move::Drop(3).to_string(), "4");
+
+        // Classic rules only drop
+        let mut classic = PopOutBoard::new(BitBoard::new());
+        classic.make_move(Move::Drop(3));
+        classic.make_move(Move::Drop(4));
+        assert!(classic.get_valid_moves().iter().all(|mv| matches!(mv, Move::Drop(_))));
+    }
+
+    #[test]
+    fn test_third_repetition_is_a_draw() {
+        // Both players drop a disc and pop it again, so the empty board comes back every four moves
+        let mut game = popout();
+        let cycle = [Move::Drop(0), Move::Drop(6), Move::Pop(0), Move::Pop(6)];
+        for mv in cycle {
+            assert_eq!(game.make_move(mv), None);
+        }
+        for &mv in &cycle[..3] {
+            assert_eq!(game.make_move(mv), None);
+        }
+        assert_eq!(game.make_move(cycle[3]), Some(0));
+    }
+
+    // Repetitions end games that would otherwise pop forever
+    #[test]
+    fn test_random_games_end() {
+        for _ in 0..100 {
+            let mut game = popout();
+            loop {
+                let moves = game.get_valid_moves();
+                if game.make_move(moves[fastrand::usize(..moves.len())]).is_some() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mcts_finds_winning_pop() {
+        // Popping the bottom x of column 1 brings the x above it down next to three more x.
+        // No drop wins.
+        let rules = Rules::STANDARD.with_popout(true);
+        let mut cells = Board::with_rules(rules);
+        for (col, discs) in [vec![1, 2, 1], vec![2, 1], vec![1, 1], vec![2, 1], vec![], vec![2], vec![2, 2]].into_iter().enumerate() {
+            for (row, player) in discs.into_iter().enumerate() {
+                cells.cells[row][col] = player;
+            }
+        }
+        let game = PopOutBoard::new(BitBoard::from_board(&cells, 1));
+        let mcts = MCTS::new(1.414, 1, 300, 10);
+        assert_eq!(mcts.search(game), Move::Pop(0));
+    }
+}
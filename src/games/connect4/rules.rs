@@ -0,0 +1,140 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::config::{GRID_COLS, GRID_ROWS};
+
+// Board size, the number of discs in a row that wins and the variant, chosen when a game starts.
+//
+// In the PopOut variant a turn can also pop one of your own discs off the bottom of a column,
+// which lets everything above it fall one row.
+//
+// Bitboards number the cells column by column with an empty sentinel row on top of every column,
+// so a board takes `columns * (rows + 1)` bits. Boards of up to 64 bits use `u64` bit sets, and
+// larger ones fall back to `u128`, which caps the number of rows.
+
+pub const MAX_COLUMNS: usize = 9;  // Move sequences name columns with a single digit
+pub const MAX_ROWS: usize = 12;    // 9 columns of 12 rows and a sentinel fit in 128 bits
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Rules {
+    pub columns: usize,
+    pub rows: usize,
+    pub connect: usize,  // Discs in a row needed to win
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub popout: bool,    // Whether discs can be popped off the bottom
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RulesError {
+    Columns(usize),
+    Rows(usize),
+    Connect { connect: usize, longest: usize },  // No line on the board is long enough, or too short to mean anything
+    Size(String),                                // Not written as <columns>x<rows>
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RulesError::Columns(columns) =>
+                write!(f, "{} columns is not between 2 and {}", columns, MAX_COLUMNS),
+            RulesError::Rows(rows) =>
+                write!(f, "{} rows is not between 2 and {}", rows, MAX_ROWS),
+            RulesError::Connect { connect, longest } =>
+                write!(f, "connect {} is not between 2 and the longest line of the board, {}", connect, longest),
+            RulesError::Size(size) =>
+                write!(f, "'{}' is not a board size such as 7x6", size),
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+impl Rules {
+    // Connect four on seven columns of six rows
+    pub const STANDARD: Rules = Rules { columns: GRID_COLS, rows: GRID_ROWS, connect: 4, popout: false };
+
+    pub fn new(columns: usize, rows: usize, connect: usize) -> Result<Self, RulesError> {
+        if !(2..=MAX_COLUMNS).contains(&columns) {
+            return Err(RulesError::Columns(columns));
+        }
+        if !(2..=MAX_ROWS).contains(&rows) {
+            return Err(RulesError::Rows(rows));
+        }
+        let longest = columns.max(rows);
+        if !(2..=longest).contains(&connect) {
+            return Err(RulesError::Connect { connect, longest });
+        }
+        Ok(Self { columns, rows, connect, popout: false })
+    }
+
+    // The same board, played as PopOut or not
+    pub fn with_popout(mut self, popout: bool) -> Self {
+        self.popout = popout;
+        self
+    }
+
+    // Reads a size such as "9x7" (columns first)
+    pub fn parse(size: &str, connect: usize) -> Result<Self, RulesError> {
+        let (columns, rows) = size.split_once('x')
+            .and_then(|(columns, rows)| Some((columns.parse().ok()?, rows.parse().ok()?)))
+            .ok_or_else(|| RulesError::Size(size.to_string()))?;
+        Self::new(columns, rows, connect)
+    }
+
+    pub fn is_standard(&self) -> bool {
+        *self == Self::STANDARD
+    }
+
+    pub fn cells(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    // Bits of a bitboard with a sentinel row on top of every column
+    pub fn layout_bits(&self) -> usize {
+        self.columns * (self.rows + 1)
+    }
+
+    // Whether bitboards of this size use the fast `u64` bit sets
+    pub fn fits_in_64_bits(&self) -> bool {
+        self.layout_bits() <= 64
+    }
+
+    // Column a move lands in on the mirrored board
+    pub fn mirror_column(&self, col: usize) -> usize {
+        self.columns - 1 - col
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{} connect {}", self.columns, self.rows, self.connect)?;
+        if self.popout {
+            write!(f, " popout")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sizes() {
+        assert_eq!(Rules::parse("7x6", 4), Ok(Rules::STANDARD));
+        assert!(Rules::parse("8x7", 4).unwrap().fits_in_64_bits());
+        assert!(!Rules::parse("9x7", 5).unwrap().fits_in_64_bits());
+        assert_eq!(Rules::parse("7by6", 4), Err(RulesError::Size("7by6".to_string())));
+        assert_eq!(Rules::parse("10x6", 4), Err(RulesError::Columns(10)));
+        assert_eq!(Rules::parse("7x13", 4), Err(RulesError::Rows(13)));
+        assert_eq!(Rules::parse("4x3", 5), Err(RulesError::Connect { connect: 5, longest: 4 }));
+        assert!(Rules::parse("9x12", 4).unwrap().layout_bits() <= 128);
+        assert_eq!(Rules::STANDARD.with_popout(true).to_string(), "7x6 connect 4 popout");
+        assert!(!Rules::STANDARD.with_popout(true).is_standard());
+    }
+}
@@ -0,0 +1,561 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::games::connect4::bitboard::{reverse_groups, BitBoard};
+use crate::games::connect4::rules::Rules;
+use crate::transposition::{Replacement, TranspositionTable};
+
+// Exact connect-k solver: negamax with alpha-beta pruning, a transposition table,
+// center-first move ordering and a null-window search that narrows down the exact score.
+//
+// Internally it uses a column-major bitboard with an empty sentinel row on top of every
+// column (bit `col * (rows + 1) + row`), so alignments can be found with plain shifts.
+// It handles the boards whose layout fits in a `u64`.
+
+// Number of entries in the transposition table, a prime so that keys spread evenly
+const TABLE_SIZE: usize = 2_097_143;
+
+// Masks and move ordering for one board size
+#[derive(Clone, PartialEq)]
+struct Layout {
+    rules: Rules,
+    cells: i32,
+    bottom_mask: u64,          // Bottom cell of every column
+    board_mask: u64,           // Every cell of the board
+    column_order: Vec<usize>,  // Columns ordered from the center outwards
+}
+
+impl Layout {
+    fn new(rules: Rules) -> Self {
+        let bottom_mask = (0..rules.columns).fold(0, |mask, col| mask | 1 << (col * (rules.rows + 1)));
+        Self {
+            rules,
+            cells: rules.cells() as i32,
+            bottom_mask,
+            board_mask: bottom_mask * ((1 << rules.rows) - 1),
+            column_order: center_first(rules.columns),
+        }
+    }
+
+    fn top_mask_col(&self, col: usize) -> u64 {
+        1 << (self.rules.rows - 1 + col * (self.rules.rows + 1))
+    }
+
+    fn bottom_mask_col(&self, col: usize) -> u64 {
+        1 << (col * (self.rules.rows + 1))
+    }
+
+    fn column_mask(&self, col: usize) -> u64 {
+        ((1 << self.rules.rows) - 1) << (col * (self.rules.rows + 1))
+    }
+
+    // Empty cells where `position` would complete an alignment of `connect` discs
+    fn winning_position(&self, position: u64, mask: u64) -> u64 {
+        // A constant length lets the compiler unroll the loops over the line
+        let r = match self.rules.connect {
+            2 => alignments::<2>(position, self.rules.rows),
+            3 => alignments::<3>(position, self.rules.rows),
+            4 => alignments::<4>(position, self.rules.rows),
+            5 => alignments::<5>(position, self.rules.rows),
+            6 => alignments::<6>(position, self.rules.rows),
+            7 => alignments::<7>(position, self.rules.rows),
+            8 => alignments::<8>(position, self.rules.rows),
+            9 => alignments::<9>(position, self.rules.rows),
+            10 => alignments::<10>(position, self.rules.rows),
+            11 => alignments::<11>(position, self.rules.rows),
+            _ => alignments::<12>(position, self.rules.rows),
+        };
+        r & (self.board_mask ^ mask)
+    }
+}
+
+// Cells, empty or not, where `position` would complete an alignment of `K` discs on a board of `rows` rows
+fn alignments<const K: usize>(position: u64, rows: usize) -> u64 {
+    let mut r = 0;
+
+    // Vertical, horizontal and both diagonals. A cell wins when it joins `before` discs on
+    // one side with `K - 1 - before` on the other.
+    for shift in [1, rows + 1, rows, rows + 2] {
+        // Cells with `i` discs in a row right behind or ahead of them
+        let mut behind = [!0u64; K];
+        let mut ahead = [!0u64; K];
+        for i in 1..K {
+            behind[i] = behind[i - 1] & shift_left(position, i * shift);
+            ahead[i] = ahead[i - 1] & shift_right(position, i * shift);
+        }
+        for before in 0..K {
+            r |= behind[before] & ahead[K - 1 - before];
+        }
+    }
+    r
+}
+
+fn shift_left(bits: u64, amount: usize) -> u64 {
+    bits.checked_shl(amount as u32).unwrap_or(0)
+}
+
+fn shift_right(bits: u64, amount: usize) -> u64 {
+    bits.checked_shr(amount as u32).unwrap_or(0)
+}
+
+// Exact value of a position for the player to move, with perfect play from both sides
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Solution {
+    Win(u32),   // The player to move wins, the winning disc is dropped this many plies from now
+    Loss(u32),  // The opponent wins, its winning disc is dropped this many plies from now
+    Draw(u32),  // Nobody wins, the board is full after this many plies
+}
+
+impl Solution {
+    // Converts a solver score for a position with `moves` discs played on a board of `cells` cells.
+    // A score is half the cells plus one minus the number of discs of the winner (22 minus them on
+    // the standard board), positive when the player to move wins.
+    fn from_score(score: i32, moves: u32, cells: i32) -> Self {
+        let own_discs = moves / 2;
+        let opponent_discs = moves - own_discs;
+        if score > 0 {
+            let final_discs = (cells / 2 + 1 - score) as u32;
+            Solution::Win(2 * (final_discs - own_discs) - 1)
+        } else if score < 0 {
+            let final_discs = (cells / 2 + 1 + score) as u32;
+            Solution::Loss(2 * (final_discs - opponent_discs))
+        } else {
+            Solution::Draw(cells as u32 - moves)
+        }
+    }
+
+    pub fn plies(&self) -> u32 {
+        match *self {
+            Solution::Win(plies) | Solution::Loss(plies) | Solution::Draw(plies) => plies,
+        }
+    }
+
+    pub fn is_win(&self) -> bool {
+        matches!(self, Solution::Win(_))
+    }
+
+    pub fn is_loss(&self) -> bool {
+        matches!(self, Solution::Loss(_))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Position {
+    current_position: u64,  // Discs of the player to move
+    mask: u64,              // All discs
+    moves: u32,
+}
+
+impl Position {
+    fn from_bitboard(board: &BitBoard) -> Self {
+        let mut position = Position { current_position: 0, mask: 0, moves: 0 };
+        let current_player = board.get_current_player();
+        let rows = board.get_rules().rows;
+        for col in 0..board.get_rules().columns {
+            for row in 0..board.get_height(col) {
+                let bit = 1u64 << (col * (rows + 1) + row);
+                position.mask |= bit;
+                if board.get_player_at(row, col) == current_player {
+                    position.current_position |= bit;
+                }
+                position.moves += 1;
+            }
+        }
+        position
+    }
+
+    fn can_play(&self, col: usize, layout: &Layout) -> bool {
+        self.mask & layout.top_mask_col(col) == 0
+    }
+
+    fn play(&mut self, move_bit: u64) {
+        self.current_position ^= self.mask;
+        self.mask |= move_bit;
+        self.moves += 1;
+    }
+
+    fn play_col(&mut self, col: usize, layout: &Layout) {
+        self.play((self.mask + layout.bottom_mask_col(col)) & layout.column_mask(col));
+    }
+
+    fn is_winning_move(&self, col: usize, layout: &Layout) -> bool {
+        self.winning_position(layout) & self.possible(layout) & layout.column_mask(col) != 0
+    }
+
+    fn can_win_next(&self, layout: &Layout) -> bool {
+        self.winning_position(layout) & self.possible(layout) != 0
+    }
+
+    // Key shared with the mirrored position, which has the same score
+    fn key(&self, layout: &Layout) -> u64 {
+        let key = self.current_position + self.mask;
+        key.min(reverse_groups(key, layout.rules.rows + 1, layout.rules.columns))
+    }
+
+    // Playable moves that do not hand the opponent an immediate win
+    fn possible_non_losing_moves(&self, layout: &Layout) -> u64 {
+        let mut possible_mask = self.possible(layout);
+        let opponent_win = self.opponent_winning_position(layout);
+        let forced_moves = possible_mask & opponent_win;
+        if forced_moves != 0 {
+            if forced_moves & (forced_moves - 1) != 0 {
+                return 0;  // The opponent has two immediate wins, we cannot block both
+            }
+            possible_mask = forced_moves;
+        }
+        possible_mask & !(opponent_win >> 1)  // Do not play directly below an opponent's winning spot
+    }
+
+    // Number of winning spots the player to move has after playing `move_bit`
+    fn move_score(&self, move_bit: u64, layout: &Layout) -> u32 {
+        layout.winning_position(self.current_position | move_bit, self.mask).count_ones()
+    }
+
+    fn winning_position(&self, layout: &Layout) -> u64 {
+        layout.winning_position(self.current_position, self.mask)
+    }
+
+    fn opponent_winning_position(&self, layout: &Layout) -> u64 {
+        layout.winning_position(self.current_position ^ self.mask, self.mask)
+    }
+
+    fn possible(&self, layout: &Layout) -> u64 {
+        (self.mask + layout.bottom_mask) & layout.board_mask
+    }
+}
+
+pub struct Solver {
+    table: TranspositionTable<i8>,  // Upper bounds of position scores
+    layout: Layout,                 // Board size of the positions in the table
+    explored_nodes: u64,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self {
+            table: TranspositionTable::new(TABLE_SIZE, Replacement::Always),
+            layout: Layout::new(Rules::STANDARD),
+            explored_nodes: 0,
+        }
+    }
+
+    // Whether positions under `rules` can be solved: their bitboards must fit in 64 bits, and the
+    // scores assume both players have dropped as many discs once the board is full. PopOut's pops are not searched.
+    pub fn supports(rules: &Rules) -> bool {
+        rules.fits_in_64_bits() && rules.cells().is_multiple_of(2) && !rules.popout
+    }
+
+    // Number of positions searched since the solver was created
+    pub fn get_explored_nodes(&self) -> u64 {
+        self.explored_nodes
+    }
+
+    // The solver only handles games that are still going: `board` must have no line yet.
+    // Check `BitBoard::get_winner` first for positions that may be over.
+    pub fn solve(&mut self, board: &BitBoard) -> Solution {
+        let position = self.prepare(board);
+        let score = self.solve_position(&position, &AtomicBool::new(false)).unwrap();
+        Solution::from_score(score, position.moves, self.layout.cells)
+    }
+
+    // Solution of every column for the player to move, or None where the column is full
+    pub fn analyze(&mut self, board: &BitBoard) -> Vec<Option<Solution>> {
+        self.analyze_until(board, &AtomicBool::new(false)).unwrap()
+    }
+
+    // Like `analyze`, but gives up and returns None once `stop` is set, e.g. from another thread
+    pub fn analyze_until(&mut self, board: &BitBoard, stop: &AtomicBool) -> Option<Vec<Option<Solution>>> {
+        let position = self.prepare(board);
+        let cells = self.layout.cells;
+        let mut solutions = vec![None; self.layout.rules.columns];
+        for (col, solution) in solutions.iter_mut().enumerate() {
+            if !position.can_play(col, &self.layout) {
+                continue;
+            }
+            let score = if position.is_winning_move(col, &self.layout) {
+                (cells + 1 - position.moves as i32) / 2
+            } else {
+                let mut next = position;
+                next.play_col(col, &self.layout);
+                -self.solve_position(&next, stop)?
+            };
+            *solution = Some(Solution::from_score(score, position.moves, cells));
+        }
+        Some(solutions)
+    }
+
+    // The best column for the player to move, preferring the center among equally good moves
+    pub fn best_move(&mut self, board: &BitBoard) -> Option<usize> {
+        self.best_move_until(board, &AtomicBool::new(false))
+    }
+
+    // Like `best_move`, but gives up and returns None once `stop` is set
+    pub fn best_move_until(&mut self, board: &BitBoard, stop: &AtomicBool) -> Option<usize> {
+        best_column(&self.analyze_until(board, stop)?)
+    }
+
+    // Switches to the board size of `board`, forgetting scores of positions of another size
+    fn prepare(&mut self, board: &BitBoard) -> Position {
+        let rules = board.get_rules();
+        assert!(Self::supports(&rules), "the solver cannot handle {} boards", rules);
+        // The search assumes nobody has a line yet, and would score a finished game as if it went on
+        assert!(board.get_winner().is_none(), "the solver cannot handle a game that is already over");
+        if self.layout.rules != rules {
+            self.layout = Layout::new(rules);
+            self.table.clear();
+        }
+        Position::from_bitboard(board)
+    }
+
+    // Exact score of a position, or None if `stop` was set before it was found
+    fn solve_position(&mut self, position: &Position, stop: &AtomicBool) -> Option<i32> {
+        let cells = self.layout.cells;
+        if position.can_win_next(&self.layout) {
+            return Some((cells + 1 - position.moves as i32) / 2);
+        }
+        if position.moves as i32 == cells {
+            return Some(0);
+        }
+
+        // Narrow the score window with null-window searches until it closes
+        let mut min = -(cells - position.moves as i32) / 2;
+        let mut max = (cells + 1 - position.moves as i32) / 2;
+        while min < max {
+            let mut med = min + (max - min) / 2;
+            if med <= 0 && min / 2 < med {
+                med = min / 2;
+            } else if med >= 0 && max / 2 > med {
+                med = max / 2;
+            }
+            let score = self.negamax(position, med, med + 1, stop)?;
+            if score <= med {
+                max = score;
+            } else {
+                min = score;
+            }
+        }
+        Some(min)
+    }
+
+    // Score of a position where the player to move cannot win immediately, within [alpha, beta].
+    // Returns None as soon as `stop` is set, before the unfinished score can reach the table.
+    fn negamax(&mut self, position: &Position, mut alpha: i32, mut beta: i32, stop: &AtomicBool) -> Option<i32> {
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.explored_nodes += 1;
+        let cells = self.layout.cells;
+
+        let next = position.possible_non_losing_moves(&self.layout);
+        if next == 0 {
+            return Some(-(cells - position.moves as i32) / 2);
+        }
+        if position.moves as i32 >= cells - 2 {
+            return Some(0);
+        }
+
+        let min = -(cells - 2 - position.moves as i32) / 2;
+        if alpha < min {
+            alpha = min;
+            if alpha >= beta {
+                return Some(alpha);
+            }
+        }
+
+        let key = position.key(&self.layout);
+        let mut max = (cells - 1 - position.moves as i32) / 2;
+        if let Some(value) = self.table.get(key) {
+            max = value as i32;
+        }
+        if beta > max {
+            beta = max;
+            if alpha >= beta {
+                return Some(beta);
+            }
+        }
+
+        // Try the moves that create the most winning spots first, the center breaking ties
+        let layout = &self.layout;
+        let mut moves: Vec<(u64, u32)> = layout.column_order.iter()
+            .map(|&col| next & layout.column_mask(col))
+            .filter(|&move_bit| move_bit != 0)
+            .map(|move_bit| (move_bit, position.move_score(move_bit, layout)))
+            .collect();
+        moves.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+
+        for (move_bit, _) in moves {
+            let mut child = *position;
+            child.play(move_bit);
+            let score = -self.negamax(&child, -beta, -alpha, stop)?;
+            if score >= beta {
+                return Some(score);
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+
+        self.table.insert(key, alpha as i8, 0);
+        Some(alpha)
+    }
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The best column among the solutions of `Solver::analyze`, preferring the center among equally good ones
+pub fn best_column(solutions: &[Option<Solution>]) -> Option<usize> {
+    let columns = solutions.len();
+    center_first(columns).into_iter()
+        .filter_map(|col| solutions[col].map(|solution| (col, solution)))
+        .max_by_key(|&(col, solution)| (rank(solution), std::cmp::Reverse(center_distance(col, columns))))
+        .map(|(col, _)| col)
+}
+
+// Orders solutions from worst to best for the player to move: slow losses beat fast ones, fast wins beat slow ones
+fn rank(solution: Solution) -> i64 {
+    match solution {
+        Solution::Loss(plies) => -1000 + plies as i64,
+        Solution::Draw(_) => 0,
+        Solution::Win(plies) => 1000 - plies as i64,
+    }
+}
+
+// Columns ordered from the center outwards, the left one first among columns as close
+fn center_first(columns: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..columns).collect();
+    order.sort_by_key(|&col| center_distance(col, columns));
+    order
+}
+
+// Twice the distance to the center, so that both middle columns of an even board are as close
+fn center_distance(col: usize, columns: usize) -> usize {
+    (2 * col + 1).abs_diff(columns)
+}
+
+// Solves `board` with a fresh solver
+pub fn solve(board: &BitBoard) -> Solution {
+    Solver::new().solve(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::connect4::notation::parse_moves;
+
+    #[test]
+    fn test_immediate_win() {
+        let board = parse_moves("172737").unwrap();
+        let mut solver = Solver::new();
+        assert_eq!(solver.solve(&board), Solution::Win(1));
+    }
+
+    #[test]
+    fn test_unstoppable_threat_is_a_loss() {
+        // Player 2 has an open three on the bottom row and wins with its next disc
+        let board = parse_moves("737415").unwrap();
+        assert_eq!(solve(&board), Solution::Loss(2));
+    }
+
+    #[test]
+    fn test_best_move_keeps_the_solution() {
+        for sequence in ["7422341735647741166133573473242566", "2252576253462244111563365343671351441"] {
+            let board = parse_moves(sequence).unwrap();
+            let mut solver = Solver::new();
+            let solution = solver.solve(&board);
+            let best = solver.best_move(&board).unwrap();
+            assert_eq!(solver.analyze(&board)[best], Some(solution), "{}", sequence);
+        }
+    }
+
+    #[test]
+    fn test_late_positions() {
+        // Checked against a plain minimax search over the remaining moves
+        let cases = [
+            ("7422341735647741166133573473242566", Solution::Win(7)),
+            ("2252576253462244111563365343671351441", Solution::Loss(4)),
+            ("23163416124767223154467471272416755633", Solution::Draw(4)),
+        ];
+        for (sequence, solution) in cases {
+            assert_eq!(solve(&parse_moves(sequence).unwrap()), solution, "{}", sequence);
+        }
+    }
+
+    #[test]
+    fn test_mirrored_positions_share_solutions() {
+        let board = parse_moves("7422341735647741166133573473242566").unwrap();
+        let mut solver = Solver::new();
+        let solutions = solver.analyze(&board);
+        let mut mirrored = solver.analyze(&board.mirror());
+        mirrored.reverse();
+        assert_eq!(solutions, mirrored);
+    }
+
+    // Plain minimax over every move, fast enough for tiny boards
+    fn minimax(board: &BitBoard) -> Solution {
+        board.get_valid_moves().into_iter()
+            .map(|col| {
+                if board.is_winning_move(col) {
+                    return Solution::Win(1);
+                }
+                let mut child = *board;
+                if child.make_move(col).is_some() {
+                    return Solution::Draw(1);
+                }
+                match minimax(&child) {
+                    Solution::Win(plies) => Solution::Loss(plies + 1),
+                    Solution::Loss(plies) => Solution::Win(plies + 1),
+                    Solution::Draw(plies) => Solution::Draw(plies + 1),
+                }
+            })
+            .max_by_key(|&solution| rank(solution))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_other_sizes_and_lengths() {
+        let mut solver = Solver::new();
+        for (rules, moves) in [
+            (Rules::new(4, 3, 3).unwrap(), vec![1, 2]),
+            (Rules::new(4, 4, 3).unwrap(), vec![0, 3, 3, 0, 1, 2]),
+            (Rules::new(5, 4, 4).unwrap(), vec![2, 2, 1, 3, 0, 4, 4, 1, 2]),
+        ] {
+            let mut board = BitBoard::with_rules(rules);
+            for col in moves {
+                board.make_move(col);
+            }
+            assert_eq!(solver.solve(&board), minimax(&board), "{}", rules);
+        }
+
+        // Switching back to the standard board forgets the scores of the small ones
+        assert_eq!(solver.solve(&parse_moves("172737").unwrap()), Solution::Win(1));
+        assert!(Solver::supports(&Rules::new(8, 7, 5).unwrap()));
+        assert!(!Solver::supports(&Rules::new(9, 7, 4).unwrap()));
+    }
+
+    #[test]
+    #[should_panic(expected = "already over")]
+    fn test_finished_games_are_refused() {
+        // Player 1 has already won with a vertical four in column 1
+        solve(&parse_moves("1212121").unwrap());
+    }
+
+    #[test]
+    fn test_stopped_search_gives_up() {
+        let board = parse_moves("7422341735647741166133573473242566").unwrap();
+        let mut solver = Solver::new();
+        assert_eq!(solver.best_move_until(&board, &AtomicBool::new(true)), None);
+        assert_eq!(solver.get_explored_nodes(), 0);
+        assert_eq!(solver.solve(&board), Solution::Win(7));
+    }
+
+    #[test]
+    fn test_score_conversion() {
+        // Player 1 to move on an empty board wins with its 4th disc at the earliest
+        assert_eq!(Solution::from_score(18, 0, 42), Solution::Win(7));
+        // Player 2 to move after one disc loses to player 1's 4th disc at the earliest
+        assert_eq!(Solution::from_score(-18, 1, 42), Solution::Loss(6));
+        assert_eq!(Solution::from_score(0, 40, 42), Solution::Draw(2));
+    }
+}
@@ -0,0 +1,346 @@
+use std::ops::{BitAnd, BitOr, Not, Shl, Shr};
+use crate::games::Game;
+use crate::games::grid::Cell;
+
+// Free-style gomoku: black (player 1) and white take turns putting a stone on any empty
+// intersection of a 15x15 board, and the first to get five in a row wins. With the exact-five
+// rule an overline of six or more does not count.
+//
+// Stones are bit sets laid out like connect four's bitboards: cell (row, col) is bit
+// `col * (SIZE + 1) + row`, with an empty sentinel row after every column. That takes 240 bits,
+// held in four 64-bit words, and lines are found with the same shifts.
+//
+// The search only looks at candidate moves, the empty cells within `CANDIDATE_DISTANCE` of a
+// stone, since random stones far from the fight make playouts meaningless. Players may still put
+// a stone anywhere.
+
+pub const SIZE: usize = 15;
+pub const CANDIDATE_DISTANCE: usize = 2;  // In rows or columns, diagonals included
+
+// Every cell of the board: 15 bits of every 16, without the sixteenth group
+const BOARD: Bits256 = Bits256([0x7FFF_7FFF_7FFF_7FFF, 0x7FFF_7FFF_7FFF_7FFF, 0x7FFF_7FFF_7FFF_7FFF, 0x0000_7FFF_7FFF_7FFF]);
+
+// A 256-bit set, bit 0 in the lowest bit of the first word
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Bits256([u64; 4]);
+
+impl Bits256 {
+    const ZERO: Self = Bits256([0; 4]);
+
+    fn bit(index: usize) -> Self {
+        let mut words = [0; 4];
+        words[index / 64] = 1 << (index % 64);
+        Bits256(words)
+    }
+
+    fn contains(&self, index: usize) -> bool {
+        self.0[index / 64] >> (index % 64) & 1 == 1
+    }
+
+    fn count(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    // Indices of the set bits, lowest first
+    fn indices(self) -> impl Iterator<Item = usize> {
+        self.0.into_iter().enumerate().flat_map(|(word_index, mut word)| {
+            std::iter::from_fn(move || {
+                (word != 0).then(|| {
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    word_index * 64 + bit
+                })
+            })
+        })
+    }
+}
+
+impl BitAnd for Bits256 {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        Bits256(std::array::from_fn(|i| self.0[i] & other.0[i]))
+    }
+}
+
+impl BitOr for Bits256 {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Bits256(std::array::from_fn(|i| self.0[i] | other.0[i]))
+    }
+}
+
+impl Not for Bits256 {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Bits256(self.0.map(|word| !word))
+    }
+}
+
+// Towards higher bits, carrying from one word into the next
+impl Shl<usize> for Bits256 {
+    type Output = Self;
+
+    fn shl(self, amount: usize) -> Self {
+        let (words, bits) = (amount / 64, amount % 64);
+        Bits256(std::array::from_fn(|i| {
+            let Some(from) = i.checked_sub(words) else {
+                return 0;
+            };
+            let carry = if bits > 0 && from > 0 { self.0[from - 1] >> (64 - bits) } else { 0 };
+            self.0[from] << bits | carry
+        }))
+    }
+}
+
+// Towards bit 0, carrying from one word into the previous
+impl Shr<usize> for Bits256 {
+    type Output = Self;
+
+    fn shr(self, amount: usize) -> Self {
+        let (words, bits) = (amount / 64, amount % 64);
+        Bits256(std::array::from_fn(|i| {
+            let from = i + words;
+            if from >= 4 {
+                return 0;
+            }
+            let carry = if bits > 0 && from + 1 < 4 { self.0[from + 1] << (64 - bits) } else { 0 };
+            self.0[from] >> bits | carry
+        }))
+    }
+}
+
+// Shifts from a cell to the next one along a column, a row and the two diagonals
+const DIRECTIONS: [usize; 4] = [1, SIZE + 1, SIZE, SIZE + 2];
+
+// Whether `stones` hold five in a row, or exactly five when `exact` rules out longer lines.
+// A line that would wrap into the next column always crosses a sentinel.
+fn has_five(stones: Bits256, exact: bool) -> bool {
+    DIRECTIONS.into_iter().any(|step| {
+        // Bit i of `fives` starts five stones at i, i + step, ..., i + 4 * step
+        let mut fives = stones;
+        for i in 1..5 {
+            fives = fives & stones >> (i * step);
+        }
+        if exact {
+            // No own stone just before the five or just after it
+            fives = fives & !(stones << step) & !(stones >> (5 * step));
+        }
+        fives != Bits256::ZERO
+    })
+}
+
+// `cells` together with every cell next to one of them, diagonals included
+fn grow(cells: Bits256) -> Bits256 {
+    let columns = cells | cells << 1 | cells >> 1;
+    (columns | columns << (SIZE + 1) | columns >> (SIZE + 1)) & BOARD
+}
+
+fn bit(cell: Cell) -> usize {
+    cell.col * (SIZE + 1) + cell.row
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GomokuBoard {
+    stones: [Bits256; 2],  // Stones of player 1 (black) and player 2 (white)
+    current_player: u8,
+    exact_five: bool,      // Whether overlines fail to win
+}
+
+impl GomokuBoard {
+    // An empty board under free-style rules, where five or more in a row wins
+    pub fn new() -> Self {
+        Self { stones: [Bits256::ZERO; 2], current_player: 1, exact_five: false }
+    }
+
+    pub fn with_exact_five(mut self, exact_five: bool) -> Self {
+        self.exact_five = exact_five;
+        self
+    }
+
+    pub fn is_exact_five(&self) -> bool {
+        self.exact_five
+    }
+
+    pub fn get_current_player(&self) -> u8 {
+        self.current_player
+    }
+
+    // 1 or 2 for a stone of that player, 0 for an empty cell
+    pub fn get_player_at(&self, cell: Cell) -> u8 {
+        let bit = bit(cell);
+        if self.stones[0].contains(bit) {
+            1
+        } else if self.stones[1].contains(bit) {
+            2
+        } else {
+            0
+        }
+    }
+
+    // Any empty cell may be played, candidate or not
+    pub fn is_valid_move(&self, cell: Cell) -> bool {
+        cell.row < SIZE && cell.col < SIZE && self.get_player_at(cell) == 0
+    }
+
+    // The moves the search looks at: empty cells near the stones, or the center of an empty board
+    pub fn get_candidate_moves(&self) -> Vec<Cell> {
+        let occupied = self.stones[0] | self.stones[1];
+        if occupied == Bits256::ZERO {
+            return vec![Cell::new(SIZE / 2, SIZE / 2)];
+        }
+        let mut near = occupied;
+        for _ in 0..CANDIDATE_DISTANCE {
+            near = grow(near);
+        }
+        (near & !occupied).indices().map(|bit| Cell::new(bit % (SIZE + 1), bit / (SIZE + 1))).collect()
+    }
+
+    // Puts a stone of the current player on `cell`, which must be empty, and returns the result
+    // from player 1's point of view if the game ended
+    pub fn make_move(&mut self, cell: Cell) -> Option<i32> {
+        let player = self.current_player;
+        let stones = &mut self.stones[player as usize - 1];
+        *stones = *stones | Bits256::bit(bit(cell));
+        let won = has_five(*stones, self.exact_five);
+        self.current_player = 3 - player;
+        if won {
+            Some(if player == 1 { 1 } else { -1 })
+        } else if self.is_full() {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        (self.stones[0] | self.stones[1]).count() == SIZE * SIZE
+    }
+}
+
+impl Default for GomokuBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game for GomokuBoard {
+    type Move = Cell;
+    type Outcome = i32;
+
+    fn get_current_player(&self) -> u8 {
+        GomokuBoard::get_current_player(self)
+    }
+
+    fn get_valid_moves(&self) -> Vec<Cell> {
+        self.get_candidate_moves()
+    }
+
+    fn is_valid_move(&self, cell: Cell) -> bool {
+        GomokuBoard::is_valid_move(self, cell)
+    }
+
+    fn make_move(&mut self, cell: Cell) -> Option<i32> {
+        GomokuBoard::make_move(self, cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcts::mcts::MCTS;
+
+    // Plays `cells`, written like "h8", in turn and returns the result of the last one
+    fn play(board: &mut GomokuBoard, cells: &str) -> Option<i32> {
+        let mut result = None;
+        for name in cells.split_whitespace() {
+            let cell = Cell::parse(name).unwrap();
+            assert!(result.is_none() && board.is_valid_move(cell), "{}", name);
+            result = board.make_move(cell);
+        }
+        result
+    }
+
+    #[test]
+    fn test_shifts_cross_words() {
+        let bits = Bits256::bit(63) | Bits256::bit(200);
+        assert_eq!((bits << 1).indices().collect::<Vec<_>>(), [64, 201]);
+        assert_eq!((bits >> 64).indices().collect::<Vec<_>>(), [136]);
+        assert_eq!((bits << 130).indices().collect::<Vec<_>>(), [193]);
+        assert_eq!((bits >> 0), bits);
+        assert_eq!(BOARD.count(), SIZE * SIZE);
+    }
+
+    #[test]
+    fn test_fives() {
+        // Along row 8 from column b to f, across the first and second words
+        let mut board = GomokuBoard::new();
+        assert_eq!(play(&mut board, "b8 b9 c8 c9 d8 d9 e8 e9"), None);
+        assert_eq!(play(&mut board, "f8"), Some(1));
+
+        // Down column o, the last one
+        let mut board = GomokuBoard::new();
+        assert_eq!(play(&mut board, "a1 o11 a2 o12 a3 o13 a4 o14 b1"), None);
+        assert_eq!(play(&mut board, "o15"), Some(-1));
+
+        // Both diagonals
+        let mut board = GomokuBoard::new();
+        assert_eq!(play(&mut board, "c3 a15 d4 b15 e5 c15 f6 d15 g7"), Some(1));
+        let mut board = GomokuBoard::new();
+        assert_eq!(play(&mut board, "k1 a15 j2 b15 i3 c15 h4 d15 g5"), Some(1));
+
+        // Nothing wraps from the bottom of one column to the top of the next
+        let mut board = GomokuBoard::new();
+        assert_eq!(play(&mut board, "a13 h8 a14 h9 a15 h10 b1 j8 b2 j9"), None);
+    }
+
+    #[test]
+    fn test_overlines() {
+        // The sixth stone joins two lines into six
+        let overline = "a1 a3 b1 b3 c1 c3 e1 e3 f1 f5";
+        let mut free_style = GomokuBoard::new();
+        assert_eq!(play(&mut free_style, overline), None);
+        assert_eq!(play(&mut free_style, "d1"), Some(1));
+
+        let mut exact = GomokuBoard::new().with_exact_five(true);
+        assert_eq!(play(&mut exact, overline), None);
+        assert_eq!(play(&mut exact, "d1"), None);
+        // Five with an opponent's stone at the end still wins
+        assert_eq!(play(&mut exact, "e5 a4 a6 f3 b5 c4 d3"), Some(-1));
+    }
+
+    #[test]
+    fn test_candidates() {
+        let mut board = GomokuBoard::new();
+        assert_eq!(board.get_candidate_moves(), [Cell::new(7, 7)]);
+        board.make_move(Cell::new(0, 0));
+        assert_eq!(board.get_candidate_moves().len(), 8);
+        assert!(board.is_valid_move(Cell::new(14, 14)));
+        board.make_move(Cell::new(7, 7));
+        assert_eq!(board.get_candidate_moves().len(), 8 + 24);
+        assert!(board.get_candidate_moves().iter().all(|&cell| board.is_valid_move(cell)));
+    }
+
+    #[test]
+    fn test_random_games_end() {
+        for exact_five in [false, true] {
+            let mut board = GomokuBoard::new().with_exact_five(exact_five);
+            loop {
+                let moves = board.get_candidate_moves();
+                if board.make_move(moves[fastrand::usize(..moves.len())]).is_some() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mcts_completes_five() {
+        let mut board = GomokuBoard::new();
+        play(&mut board, "h8 h9 i8 i9 j8 a1 k8 b1");
+        let cell = MCTS::new(1.414, 1, 1000, 10).search(board);
+        assert!(cell == Cell::parse("g8").unwrap() || cell == Cell::parse("l8").unwrap(), "{}", cell);
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::ops::{Add, BitAnd, BitOr, Not, Shl, Shr};
+
+// Cells of boards where stones go anywhere, without gravity, and the bit sets that boards of any
+// game keep their cells in
+
+// A cell by row and column, 0-indexed with row 0 at the top
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cell {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Cell {
+    // Columns that `Display` can name with a single letter
+    pub const MAX_COLUMNS: usize = 26;
+
+    pub fn new(row: usize, col: usize) -> Self {
+        Self { row, col }
+    }
+
+    // Reads a cell written like `Display` writes it, e.g. "b3"
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut chars = text.chars();
+        let letter = chars.next().filter(|letter| letter.is_ascii_lowercase())?;
+        let row: usize = chars.as_str().parse().ok().filter(|&row| row >= 1)?;
+        Some(Self::new(row - 1, (letter as u8 - b'a') as usize))
+    }
+}
+
+// The column as a letter from 'a' and the row as a number from 1, so "a1" is the top left corner
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.col as u8) as char, self.row + 1)
+    }
+}
+
+// Cell (row, col) of a board in a bit set is bit `col * (rows + 1) + row`: columns follow each other
+// with an empty sentinel row after every column, so that lines never wrap from one column into the next.
+// Integers used as bit sets
+pub(crate) trait Bits:
+    Copy + Eq + Add<Output = Self> + BitAnd<Output = Self> + BitOr<Output = Self> + Not<Output = Self>
+    + Shl<usize, Output = Self> + Shr<usize, Output = Self>
+{
+    const ZERO: Self;
+    const BITS: usize;
+}
+
+impl Bits for u64 {
+    const ZERO: Self = 0;
+    const BITS: usize = 64;
+}
+
+impl Bits for u128 {
+    const ZERO: Self = 0;
+    const BITS: usize = 128;
+}
+
+// `bits` shifted towards bit 0, empty once everything is shifted out
+pub(crate) fn shift_down<B: Bits>(bits: B, amount: usize) -> B {
+    if amount >= B::BITS { B::ZERO } else { bits >> amount }
+}
+
+// Whether `bits` holds `connect` cells in a line on a board of `rows` rows
+pub(crate) fn has_line<B: Bits>(bits: B, rows: usize, connect: usize) -> bool {
+    [1, rows + 1, rows, rows + 2].into_iter().any(|step| {
+        let mut line = bits;
+        for i in 1..connect {
+            line = line & shift_down(bits, i * step);
+        }
+        line != B::ZERO
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(Cell::new(0, 0).to_string(), "a1");
+        assert_eq!(Cell::new(14, 7).to_string(), "h15");
+        assert_eq!(Cell::parse("h15"), Some(Cell::new(14, 7)));
+        for text in ["", "a", "a0", "A1", "1a", "b-1"] {
+            assert_eq!(Cell::parse(text), None, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::fmt;
+use crate::games::Game;
+use crate::games::grid::{has_line, Cell};
+
+// The m,n,k-game: two players take turns putting a stone on any empty cell of an m x n board,
+// and the first to get k in a row wins. Tic-tac-toe is the 3,3,3-game. The small boards can be
+// solved outright, which makes them a test bed for the search.
+//
+// Stones are bit sets laid out like connect four's bitboards: cell (row, col) is bit
+// `col * (rows + 1) + row`, with an empty sentinel row after every column, so lines are found
+// with the same shifts (`grid::has_line`).
+
+pub const MAX_BITS: usize = 128;  // columns * (rows + 1) must fit one u128
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MnkBoard {
+    columns: usize,  // m
+    rows: usize,     // n
+    k: usize,        // Stones in a row needed to win
+    stones: [u128; 2],  // Stones of player 1 and player 2
+    current_player: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MnkError {
+    Size { columns: usize, rows: usize },    // Empty, or too many cells for the bit sets
+    Columns(usize),                          // More columns than cell names have letters
+    Connect { k: usize, longest: usize },    // No line on the board is long enough, or too short to mean anything
+}
+
+impl fmt::Display for MnkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MnkError::Size { columns, rows } =>
+                write!(f, "a board of {}x{} cells does not fit in {} bits", columns, rows, MAX_BITS),
+            MnkError::Columns(columns) =>
+                write!(f, "{} columns cannot be named a to z, at most {} fit", columns, Cell::MAX_COLUMNS),
+            MnkError::Connect { k, longest } =>
+                write!(f, "{} in a row is not between 2 and the longest line of the board, {}", k, longest),
+        }
+    }
+}
+
+impl std::error::Error for MnkError {}
+
+impl MnkBoard {
+    // An empty board of `columns` x `rows` where `k` in a row wins
+    pub fn new(columns: usize, rows: usize, k: usize) -> Result<Self, MnkError> {
+        if columns == 0 || rows == 0 || columns * (rows + 1) > MAX_BITS {
+            return Err(MnkError::Size { columns, rows });
+        }
+        if columns > Cell::MAX_COLUMNS {
+            return Err(MnkError::Columns(columns));
+        }
+        let longest = columns.max(rows);
+        if !(2..=longest).contains(&k) {
+            return Err(MnkError::Connect { k, longest });
+        }
+        Ok(Self { columns, rows, k, stones: [0; 2], current_player: 1 })
+    }
+
+    pub fn tic_tac_toe() -> Self {
+        Self::new(3, 3, 3).expect("3x3 fits")
+    }
+
+    pub fn get_columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn get_rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn get_k(&self) -> usize {
+        self.k
+    }
+
+    pub fn get_current_player(&self) -> u8 {
+        self.current_player
+    }
+
+    // 1 or 2 for a stone of that player, 0 for an empty cell
+    pub fn get_player_at(&self, cell: Cell) -> u8 {
+        let bit = self.bit(cell);
+        match (self.stones[0] >> bit & 1, self.stones[1] >> bit & 1) {
+            (1, _) => 1,
+            (_, 1) => 2,
+            _ => 0,
+        }
+    }
+
+    pub fn is_valid_move(&self, cell: Cell) -> bool {
+        cell.row < self.rows && cell.col < self.columns && self.get_player_at(cell) == 0
+    }
+
+    // Empty cells, row by row from the top
+    pub fn get_valid_moves(&self) -> Vec<Cell> {
+        (0..self.rows)
+            .flat_map(|row| (0..self.columns).map(move |col| Cell::new(row, col)))
+            .filter(|&cell| self.get_player_at(cell) == 0)
+            .collect()
+    }
+
+    // Puts a stone of the current player on `cell`, which must be empty, and returns the result
+    // from player 1's point of view if the game ended
+    pub fn make_move(&mut self, cell: Cell) -> Option<i32> {
+        let player = self.current_player;
+        let bit = self.bit(cell);
+        let stones = &mut self.stones[player as usize - 1];
+        *stones |= 1 << bit;
+        let won = has_line(*stones, self.rows, self.k);
+        self.current_player = 3 - player;
+        if won {
+            Some(if player == 1 { 1 } else { -1 })
+        } else if self.is_full() {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        ((self.stones[0] | self.stones[1]).count_ones() as usize) == self.columns * self.rows
+    }
+
+    fn bit(&self, cell: Cell) -> usize {
+        cell.col * (self.rows + 1) + cell.row
+    }
+}
+
+impl Game for MnkBoard {
+    type Move = Cell;
+    type Outcome = i32;
+
+    fn get_current_player(&self) -> u8 {
+        MnkBoard::get_current_player(self)
+    }
+
+    fn get_valid_moves(&self) -> Vec<Cell> {
+        MnkBoard::get_valid_moves(self)
+    }
+
+    fn is_valid_move(&self, cell: Cell) -> bool {
+        MnkBoard::is_valid_move(self, cell)
+    }
+
+    fn make_move(&mut self, cell: Cell) -> Option<i32> {
+        MnkBoard::make_move(self, cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use super::*;
+    use crate::mcts::mcts::MCTS;
+
+    fn play(board: &mut MnkBoard, cells: &[(usize, usize)]) -> Option<i32> {
+        let mut result = None;
+        for &(row, col) in cells {
+            assert!(result.is_none() && board.is_valid_move(Cell::new(row, col)));
+            result = board.make_move(Cell::new(row, col));
+        }
+        result
+    }
+
+    // Value of `board` for the player to move under perfect play: 1 for a win, 0 for a draw, -1 for a loss
+    fn solve(board: &MnkBoard, values: &mut HashMap<MnkBoard, i32>) -> i32 {
+        if let Some(&value) = values.get(board) {
+            return value;
+        }
+        let player = board.get_current_player();
+        let value = board.get_valid_moves().into_iter().map(|cell| {
+            let mut child = *board;
+            match child.make_move(cell) {
+                Some(result) => if player == 1 { result } else { -result },
+                None => -solve(&child, values),
+            }
+        }).max().unwrap();
+        values.insert(*board, value);
+        value
+    }
+
+    #[test]
+    fn test_lines_and_draws() {
+        let mut board = MnkBoard::tic_tac_toe();
+        assert_eq!(board.get_valid_moves().len(), 9);
+        assert_eq!(play(&mut board, &[(0, 0), (1, 0), (1, 1), (2, 0)]), None);
+        assert_eq!(board.get_player_at(Cell::new(2, 0)), 2);
+        assert!(!board.is_valid_move(Cell::new(1, 1)) && !board.is_valid_move(Cell::new(3, 0)));
+        assert_eq!(play(&mut board, &[(2, 2)]), Some(1));
+
+        // The anti-diagonal for player 2
+        let mut board = MnkBoard::tic_tac_toe();
+        assert_eq!(play(&mut board, &[(0, 0), (0, 2), (0, 1), (1, 1), (2, 2), (2, 0)]), Some(-1));
+
+        // Nothing wraps from the bottom of one column to the top of the next
+        let mut board = MnkBoard::new(4, 4, 3).unwrap();
+        assert_eq!(play(&mut board, &[(2, 0), (1, 2), (3, 0), (2, 2), (0, 1), (3, 3)]), None);
+
+        let mut board = MnkBoard::tic_tac_toe();
+        let draw = [(1, 1), (0, 0), (0, 2), (2, 0), (1, 0), (1, 2), (0, 1), (2, 1), (2, 2)];
+        assert_eq!(play(&mut board, &draw), Some(0));
+        assert!(board.is_full());
+    }
+
+    #[test]
+    fn test_sizes() {
+        assert_eq!(MnkBoard::new(4, 4, 5), Err(MnkError::Connect { k: 5, longest: 4 }));
+        assert_eq!(MnkBoard::new(3, 3, 1), Err(MnkError::Connect { k: 1, longest: 3 }));
+        assert_eq!(MnkBoard::new(12, 10, 5), Err(MnkError::Size { columns: 12, rows: 10 }));
+        assert_eq!(MnkBoard::new(0, 3, 3), Err(MnkError::Size { columns: 0, rows: 3 }));
+        assert!(MnkBoard::new(11, 10, 5).is_ok());
+        assert_eq!(MnkBoard::new(27, 1, 5), Err(MnkError::Columns(27)));
+        assert!(MnkBoard::new(26, 1, 5).is_ok());
+    }
+
+    #[test]
+    fn test_tic_tac_toe_is_a_draw() {
+        assert_eq!(solve(&MnkBoard::tic_tac_toe(), &mut HashMap::new()), 0);
+        // One more column lets the first player force three in a row
+        assert_eq!(solve(&MnkBoard::new(4, 3, 3).unwrap(), &mut HashMap::new()), 1);
+    }
+
+    // MCTS playing both sides never gives away the draw: every move keeps the value of the position
+    #[test]
+    fn test_mcts_plays_tic_tac_toe_perfectly() {
+        let mut values = HashMap::new();
+        let mcts = MCTS::new(1.414, 1, 200, 10);
+        for _ in 0..3 {
+            let mut board = MnkBoard::tic_tac_toe();
+            loop {
+                let value = solve(&board, &mut values);
+                let cell = mcts.search(board);
+                match board.make_move(cell) {
+                    Some(result) => {
+                        assert_eq!(result, 0);
+                        break;
+                    }
+                    None => assert_eq!(-solve(&board, &mut values), value, "{} gives away the draw", cell),
+                }
+            }
+        }
+    }
+
+    // Every tic-tac-toe position reachable in a game where the player to move can win right away
+    fn positions_with_a_win(board: &MnkBoard, seen: &mut HashSet<MnkBoard>, found: &mut Vec<MnkBoard>) {
+        if !seen.insert(*board) {
+            return;
+        }
+        let mut can_win = false;
+        for cell in board.get_valid_moves() {
+            let mut child = *board;
+            match child.make_move(cell) {
+                Some(result) => can_win |= result != 0,
+                None => positions_with_a_win(&child, seen, found),
+            }
+        }
+        if can_win {
+            found.push(*board);
+        }
+    }
+
+    #[test]
+    fn test_mcts_never_misses_a_win() {
+        let mut positions = Vec::new();
+        positions_with_a_win(&MnkBoard::tic_tac_toe(), &mut HashSet::new(), &mut positions);
+        assert!(positions.len() > 1000);
+
+        let mcts = MCTS::new(1.414, 1, 1000, 10);
+        for board in positions {
+            let cell = mcts.search(board);
+            let mut child = board;
+            assert!(matches!(child.make_move(cell), Some(result) if result != 0), "{:?} misses the win, plays {}", board, cell);
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use std::fmt;
+use crate::games::{Game, Outcome};
+use crate::games::grid::Cell;
+
+// Othello: black (player 1) and white take turns putting a disc on the 8x8 board so that it
+// brackets a line of the opponent's discs, which then flip to their colour. A player without
+// such a move has to pass, and the game ends when both players pass in a row or the board is
+// full. Whoever has more discs wins.
+//
+// Discs are one 64-bit set per player: cell (row, col) is bit `row * SIZE + col`. Moves and flips
+// are found a whole direction at a time by shifting those sets, masking off the discs that would
+// wrap around to the other side of the board.
+
+pub const SIZE: usize = 8;
+
+const NOT_A: u64 = 0xFEFE_FEFE_FEFE_FEFE;  // Every column but a, the first
+const NOT_H: u64 = 0x7F7F_7F7F_7F7F_7F7F;  // Every column but h, the last
+
+// The eight directions as (shift, mask): a positive shift moves discs to higher bits, and the mask
+// clears the column that wrapped around
+const DIRECTIONS: [(i32, u64); 8] = [
+    (1, NOT_A),    // East
+    (-1, NOT_H),   // West
+    (8, !0),       // South
+    (-8, !0),      // North
+    (9, NOT_A),    // South-east
+    (7, NOT_H),    // South-west
+    (-7, NOT_A),   // North-east
+    (-9, NOT_H),   // North-west
+];
+
+// Moves every disc one cell in `direction`, dropping those that leave the board
+fn shift(discs: u64, (amount, mask): (i32, u64)) -> u64 {
+    if amount > 0 { discs << amount & mask } else { discs >> -amount & mask }
+}
+
+// Empty cells where `own` can bracket some of `opponent`'s discs
+fn placements(own: u64, opponent: u64) -> u64 {
+    let empty = !(own | opponent);
+    let mut moves = 0;
+    for direction in DIRECTIONS {
+        // Opponent's discs in a line right after one of our own, up to six of them
+        let mut line = shift(own, direction) & opponent;
+        for _ in 0..5 {
+            line |= shift(line, direction) & opponent;
+        }
+        moves |= shift(line, direction) & empty;
+    }
+    moves
+}
+
+// The opponent's discs that a disc of `own` on `placed` flips
+fn flips(own: u64, opponent: u64, placed: u64) -> u64 {
+    let mut flipped = 0;
+    for direction in DIRECTIONS {
+        let mut line = 0;
+        let mut next = shift(placed, direction);
+        while next & opponent != 0 {
+            line |= next;
+            next = shift(next, direction);
+        }
+        if next & own != 0 {
+            flipped |= line;
+        }
+    }
+    flipped
+}
+
+fn bit(cell: Cell) -> u64 {
+    1 << (cell.row * SIZE + cell.col)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Move {
+    Place(Cell),
+    Pass,  // Only when the player has nowhere to place a disc
+}
+
+impl Move {
+    // Reads a move written like `Display` writes it, e.g. "d3" or "pass"
+    pub fn parse(text: &str) -> Option<Self> {
+        if text == "pass" { Some(Move::Pass) } else { Cell::parse(text).map(Move::Place) }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Move::Place(cell) => write!(f, "{}", cell),
+            Move::Pass => write!(f, "pass"),
+        }
+    }
+}
+
+// Final disc count of black (player 1) and white
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OthelloResult {
+    pub discs: [u8; 2],
+}
+
+impl Outcome for OthelloResult {
+    fn reward(&self) -> i32 {
+        (self.discs[0] as i32 - self.discs[1] as i32).signum()
+    }
+
+    // The disc difference out of all 64 cells, so that a wipe-out on a full board scores 1
+    fn margin(&self) -> f32 {
+        (self.discs[0] as f32 - self.discs[1] as f32) / (SIZE * SIZE) as f32
+    }
+}
+
+// Black's count first, e.g. "34-30"
+impl fmt::Display for OthelloResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.discs[0], self.discs[1])
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OthelloBoard {
+    discs: [u64; 2],  // Discs of player 1 (black) and player 2 (white)
+    current_player: u8,
+    passed: bool,     // Whether the last move was a pass, so that another one ends the game
+}
+
+impl OthelloBoard {
+    // The starting position: white on d4 and e5, black on e4 and d5, black to move
+    pub fn new() -> Self {
+        Self::from_cells(&[Cell::new(3, 4), Cell::new(4, 3)], &[Cell::new(3, 3), Cell::new(4, 4)], 1)
+    }
+
+    // A position with black's and white's discs on the given cells and `current_player` to move
+    pub fn from_cells(black: &[Cell], white: &[Cell], current_player: u8) -> Self {
+        let discs = [black, white].map(|cells| cells.iter().fold(0, |discs, &cell| discs | bit(cell)));
+        Self { discs, current_player, passed: false }
+    }
+
+    pub fn get_current_player(&self) -> u8 {
+        self.current_player
+    }
+
+    // 1 or 2 for a disc of that player, 0 for an empty cell
+    pub fn get_player_at(&self, cell: Cell) -> u8 {
+        if self.discs[0] & bit(cell) != 0 {
+            1
+        } else if self.discs[1] & bit(cell) != 0 {
+            2
+        } else {
+            0
+        }
+    }
+
+    // Discs of black and white on the board
+    pub fn count_discs(&self) -> [u8; 2] {
+        self.discs.map(|discs| discs.count_ones() as u8)
+    }
+
+    // Whether the current player's opponent passed on the last move
+    pub fn has_passed(&self) -> bool {
+        self.passed
+    }
+
+    fn own_and_opponent(&self) -> (u64, u64) {
+        let player = self.current_player as usize - 1;
+        (self.discs[player], self.discs[1 - player])
+    }
+
+    // Cells where the current player may put a disc
+    pub fn get_placements(&self) -> Vec<Cell> {
+        let (own, opponent) = self.own_and_opponent();
+        let mut moves = placements(own, opponent);
+        std::iter::from_fn(|| {
+            (moves != 0).then(|| {
+                let index = moves.trailing_zeros() as usize;
+                moves &= moves - 1;
+                Cell::new(index / SIZE, index % SIZE)
+            })
+        })
+        .collect()
+    }
+
+    // A placement that flips something, or a pass when there is none
+    pub fn is_valid_move(&self, mv: Move) -> bool {
+        let (own, opponent) = self.own_and_opponent();
+        let moves = placements(own, opponent);
+        match mv {
+            Move::Place(cell) => cell.row < SIZE && cell.col < SIZE && moves & bit(cell) != 0,
+            Move::Pass => moves == 0,
+        }
+    }
+
+    // Plays `mv`, which must be valid, for the current player and returns the disc count if the
+    // game ended
+    pub fn make_move(&mut self, mv: Move) -> Option<OthelloResult> {
+        let player = self.current_player as usize - 1;
+        let ended = match mv {
+            Move::Place(cell) => {
+                let placed = bit(cell);
+                let flipped = flips(self.discs[player], self.discs[1 - player], placed);
+                self.discs[player] |= placed | flipped;
+                self.discs[1 - player] &= !flipped;
+                self.passed = false;
+                self.is_full()
+            }
+            Move::Pass => std::mem::replace(&mut self.passed, true),
+        };
+        self.current_player = 3 - self.current_player;
+        ended.then(|| OthelloResult { discs: self.count_discs() })
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.discs[0] | self.discs[1] == !0
+    }
+}
+
+impl Default for OthelloBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game for OthelloBoard {
+    type Move = Move;
+    type Outcome = OthelloResult;
+
+    fn get_current_player(&self) -> u8 {
+        OthelloBoard::get_current_player(self)
+    }
+
+    fn get_valid_moves(&self) -> Vec<Move> {
+        let placements = self.get_placements();
+        if placements.is_empty() { vec![Move::Pass] } else { placements.into_iter().map(Move::Place).collect() }
+    }
+
+    fn is_valid_move(&self, mv: Move) -> bool {
+        OthelloBoard::is_valid_move(self, mv)
+    }
+
+    fn make_move(&mut self, mv: Move) -> Option<OthelloResult> {
+        OthelloBoard::make_move(self, mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcts::mcts::MCTS;
+    use crate::mcts::playout::Scoring;
+
+    fn cells(names: &str) -> Vec<Cell> {
+        names.split_whitespace().map(|name| Cell::parse(name).unwrap()).collect()
+    }
+
+    // Plays `moves`, written like "d3" or "pass", in turn and returns the result of the last one
+    fn play(board: &mut OthelloBoard, moves: &str) -> Option<OthelloResult> {
+        let mut result = None;
+        for name in moves.split_whitespace() {
+            let mv = Move::parse(name).unwrap();
+            assert!(result.is_none() && board.is_valid_move(mv), "{}", name);
+            result = board.make_move(mv);
+        }
+        result
+    }
+
+    #[test]
+    fn test_opening_moves() {
+        let board = OthelloBoard::new();
+        assert_eq!(board.count_discs(), [2, 2]);
+        assert_eq!(board.get_placements(), cells("d3 c4 f5 e6"));
+        assert!(!board.is_valid_move(Move::Pass));
+        assert!(!board.is_valid_move(Move::Place(Cell::new(0, 0))));
+        assert_eq!(Move::parse("pass"), Some(Move::Pass));
+        assert_eq!(Move::Place(Cell::new(2, 3)).to_string(), "d3");
+    }
+
+    #[test]
+    fn test_flips() {
+        let mut board = OthelloBoard::new();
+        assert_eq!(play(&mut board, "d3"), None);
+        assert_eq!(board.get_player_at(Cell::parse("d4").unwrap()), 1);
+        assert_eq!(board.count_discs(), [4, 1]);
+
+        // One disc flips lines in two directions at once, and lines never wrap around the edge
+        let mut board = OthelloBoard::from_cells(&cells("a1 a3 g1"), &cells("b1 b2 h1"), 1);
+        assert!(!board.is_valid_move(Move::Place(Cell::parse("a2").unwrap())));
+        play(&mut board, "c1");
+        assert_eq!(board.count_discs(), [6, 1]);
+        for name in ["b1", "b2"] {
+            assert_eq!(board.get_player_at(Cell::parse(name).unwrap()), 1, "{}", name);
+        }
+        assert_eq!(board.get_player_at(Cell::parse("h1").unwrap()), 2);
+    }
+
+    #[test]
+    fn test_passes() {
+        let mut board = OthelloBoard::from_cells(&cells("a1"), &cells("b1 c2"), 1);
+        assert_eq!(board.get_valid_moves(), [Move::Place(Cell::parse("c1").unwrap())]);
+        assert_eq!(play(&mut board, "c1"), None);
+
+        // White's last disc brackets nothing, so white has to pass
+        assert_eq!(board.get_valid_moves(), [Move::Pass]);
+        assert!(!board.is_valid_move(Move::Place(Cell::parse("c3").unwrap())));
+        assert_eq!(play(&mut board, "pass"), None);
+        assert!(board.has_passed());
+
+        // A pass that the opponent answers with a disc does not end the game
+        assert_eq!(play(&mut board, "c3"), None);
+        assert!(!board.has_passed());
+
+        // Without white discs neither player can move, and two passes end the game
+        assert_eq!(board.count_discs(), [5, 0]);
+        assert_eq!(play(&mut board, "pass"), None);
+        let result = play(&mut board, "pass").unwrap();
+        assert_eq!(result, OthelloResult { discs: [5, 0] });
+        assert_eq!(result.reward(), 1);
+        assert_eq!(result.margin(), 5.0 / 64.0);
+        assert_eq!(result.to_string(), "5-0");
+    }
+
+    #[test]
+    fn test_random_games_end() {
+        for _ in 0..20 {
+            let mut board = OthelloBoard::new();
+            let result = loop {
+                let moves = board.get_valid_moves();
+                if let Some(result) = board.make_move(moves[fastrand::usize(..moves.len())]) {
+                    break result;
+                }
+            };
+            assert!(result.discs[0] + result.discs[1] <= 64);
+            assert!(board.is_full() || board.get_valid_moves() == [Move::Pass]);
+        }
+    }
+
+    #[test]
+    fn test_mcts_wipes_out_the_opponent() {
+        // Black's c1 flips both white discs, while c3 only flips one
+        let board = OthelloBoard::from_cells(&cells("a1 a3"), &cells("b1 b2"), 1);
+        for scoring in [Scoring::WinLoss, Scoring::Margin] {
+            let mcts = MCTS::new(1.414, 1, 300, 10).with_scoring(scoring);
+            assert_eq!(mcts.search(board), Move::Place(Cell::parse("c1").unwrap()), "{:?}", scoring);
+        }
+    }
+}
@@ -0,0 +1,12 @@
+pub mod config;
+pub mod games;
+pub mod mcts;
+pub mod bot;
+pub mod player;
+pub mod controller;
+pub mod board_game;
+pub mod editor;
+pub mod replay;
+pub mod saved_game;
+pub mod tournament;
+pub mod transposition;
\ No newline at end of file
@@ -1,54 +1,333 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 use rayon::prelude::*;
-use crate::games::connect4::bitboard::BitBoard;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use crate::games::Game;
 use super::node::{Node, RootNode};
+use super::playout::{self, PlayoutPolicy, RandomPlayout, Scoring};
+use super::rave::{AmafUpdate, Playout};
+use super::selection::{select_move, ChildStats, MoveSelection};
+use super::session::SearchSession;
+use super::shared::{SharedNode, Step};
 
-pub struct MCTS {
+// How the search threads share their work
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ParallelMode {
+    RootParallel,  // Every thread grows a private tree, root children are merged at the end
+    SharedTree,    // All threads grow one tree, spread out by virtual loss
+}
+
+// Summary of a finished search
+#[derive(Clone, Debug)]
+pub struct SearchStats<M> {
+    pub best_move: M,
+    pub total_visits: u32,
+    pub reused_visits: u32,
+    pub max_depth: usize,
+    pub elapsed: Duration,
+    pub result: Option<i32>,  // Proven result of the root from player 1's point of view
+    pub children: Vec<ChildStats<M>>,  // Statistics of every expanded root child
+    pub best_line: Vec<M>,             // The best move followed by the most visited replies below it
+}
+
+// How often `analyze_with_progress` reports on a running search
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[allow(clippy::upper_case_acronyms)]
+pub struct MCTS<G: Game> {
     exploration_constant: f32,
     num_threads: usize,
     time_limit: Duration,
     n_simulations: u32,
+    parallel_mode: ParallelMode,
+    virtual_loss: u32,
+    merge_depth: usize,
+    move_selection: MoveSelection,
+    rave_equivalence: Option<f32>,
+    playout_policy: Box<dyn PlayoutPolicy<G>>,
+    scoring: Scoring,
+    pool: ThreadPool,
+    game: PhantomData<G>,
 }
 
-impl MCTS {
+impl<G: Game> MCTS<G> {
     pub fn new(exploration_constant: f32, num_threads: usize, time_limit_ms: u64, n_simulations: u32) -> Self {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .expect("Failed to build the search thread pool");
+
         Self {
             exploration_constant,
             num_threads,
             time_limit: Duration::from_millis(time_limit_ms),
             n_simulations,
+            parallel_mode: ParallelMode::RootParallel,
+            virtual_loss: 1,
+            merge_depth: 1,
+            move_selection: MoveSelection::MostVisits,
+            rave_equivalence: None,
+            playout_policy: Box::new(RandomPlayout),
+            scoring: Scoring::WinLoss,
+            pool,
+            game: PhantomData,
         }
     }
 
-    pub fn search(&self, board: BitBoard, player_number: u8) -> usize {
-        let mut root = RootNode::new(board);
+    pub fn with_parallel_mode(mut self, parallel_mode: ParallelMode) -> Self {
+        self.parallel_mode = parallel_mode;
+        self
+    }
+
+    // Number of iterations' worth of losses a thread adds to each node on its path in shared-tree mode
+    pub fn with_virtual_loss(mut self, virtual_loss: u32) -> Self {
+        self.virtual_loss = virtual_loss;
+        self
+    }
+
+    // Number of levels below the root whose statistics are merged across threads in root-parallel mode
+    pub fn with_merge_depth(mut self, merge_depth: usize) -> Self {
+        self.merge_depth = merge_depth;
+        self
+    }
+
+    pub fn with_move_selection(mut self, move_selection: MoveSelection) -> Self {
+        self.move_selection = move_selection;
+        self
+    }
+
+    // Enables RAVE: selection blends in all-moves-as-first statistics, which weigh as much as the real
+    // statistics once a child has `equivalence` visits
+    pub fn with_rave(mut self, equivalence: f32) -> Self {
+        self.rave_equivalence = Some(equivalence);
+        self
+    }
+
+    // Replaces the default uniformly random playouts
+    pub fn with_playout_policy(mut self, playout_policy: impl PlayoutPolicy<G> + 'static) -> Self {
+        self.playout_policy = Box::new(playout_policy);
+        self
+    }
+
+    // Scores playouts by the margin of their outcome instead of by win, draw or loss. Proven results
+    // still count as full wins and losses, so a sure win comes before a big one.
+    pub fn with_scoring(mut self, scoring: Scoring) -> Self {
+        self.scoring = scoring;
+        self
+    }
+
+    pub fn search(&self, board: G) -> G::Move {
+        self.search_with_session(&mut SearchSession::new(), board)
+    }
+
+    // Like `search`, but continues from the trees kept in `session` when `board` follows from its last search
+    pub fn search_with_session(&self, session: &mut SearchSession<G>, board: G) -> G::Move {
+        self.analyze(session, board).best_move
+    }
+
+    // Runs a full search and reports the chosen move together with the search statistics
+    pub fn analyze(&self, session: &mut SearchSession<G>, board: G) -> SearchStats<G::Move> {
+        self.analyze_until(session, board, &AtomicBool::new(false))
+    }
+
+    // Like `analyze`, but also ends the search early once `stop` is set, e.g. from another thread
+    pub fn analyze_until(&self, session: &mut SearchSession<G>, board: G, stop: &AtomicBool) -> SearchStats<G::Move> {
+        self.analyze_with_progress(session, board, stop, |_| {})
+    }
+
+    // Like `analyze_until`, but hands the statistics so far to `on_progress` every `PROGRESS_INTERVAL`
+    // of searching and once more at the end, so that the search can be watched while it runs
+    pub fn analyze_with_progress(
+        &self,
+        session: &mut SearchSession<G>,
+        board: G,
+        stop: &AtomicBool,
+        on_progress: impl FnMut(&SearchStats<G::Move>),
+    ) -> SearchStats<G::Move> {
         let start_time = Instant::now();
+        match self.parallel_mode {
+            ParallelMode::RootParallel => self.search_root_parallel(session, board, start_time, stop, on_progress),
+            ParallelMode::SharedTree => self.search_shared_tree(session, board, start_time, stop, on_progress),
+        }
+    }
 
-        // Create thread-local search trees and run them in parallel
-        let thread_results: Vec<Node> = (0..self.num_threads)
-            .into_par_iter()
-            .map(|_| {
-                let mut thread_root = root.create_thread_tree();
-                while start_time.elapsed() < self.time_limit {
-                    self.run_iteration(&mut thread_root, player_number);
-                }
-                thread_root
-            })
-            .collect();
+    // Whether the search has to end, other than by proving the root
+    fn is_stopped(&self, start_time: Instant, stop: &AtomicBool) -> bool {
+        stop.load(Ordering::Relaxed) || start_time.elapsed() >= self.time_limit
+    }
+
+    fn search_root_parallel(
+        &self,
+        session: &mut SearchSession<G>,
+        board: G,
+        start_time: Instant,
+        stop: &AtomicBool,
+        mut on_progress: impl FnMut(&SearchStats<G::Move>),
+    ) -> SearchStats<G::Move> {
+        let mut thread_roots = session.take_trees(&RootNode::new(board.clone(), self.merge_depth), &board, self.num_threads);
+        let reused_visits: u32 = thread_roots.iter().map(|thread_root| thread_root.get_visits()).sum();
+        let mut max_depth = 0;
+
+        // Run the thread-local search trees in parallel, one progress interval at a time,
+        // until time runs out or one of them solves the root
+        let solved = AtomicBool::new(false);
+        loop {
+            let interval_end = (start_time.elapsed() + PROGRESS_INTERVAL).min(self.time_limit);
+            let thread_results: Vec<(Node<G>, usize)> = self.pool.install(|| {
+                thread_roots
+                    .into_par_iter()
+                    .map(|mut thread_root| {
+                        let mut max_depth = 0;
+                        while !thread_root.is_terminal() && !solved.load(Ordering::Relaxed) && !stop.load(Ordering::Relaxed)
+                            && start_time.elapsed() < interval_end
+                        {
+                            max_depth = max_depth.max(self.run_iteration(&mut thread_root));
+                        }
+                        if thread_root.is_terminal() {
+                            solved.store(true, Ordering::Relaxed);
+                        }
+                        (thread_root, max_depth)
+                    })
+                    .collect()
+            });
+            max_depth = thread_results.iter().map(|(_, depth)| *depth).fold(max_depth, usize::max);
+            thread_roots = thread_results.into_iter().map(|(thread_root, _)| thread_root).collect();
+
+            // Merge the thread results into a fresh root
+            let mut root = RootNode::new(board.clone(), self.merge_depth);
+            for thread_root in &thread_roots {
+                root.merge_thread_results(thread_root);
+            }
+            let best_move = root.get_best_move(self.move_selection);
+            let stats = SearchStats {
+                best_move,
+                total_visits: root.get_total_visits(),
+                reused_visits,
+                max_depth,
+                elapsed: start_time.elapsed(),
+                result: root.get_result(),
+                children: root.get_child_stats(),
+                best_line: best_line(&thread_roots, best_move),
+            };
+            on_progress(&stats);
+
+            if solved.load(Ordering::Relaxed) || self.is_stopped(start_time, stop) {
+                session.store_trees(thread_roots);
+                return stats;
+            }
+        }
+    }
+
+    fn search_shared_tree(
+        &self,
+        session: &mut SearchSession<G>,
+        board: G,
+        start_time: Instant,
+        stop: &AtomicBool,
+        mut on_progress: impl FnMut(&SearchStats<G::Move>),
+    ) -> SearchStats<G::Move> {
+        let root = session.take_shared_tree(&board);
+        let reused_visits = root.get_visits();
+        let mut max_depth = 0;
+
+        loop {
+            let interval_end = (start_time.elapsed() + PROGRESS_INTERVAL).min(self.time_limit);
+            max_depth = self.pool.install(|| {
+                (0..self.num_threads)
+                    .into_par_iter()
+                    .map(|_| {
+                        let mut max_depth = 0;
+                        while !root.is_terminal() && !stop.load(Ordering::Relaxed) && start_time.elapsed() < interval_end {
+                            max_depth = max_depth.max(self.run_shared_iteration(&root));
+                        }
+                        max_depth
+                    })
+                    .max()
+                    .unwrap_or(0)
+            }).max(max_depth);
+
+            let child_stats = root.get_child_stats();
+            let best_move = select_move(&child_stats, board.get_current_player(), self.move_selection)
+                .unwrap_or_else(|| board.get_valid_moves()[0]);
+            let mut line = vec![best_move];
+            if let Some(child) = root.get_child(best_move) {
+                line.extend(child.get_principal_variation());
+            }
+            let stats = SearchStats {
+                best_move,
+                total_visits: child_stats.iter().map(|child| child.visits).sum(),
+                reused_visits,
+                max_depth,
+                elapsed: start_time.elapsed(),
+                result: root.get_result(),
+                children: child_stats,
+                best_line: line,
+            };
+            on_progress(&stats);
+
+            if root.is_terminal() || self.is_stopped(start_time, stop) {
+                session.store_shared_tree(root);
+                return stats;
+            }
+        }
+    }
+
+    // Runs one selection, expansion, simulation and backpropagation pass on the shared tree.
+    // Returns the depth at which the simulation started.
+    fn run_shared_iteration(&self, root: &Arc<SharedNode<G>>) -> usize {
+        let virtual_loss = self.virtual_loss * self.n_simulations;
+        let mut path = vec![Arc::clone(root)];
+        root.add_virtual_loss(virtual_loss);
 
-        // Merge results back to root
-        for thread_root in thread_results {
-            root.merge_thread_results(&thread_root);
+        // Selection and expansion
+        loop {
+            let current = path.last().unwrap();
+            if current.is_terminal() {
+                break;
+            }
+
+            match current.step(self.exploration_constant, self.rave_equivalence, virtual_loss) {
+                Step::Selected(child) => path.push(child),
+                Step::Expanded(child) => {
+                    path.push(child);
+                    break;
+                }
+                Step::Leaf => break,
+            }
         }
 
-        println!("Root visits: {}", root.get_total_visits());
+        // Simulation
+        let leaf = path.last().unwrap();
+        let (result, playouts) = self.simulate(&leaf.board, leaf.get_result());
+        let amaf = self.rave_equivalence.map(|_| AmafUpdate {
+            tree_moves: path.windows(2)
+                .map(|pair| (pair[0].board.get_current_player(), pair[1].get_action().unwrap()))
+                .collect(),
+            playouts,
+        });
 
-        root.get_best_move()
+        // Backpropagation, proving nodes bottom-up for as long as their child on the path is proven
+        let mut child_proven = false;
+        for (depth, node) in path.iter().enumerate().rev() {
+            node.update(result, self.n_simulations, virtual_loss);
+            if let Some(amaf) = &amaf {
+                node.update_amaf(amaf, depth);
+            }
+            if child_proven {
+                node.update_proof();
+            }
+            child_proven = node.is_terminal();
+        }
+        path.len() - 1
     }
 
-    fn run_iteration(&self, root: &mut Node, player_number: u8) {
+    // Returns the depth at which the simulation started
+    fn run_iteration(&self, root: &mut Node<G>) -> usize {
         let mut current = &mut *root;
         let mut path_indices = Vec::new();
+        let mut tree_moves = Vec::new();
         
         // Selection
         while !current.is_terminal() && current.is_fully_expanded() {
@@ -56,43 +335,71 @@ impl MCTS {
                 break;
             }
             
-            let child_idx = current.get_best_child_index(self.exploration_constant);
+            let child_idx = current.get_best_child_index(self.exploration_constant, self.rave_equivalence);
+            let player = current.board.get_current_player();
                 
             path_indices.push(child_idx);
             current = &mut current.children[child_idx];
+            tree_moves.push((player, current.get_action().unwrap()));
         }
-    
-        let result = if current.is_terminal() {
-            current.result.unwrap() * self.n_simulations as i32
-        } else if !current.is_fully_expanded() {
-            let new_node = current.expand();
-            if new_node.is_terminal() {
-                new_node.result.unwrap() * self.n_simulations as i32
-            } else {
-                new_node.simulate(player_number, self.n_simulations)
-            }
-        } else {
-            current.simulate(player_number, self.n_simulations)
-        };
+
+        // Expansion
+        if !current.is_terminal() && !current.is_fully_expanded() {
+            let player = current.board.get_current_player();
+            path_indices.push(current.children.len());
+            current = current.expand();
+            tree_moves.push((player, current.get_action().unwrap()));
+        }
+
+        // Simulation
+        let (result, playouts) = self.simulate(&current.board, current.result);
     
         // Backpropagation
-        let mut current = root;
-        current.update(result, self.n_simulations);
-        
-        for &idx in &path_indices {
-            current = &mut current.children[idx];
-            current.update(result, self.n_simulations);
+        let amaf = self.rave_equivalence.map(|_| AmafUpdate { tree_moves, playouts });
+        root.backpropagate(&path_indices, result, self.n_simulations, amaf.as_ref());
+        path_indices.len()
+    }
+
+    // Plays out `board` unless its result is already known.
+    // Returns the summed reward and, when RAVE is enabled, the playouts that produced it.
+    fn simulate(&self, board: &G, result: Option<i32>) -> (i32, Vec<Playout<G::Move>>) {
+        let n_simulations = self.n_simulations;
+        match (result, self.rave_equivalence) {
+            (Some(result), None) => (result * n_simulations as i32, Vec::new()),
+            (Some(result), Some(_)) => {
+                let reward = result * n_simulations as i32;
+                (reward, vec![Playout { moves: Vec::new(), visits: n_simulations, reward }])
+            }
+            (None, None) => (playout::simulate_from(board, self.playout_policy.as_ref(), self.scoring, n_simulations), Vec::new()),
+            (None, Some(_)) => {
+                let playouts = playout::record_playouts(board, self.playout_policy.as_ref(), self.scoring, n_simulations);
+                (playouts.iter().map(|playout| playout.reward).sum(), playouts)
+            }
         }
     }
 }
 
+// The best move followed by the most visited line below it, in the thread tree that searched it most
+fn best_line<G: Game>(thread_roots: &[Node<G>], best_move: G::Move) -> Vec<G::Move> {
+    let mut line = vec![best_move];
+    let child = thread_roots.iter()
+        .flat_map(|thread_root| thread_root.children.iter())
+        .filter(|child| child.get_action() == Some(best_move))
+        .max_by_key(|child| child.get_visits());
+    if let Some(child) = child {
+        line.extend(child.get_principal_variation());
+    }
+    line
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::games::connect4::bitboard::BitBoard;
 
     #[test]
     fn test_mcts_finds_winning_moves() {
-        let mcts = MCTS::new(1.414, 4, 1000);
+        let mcts = MCTS::new(1.414, 4, 1000, 10);
         
         // Test horizontal win
         let mut board = BitBoard::new();
@@ -103,7 +410,7 @@ mod tests {
         board.make_move(2); // Player 1
         board.make_move(6); // Player 2
         
-        let best_move = mcts.search(board, 1);
+        let best_move = mcts.search(board);
         assert_eq!(best_move, 3, "Failed to find horizontal winning move");
 
         // Test vertical win
@@ -115,7 +422,7 @@ mod tests {
         board.make_move(0); // Player 1
         board.make_move(1); // Player 2
         
-        let best_move = mcts.search(board, 1);
+        let best_move = mcts.search(board);
         assert_eq!(best_move, 0, "Failed to find vertical winning move");
 
         // Test diagonal win
@@ -131,13 +438,13 @@ mod tests {
         board.make_move(3); // Player 1
         board.make_move(6); // Player 2
         
-        let best_move = mcts.search(board, 1);
+        let best_move = mcts.search(board);
         assert_eq!(best_move, 3, "Failed to find diagonal winning move");
     }
 
     #[test]
     fn test_mcts_blocks_opponent_win() {
-        let mcts = MCTS::new(1.414, 4, 1000);
+        let mcts = MCTS::new(1.414, 4, 1000, 10);
         let mut board = BitBoard::new();
         board.make_move(5); // Player 1
         board.make_move(0); // Player 2
@@ -146,7 +453,228 @@ mod tests {
         board.make_move(6); // Player 1
         board.make_move(0); // Player 2
         
-        let best_move = mcts.search(board, 1);
+        let best_move = mcts.search(board);
         assert_eq!(best_move, 0, "Failed to find blocking move");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_shared_tree_finds_winning_move() {
+        let mcts = MCTS::new(1.414, 4, 500, 10).with_parallel_mode(ParallelMode::SharedTree);
+        let mut board = BitBoard::new();
+        for col in [0, 6, 1, 6, 2, 6] {
+            board.make_move(col);
+        }
+
+        let best_move = mcts.search(board);
+        assert_eq!(best_move, 3, "Failed to find horizontal winning move");
+    }
+
+    #[test]
+    fn test_shared_tree_blocks_opponent_win() {
+        let mcts = MCTS::new(1.414, 4, 500, 10).with_parallel_mode(ParallelMode::SharedTree);
+        let mut board = BitBoard::new();
+        for col in [5, 0, 6, 0, 6, 0] {
+            board.make_move(col);
+        }
+
+        let stats = mcts.analyze(&mut SearchSession::new(), board);
+        assert_eq!(stats.best_move, 0, "Failed to find blocking move");
+        assert!(stats.max_depth > 1);
+    }
+
+    #[test]
+    fn test_root_parallel_selects_by_merged_value() {
+        let mcts = MCTS::new(1.414, 4, 500, 10)
+            .with_merge_depth(2)
+            .with_move_selection(MoveSelection::SecureValue);
+        let mut board = BitBoard::new();
+        for col in [5, 0, 6, 0, 6, 0] {
+            board.make_move(col);
+        }
+
+        let best_move = mcts.search(board);
+        assert_eq!(best_move, 0, "Failed to find blocking move");
+    }
+
+    #[test]
+    fn test_search_stops_once_root_is_proven() {
+        for mode in [ParallelMode::RootParallel, ParallelMode::SharedTree] {
+            let mcts = MCTS::new(1.414, 2, 5000, 10).with_parallel_mode(mode);
+
+            // Player 1 wins immediately in column 3
+            let mut board = BitBoard::new();
+            for col in [0, 6, 1, 6, 2, 6] {
+                board.make_move(col);
+            }
+            let stats = mcts.analyze(&mut SearchSession::new(), board);
+            assert_eq!(stats.best_move, 3);
+            assert_eq!(stats.result, Some(1));
+            assert!(stats.elapsed < Duration::from_millis(2500), "{:?}: search did not stop early", mode);
+
+            // Player 2 has an open three on the bottom row, so every move of player 1 loses
+            let mut board = BitBoard::new();
+            for col in [6, 2, 6, 3, 0, 4] {
+                board.make_move(col);
+            }
+            let stats = mcts.analyze(&mut SearchSession::new(), board);
+            assert_eq!(stats.result, Some(-1));
+            assert!(stats.elapsed < Duration::from_millis(2500), "{:?}: search did not stop early", mode);
+        }
+    }
+
+    #[test]
+    fn test_search_stops_when_asked() {
+        for mode in [ParallelMode::RootParallel, ParallelMode::SharedTree] {
+            let mcts = MCTS::new(1.414, 2, 5000, 10).with_parallel_mode(mode);
+            let stats = mcts.analyze_until(&mut SearchSession::new(), BitBoard::new(), &AtomicBool::new(true));
+            assert!(stats.elapsed < Duration::from_millis(2500), "{:?}: search did not stop", mode);
+            assert!(BitBoard::new().is_valid_move(stats.best_move));
+        }
+    }
+
+    #[test]
+    fn test_progress_reports_running_search() {
+        for mode in [ParallelMode::RootParallel, ParallelMode::SharedTree] {
+            let mcts = MCTS::new(1.414, 2, 350, 10).with_parallel_mode(mode);
+            let mut reports: Vec<SearchStats<usize>> = Vec::new();
+            let stats = mcts.analyze_with_progress(&mut SearchSession::new(), BitBoard::new(), &AtomicBool::new(false), |stats| {
+                reports.push(stats.clone());
+            });
+
+            assert!(reports.len() >= 3, "{:?}: only {} reports", mode, reports.len());
+            assert!(reports.windows(2).all(|pair| pair[0].total_visits <= pair[1].total_visits));
+            assert_eq!(reports.last().unwrap().total_visits, stats.total_visits);
+
+            // The best line is a playable sequence starting with the best move
+            assert_eq!(stats.best_line[0], stats.best_move);
+            assert!(stats.best_line.len() > 1);
+            let mut board = BitBoard::new();
+            for &col in &stats.best_line {
+                assert!(board.is_valid_move(col), "{:?}: illegal best line {:?}", mode, stats.best_line);
+                board.make_move(col);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rave_blocks_opponent_win() {
+        for mode in [ParallelMode::RootParallel, ParallelMode::SharedTree] {
+            let mcts = MCTS::new(1.414, 2, 500, 10)
+                .with_parallel_mode(mode)
+                .with_rave(300.0);
+            let mut board = BitBoard::new();
+            for col in [5, 0, 6, 0, 6, 0] {
+                board.make_move(col);
+            }
+
+            let best_move = mcts.search(board);
+            assert_eq!(best_move, 0, "{:?}: failed to find blocking move", mode);
+        }
+    }
+
+    // Player 1 may stall into a position without moves, lose at once, or hand player 2 a move
+    // that stalls
+    #[derive(Clone, PartialEq)]
+    enum Stall {
+        Start,
+        Stuck,
+        Handed,
+    }
+
+    impl Game for Stall {
+        type Move = u8;
+        type Outcome = i32;
+
+        fn get_current_player(&self) -> u8 {
+            if *self == Stall::Handed { 2 } else { 1 }
+        }
+
+        fn get_valid_moves(&self) -> Vec<u8> {
+            match self {
+                Stall::Start => vec![0, 1, 2],
+                Stall::Stuck => Vec::new(),
+                Stall::Handed => vec![0],
+            }
+        }
+
+        fn make_move(&mut self, mv: u8) -> Option<i32> {
+            match (&*self, mv) {
+                (Stall::Start, 1) => Some(-1),
+                (Stall::Start, 2) => {
+                    *self = Stall::Handed;
+                    None
+                }
+                _ => {
+                    *self = Stall::Stuck;
+                    None
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_positions_without_moves_are_draws() {
+        for mode in [ParallelMode::RootParallel, ParallelMode::SharedTree] {
+            for rave in [None, Some(300.0)] {
+                let mut mcts = MCTS::new(1.414, 2, 200, 10).with_parallel_mode(mode);
+                if let Some(equivalence) = rave {
+                    mcts = mcts.with_rave(equivalence);
+                }
+                let stats = mcts.analyze(&mut SearchSession::new(), Stall::Start);
+                assert_ne!(stats.best_move, 1, "{:?}: chose the loss", mode);
+                assert_eq!(stats.result, Some(0), "{:?}", mode);
+            }
+        }
+    }
+
+    // A result with a score, whose margin is a tenth of it
+    #[derive(Copy, Clone, Debug)]
+    struct Score(i32);
+
+    impl crate::games::Outcome for Score {
+        fn reward(&self) -> i32 {
+            self.0.signum()
+        }
+
+        fn margin(&self) -> f32 {
+            self.0 as f32 / 10.0
+        }
+    }
+
+    // Player 1 picks a win by 1 or by 9, then both players play moves that change nothing for long
+    // enough that the search cannot prove either win
+    #[derive(Clone, PartialEq)]
+    struct Race {
+        score: i32,
+        plies: u8,
+    }
+
+    impl Game for Race {
+        type Move = i32;
+        type Outcome = Score;
+
+        fn get_current_player(&self) -> u8 {
+            self.plies % 2 + 1
+        }
+
+        fn get_valid_moves(&self) -> Vec<i32> {
+            if self.plies == 0 { vec![1, 9] } else { vec![0, 1] }
+        }
+
+        fn make_move(&mut self, mv: i32) -> Option<Score> {
+            if self.plies == 0 {
+                self.score = mv;
+            }
+            self.plies += 1;
+            (self.plies == 30).then_some(Score(self.score))
+        }
+    }
+
+    #[test]
+    fn test_margin_scoring_prefers_bigger_wins() {
+        let mcts = MCTS::new(1.414, 2, 300, 10).with_scoring(Scoring::Margin);
+        let stats = mcts.analyze(&mut SearchSession::new(), Race { score: 0, plies: 0 });
+        assert_eq!(stats.best_move, 9);
+        assert_eq!(stats.result, None);
+    }
+}
@@ -1,3 +1,13 @@
 mod node;
+mod session;
+mod shared;
+mod selection;
+mod rave;
 
-pub mod mcts;
\ No newline at end of file
+pub mod playout;
+
+#[allow(clippy::module_inception)]
+pub mod mcts;
+
+pub use session::SearchSession;
+pub use selection::{perspective, ChildStats, MoveSelection};
\ No newline at end of file
@@ -1,27 +1,42 @@
-use crate::games::connect4::bitboard::BitBoard;
+use crate::games::{Game, Outcome};
+use super::rave::{self, AmafUpdate};
+use super::selection::{perspective, prove_from_children, select_move, ChildStats, MoveSelection};
 use std::collections::HashMap;
+use std::hash::Hash;
 
 // Regular node for the tree exploration
 #[derive(Clone)]  // Explicit derive
-pub struct Node {
-    pub board: BitBoard,
+pub struct Node<G: Game> {
+    pub board: G,
     visits: u32,
     wins: i32,
-    action: Option<usize>,
-    pub children: Vec<Node>,
-    untried_moves: Vec<usize>,
+    action: Option<G::Move>,
+    pub children: Vec<Node<G>>,
+    untried_moves: Vec<G::Move>,
     pub result: Option<i32>,
+    amaf_visits: u32,  // RAVE statistics of `action`, only filled in when RAVE is enabled
+    amaf_wins: i32,
 }
 
-// Special root node that supports parallel access
-pub struct RootNode {
-    board: BitBoard,
-    move_to_visits: HashMap<usize, u32>,
+// Special root node that merges the statistics of the thread-local trees
+pub struct RootNode<G: Game> {
+    board: G,
+    merge_depth: usize,  // Number of levels below the root that are merged
+    stats: MergedStats<G::Move>,
 }
 
-impl Node {
-    pub fn new(board: BitBoard, action: Option<usize>, result: Option<i32>) -> Self {
+// Statistics of one position summed over all thread trees
+struct MergedStats<M> {
+    visits: u32,
+    wins: i32,
+    result: Option<i32>,
+    children: HashMap<M, MergedStats<M>>,
+}
+
+impl<G: Game> Node<G> {
+    pub fn new(board: G, action: Option<G::Move>, result: Option<i32>) -> Self {
         let untried_moves = board.get_valid_moves();
+        let result = result.or(untried_moves.is_empty().then_some(0));  // Stuck without moves: a draw
         let children = Vec::new();
         Self {
             board,
@@ -31,25 +46,49 @@ impl Node {
             children,
             untried_moves,
             result,
+            amaf_visits: 0,
+            amaf_wins: 0,
         }
     }
 
+    pub fn get_visits(&self) -> u32 {
+        self.visits
+    }
+
+    pub fn get_action(&self) -> Option<G::Move> {
+        self.action
+    }
+
     pub fn get_win_ratio(&self) -> f32 {
         self.wins as f32 / (self.visits + 1) as f32
     }
 
-    pub fn get_best_child_index(&self, exploration_constant: f32) -> usize {
+    // Picks the child to descend into; with `rave_equivalence` set, the win ratio is blended with the AMAF statistics
+    pub fn get_best_child_index(&self, exploration_constant: f32, rave_equivalence: Option<f32>) -> usize {
         self.children.iter()
             .enumerate()
             .max_by(|(_, a), (_, b)| {
-                self.ucb1(a, exploration_constant).partial_cmp(&self.ucb1(b, exploration_constant)).unwrap()
+                self.selection_score(a, exploration_constant, rave_equivalence)
+                    .partial_cmp(&self.selection_score(b, exploration_constant, rave_equivalence))
+                    .unwrap()
             })
             .map(|(i, _)| i)
             .unwrap()
     }
+
+    // UCB1, except that moves proven to lose are never selected
+    fn selection_score(&self, child: &Node<G>, exploration_constant: f32, rave_equivalence: Option<f32>) -> f32 {
+        match child.result {
+            Some(result) if perspective(result, self.board.get_current_player()) < 0 => f32::NEG_INFINITY,
+            _ => self.ucb1(child, exploration_constant, rave_equivalence),
+        }
+    }
     
-    pub fn ucb1(&self, child: &Node, exploration_constant: f32) -> f32 {
-        let win_ratio = child.get_win_ratio();
+    pub fn ucb1(&self, child: &Node<G>, exploration_constant: f32, rave_equivalence: Option<f32>) -> f32 {
+        let win_ratio = match rave_equivalence {
+            Some(equivalence) => rave::blend(child.get_win_ratio(), child.visits, child.amaf_wins, child.amaf_visits, equivalence),
+            None => child.get_win_ratio(),
+        };
         let exploration = exploration_constant * ((self.visits as f32).ln() / child.visits as f32).sqrt();
         
         if self.board.get_current_player() == 1 {
@@ -59,12 +98,12 @@ impl Node {
         }
     }
     
-    pub fn expand(&mut self) -> &mut Node {
+    pub fn expand(&mut self) -> &mut Node<G> {
         let move_index = fastrand::usize(..self.untried_moves.len());
         let action = self.untried_moves.swap_remove(move_index);
         
-        let mut new_board = self.board;
-        let result = new_board.make_move(action);
+        let mut new_board = self.board.clone();
+        let result = new_board.make_move(action).map(|outcome| outcome.reward());
         
         self.children.push(Node::new(new_board, Some(action), result));
         self.children.last_mut().unwrap()
@@ -75,71 +114,138 @@ impl Node {
         self.wins += wins;
     }
 
-    pub fn is_fully_expanded(&self) -> bool {
-        self.untried_moves.is_empty()
+    // Adds `wins` to this node and to the nodes along `path`, then backs proven results up the same path.
+    // With `amaf` set, the AMAF statistics of the children of every node on the path are updated as well.
+    pub fn backpropagate(&mut self, path: &[usize], wins: i32, n_simulations: u32, amaf: Option<&AmafUpdate<G::Move>>) {
+        self.update(wins, n_simulations);
+        if let Some(amaf) = amaf {
+            let depth = amaf.tree_moves.len() - path.len();
+            let player = self.board.get_current_player();
+            for child in &mut self.children {
+                if let Some(action) = child.action {
+                    amaf.for_move(depth, player, action, |visits, reward| {
+                        child.amaf_visits += visits;
+                        child.amaf_wins += reward;
+                    });
+                }
+            }
+        }
+        if let Some((&idx, rest)) = path.split_first() {
+            self.children[idx].backpropagate(rest, wins, n_simulations, amaf);
+            if self.result.is_none() && self.children[idx].is_terminal() {
+                self.result = prove_from_children(
+                    self.board.get_current_player(),
+                    self.children.iter().map(|child| child.result),
+                    self.is_fully_expanded(),
+                );
+            }
+        }
     }
 
-    pub fn is_terminal(&self) -> bool {
-        self.result.is_some()
+    // Detaches the node for `board` if it lies at most `max_depth` plies below this one
+    pub fn take_descendant(self, board: &G, max_depth: usize) -> Option<Node<G>> {
+        if self.board == *board {
+            return Some(self);
+        }
+        if max_depth == 0 {
+            return None;
+        }
+        self.children
+            .into_iter()
+            .find_map(|child| child.take_descendant(board, max_depth - 1))
     }
 
-    pub fn simulate(&self, player_number: u8, n_simulations: u32) -> i32 {
-        let mut wins = 0;
-        for _ in 0..n_simulations {
-            let result = self._simulate(self.board, player_number);
-            wins += result;
+    // Moves along the most visited children below this node
+    pub fn get_principal_variation(&self) -> Vec<G::Move> {
+        let mut line = Vec::new();
+        let mut node = self;
+        while let Some(child) = node.children.iter().max_by_key(|child| child.visits) {
+            line.extend(child.action);
+            node = child;
         }
-        wins
+        line
     }
 
-    fn _simulate(&self, mut board: BitBoard, player_number: u8) -> i32 {
-        let mut current_player = player_number;
-
-        loop {
-            let valid_moves = board.get_valid_moves();
+    pub fn is_fully_expanded(&self) -> bool {
+        self.untried_moves.is_empty()
+    }
 
-            let mov = valid_moves[fastrand::usize(..valid_moves.len())];
-            let result = board.make_move(mov);
-            if result.is_some() {
-                return result.unwrap();
-            }
-        }
+    pub fn is_terminal(&self) -> bool {
+        self.result.is_some()
     }
 }
 
-impl RootNode {
-    pub fn new(board: BitBoard) -> Self {
-        let untried_moves = board.get_valid_moves();
-        let mut move_to_visits = HashMap::new();
-        for m in untried_moves {
-            move_to_visits.insert(m, 0);
+impl<G: Game> RootNode<G> {
+    pub fn new(board: G, merge_depth: usize) -> Self {
+        let mut stats = MergedStats::default();
+        for m in board.get_valid_moves() {
+            stats.children.insert(m, MergedStats::default());
         }
         Self {
             board,
-            move_to_visits,
+            merge_depth,
+            stats,
         }
     }
 
-    pub fn create_thread_tree(&self) -> Node {
-        Node::new(self.board, None, None)
+    pub fn create_thread_tree(&self) -> Node<G> {
+        Node::new(self.board.clone(), None, None)
     }
 
-    pub fn merge_thread_results(&mut self, thread_node: &Node) {
-        for child in &thread_node.children {
-            if let Some(action) = child.action {
-                self.move_to_visits.entry(action).and_modify(|v| *v += child.visits);
-            }
-        }
+    pub fn merge_thread_results(&mut self, thread_node: &Node<G>) {
+        self.stats.merge(thread_node, self.merge_depth);
     }
 
-    pub fn get_best_move(&self) -> usize {
-        *self.move_to_visits.iter()
-            .max_by_key(|(_, &visits)| visits)
-            .unwrap()
-            .0
+    pub fn get_child_stats(&self) -> Vec<ChildStats<G::Move>> {
+        self.stats.children.iter()
+            .map(|(&action, child)| ChildStats {
+                action,
+                visits: child.visits,
+                wins: child.wins,
+                result: child.result,
+            })
+            .collect()
+    }
+
+    pub fn get_best_move(&self, selection: MoveSelection) -> G::Move {
+        select_move(&self.get_child_stats(), self.board.get_current_player(), selection).unwrap()
     }
     
     pub fn get_total_visits(&self) -> u32 {
-        self.move_to_visits.values().sum()
+        self.stats.children.values().map(|child| child.visits).sum()
+    }
+
+    pub fn get_result(&self) -> Option<i32> {
+        self.stats.result
     }
-}
\ No newline at end of file
+}
+
+impl<M: Copy + Eq + Hash> MergedStats<M> {
+    // Adds the statistics of `node` and of its descendants up to `depth` levels below it
+    fn merge<G: Game<Move = M>>(&mut self, node: &Node<G>, depth: usize) {
+        self.visits += node.visits;
+        self.wins += node.wins;
+        if node.result.is_some() {
+            self.result = node.result;
+        }
+        if depth == 0 {
+            return;
+        }
+        for child in &node.children {
+            if let Some(action) = child.action {
+                self.children.entry(action).or_default().merge(child, depth - 1);
+            }
+        }
+    }
+}
+
+impl<M> Default for MergedStats<M> {
+    fn default() -> Self {
+        Self {
+            visits: 0,
+            wins: 0,
+            result: None,
+            children: HashMap::new(),
+        }
+    }
+}
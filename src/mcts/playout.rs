@@ -0,0 +1,77 @@
+use crate::games::{Game, Outcome};
+use super::rave::Playout;
+
+// Chooses the moves of a simulated game
+pub trait PlayoutPolicy<G: Game>: Send + Sync {
+    // Picks one of `valid_moves`, which is never empty, for the player to move on `board`
+    fn choose_move(&self, board: &G, valid_moves: &[G::Move]) -> G::Move;
+}
+
+// What a finished playout is worth
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Scoring {
+    WinLoss,  // 1 for a win, 0 for a draw and -1 for a loss
+    Margin,   // The outcome's margin, so that bigger wins count for more
+}
+
+impl Scoring {
+    // Reward of `outcome` from player 1's point of view. The statistics sum whole rewards, so a margin
+    // counts as a win or a loss with a probability of its size and as a draw otherwise, which averages
+    // to the margin.
+    pub(crate) fn reward<O: Outcome>(self, outcome: &O) -> i32 {
+        match self {
+            Scoring::WinLoss => outcome.reward(),
+            Scoring::Margin => {
+                let margin = outcome.margin().clamp(-1.0, 1.0);
+                if fastrand::f32() < margin.abs() { margin.signum() as i32 } else { 0 }
+            }
+        }
+    }
+}
+
+// Plays uniformly random moves
+pub struct RandomPlayout;
+
+impl<G: Game> PlayoutPolicy<G> for RandomPlayout {
+    fn choose_move(&self, _board: &G, valid_moves: &[G::Move]) -> G::Move {
+        valid_moves[fastrand::usize(..valid_moves.len())]
+    }
+}
+
+// Runs `n_simulations` playouts from `board` and sums their rewards
+pub(crate) fn simulate_from<G: Game>(board: &G, policy: &dyn PlayoutPolicy<G>, scoring: Scoring, n_simulations: u32) -> i32 {
+    let mut wins = 0;
+    for _ in 0..n_simulations {
+        let result = _simulate(board.clone(), policy, scoring, None);
+        wins += result;
+    }
+    wins
+}
+
+// Like `simulate_from`, but keeps the moves of every playout for the AMAF statistics
+pub(crate) fn record_playouts<G: Game>(board: &G, policy: &dyn PlayoutPolicy<G>, scoring: Scoring, n_simulations: u32) -> Vec<Playout<G::Move>> {
+    (0..n_simulations)
+        .map(|_| {
+            let mut moves = Vec::new();
+            let reward = _simulate(board.clone(), policy, scoring, Some(&mut moves));
+            Playout { moves, visits: 1, reward }
+        })
+        .collect()
+}
+
+fn _simulate<G: Game>(mut board: G, policy: &dyn PlayoutPolicy<G>, scoring: Scoring, mut moves: Option<&mut Vec<(u8, G::Move)>>) -> i32 {
+    loop {
+        let valid_moves = board.get_valid_moves();
+        if valid_moves.is_empty() {
+            return 0;  // The player to move is stuck, which is a draw
+        }
+
+        let mov = policy.choose_move(&board, &valid_moves);
+        if let Some(moves) = moves.as_deref_mut() {
+            moves.push((board.get_current_player(), mov));
+        }
+        if let Some(outcome) = board.make_move(mov) {
+            return scoring.reward(&outcome);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// All-moves-as-first (AMAF) statistics for RAVE.
+// A move counts for a node if, the first time it was played later in the iteration (further down
+// the selected path or during a playout), it was played by the player to move at that node.
+
+// Moves of one or more playouts that ended with the summed `reward` over `visits` games
+pub struct Playout<M> {
+    pub moves: Vec<(u8, M)>,  // (player, move) in playing order
+    pub visits: u32,
+    pub reward: i32,
+}
+
+// Everything an iteration played below the root, used to update the AMAF statistics along its path
+pub struct AmafUpdate<M> {
+    pub tree_moves: Vec<(u8, M)>,  // (player, move) for every step of the selected path
+    pub playouts: Vec<Playout<M>>,
+}
+
+impl<M: Copy + Eq> AmafUpdate<M> {
+    // Calls `update(visits, reward)` for every playout in which `player` was first to play `action` from path step `depth` on
+    pub fn for_move(&self, depth: usize, player: u8, action: M, mut update: impl FnMut(u32, i32)) {
+        let tree_moves = self.tree_moves.get(depth..).unwrap_or(&[]);
+        let first_in_tree = tree_moves.iter().find(|&&(_, played)| played == action);
+        for playout in &self.playouts {
+            let first = first_in_tree.or_else(|| playout.moves.iter().find(|&&(_, played)| played == action));
+            if first.is_some_and(|&(first_player, _)| first_player == player) {
+                update(playout.visits, playout.reward);
+            }
+        }
+    }
+}
+
+// Blends the regular win ratio of a child with its AMAF win ratio. The AMAF weight starts at 1
+// and halves once the child has `equivalence` real visits.
+pub fn blend(win_ratio: f32, visits: u32, amaf_wins: i32, amaf_visits: u32, equivalence: f32) -> f32 {
+    if amaf_visits == 0 {
+        return win_ratio;
+    }
+    let amaf_ratio = amaf_wins as f32 / (amaf_visits + 1) as f32;
+    let beta = (equivalence / (3.0 * visits as f32 + equivalence)).sqrt();
+    (1.0 - beta) * win_ratio + beta * amaf_ratio
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_move_matches_player_and_depth() {
+        let update = AmafUpdate {
+            tree_moves: vec![(1, 3), (2, 4)],
+            playouts: vec![
+                Playout { moves: vec![(1, 0), (2, 1), (1, 4)], visits: 1, reward: 1 },
+                Playout { moves: vec![(1, 1), (2, 0), (1, 0)], visits: 1, reward: -1 },
+            ],
+        };
+
+        let collect = |depth, player, action| {
+            let mut seen = Vec::new();
+            update.for_move(depth, player, action, |visits, reward| seen.push((visits, reward)));
+            seen
+        };
+
+        // Played on the path, so it counts for every playout
+        assert_eq!(collect(0, 1, 3), vec![(1, 1), (1, -1)]);
+        // Path moves above `depth` do not count
+        assert_eq!(collect(1, 1, 3), vec![]);
+        // Only playouts where the same player played the move first
+        assert_eq!(collect(0, 1, 0), vec![(1, 1)]);
+        assert_eq!(collect(0, 2, 0), vec![(1, -1)]);
+        assert_eq!(collect(0, 1, 4), vec![]);
+        assert_eq!(collect(0, 2, 4), vec![(1, 1), (1, -1)]);
+    }
+
+    #[test]
+    fn test_blend_moves_from_amaf_to_real_value() {
+        assert_eq!(blend(0.5, 10, 0, 0, 100.0), 0.5);
+        let early = blend(0.0, 0, 90, 99, 100.0);
+        let late = blend(0.0, 10_000, 90, 99, 100.0);
+        assert!((early - 0.9).abs() < 1e-6);
+        assert!(late < 0.1);
+    }
+}
@@ -0,0 +1,129 @@
+// How the final move is picked from the root children once the search is over
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MoveSelection {
+    MostVisits,    // The most visited child
+    HighestValue,  // The child with the best average result
+    SecureValue,   // The best average result minus a penalty for few visits
+}
+
+// Merged statistics of one root child
+#[derive(Clone, Debug)]
+pub struct ChildStats<M> {
+    pub action: M,
+    pub visits: u32,
+    pub wins: i32,              // Summed rewards from player 1's point of view
+    pub result: Option<i32>,    // Proven game result from player 1's point of view
+}
+
+impl<M: Copy> ChildStats<M> {
+    // Average result for `player`, between -1 and 1
+    pub fn value(&self, player: u8) -> f32 {
+        if let Some(result) = self.result {
+            return perspective(result, player) as f32;
+        }
+        if self.visits == 0 {
+            return 0.0;
+        }
+        perspective(self.wins, player) as f32 / self.visits as f32
+    }
+
+    pub fn is_proven_win(&self, player: u8) -> bool {
+        self.result.is_some_and(|result| perspective(result, player) > 0)
+    }
+
+    pub fn is_proven_loss(&self, player: u8) -> bool {
+        self.result.is_some_and(|result| perspective(result, player) < 0)
+    }
+}
+
+// Converts a value from player 1's point of view to `player`'s
+pub fn perspective(value: i32, player: u8) -> i32 {
+    if player == 1 { value } else { -value }
+}
+
+// MCTS-Solver rule for a node where `player` is to move: one proven winning child proves a win,
+// and once every move has been tried and proven the node is worth its best child
+pub fn prove_from_children(player: u8, child_results: impl Iterator<Item = Option<i32>>, fully_expanded: bool) -> Option<i32> {
+    let mut best: Option<i32> = None;
+    let mut all_proven = true;
+    for result in child_results {
+        match result {
+            Some(result) if perspective(result, player) > 0 => return Some(result),
+            Some(result) => {
+                if best.is_none_or(|best| perspective(result, player) > perspective(best, player)) {
+                    best = Some(result);
+                }
+            }
+            None => all_proven = false,
+        }
+    }
+    if fully_expanded && all_proven { best } else { None }
+}
+
+// Picks the move for `player`: a proven win if there is one, never a proven loss unless every move loses
+pub fn select_move<M: Copy>(children: &[ChildStats<M>], player: u8, selection: MoveSelection) -> Option<M> {
+    if let Some(win) = children.iter().find(|child| child.is_proven_win(player)) {
+        return Some(win.action);
+    }
+
+    let candidates: Vec<&ChildStats<M>> = children.iter()
+        .filter(|child| !child.is_proven_loss(player))
+        .collect();
+    let candidates = if candidates.is_empty() { children.iter().collect() } else { candidates };
+
+    let score = |child: &ChildStats<M>| -> f32 {
+        match selection {
+            MoveSelection::MostVisits => child.visits as f32,
+            MoveSelection::HighestValue if child.visits == 0 && child.result.is_none() => f32::NEG_INFINITY,
+            MoveSelection::HighestValue => child.value(player),
+            MoveSelection::SecureValue => child.value(player) - 1.0 / ((child.visits + 1) as f32).sqrt(),
+        }
+    };
+
+    candidates.into_iter()
+        .max_by(|a, b| score(a).partial_cmp(&score(b)).unwrap())
+        .map(|child| child.action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn child(action: usize, visits: u32, wins: i32, result: Option<i32>) -> ChildStats<usize> {
+        ChildStats { action, visits, wins, result }
+    }
+
+    #[test]
+    fn test_value_and_visits_can_disagree() {
+        let children = vec![child(0, 1000, 100, None), child(1, 400, 300, None)];
+        assert_eq!(select_move(&children, 1, MoveSelection::MostVisits), Some(0));
+        assert_eq!(select_move(&children, 1, MoveSelection::HighestValue), Some(1));
+        assert_eq!(select_move(&children, 1, MoveSelection::SecureValue), Some(1));
+
+        // Player 2 prefers the move that is worse for player 1
+        assert_eq!(select_move(&children, 2, MoveSelection::HighestValue), Some(0));
+    }
+
+    #[test]
+    fn test_prove_from_children() {
+        // A single winning child is enough, even with untried moves left
+        assert_eq!(prove_from_children(1, [None, Some(1)].into_iter(), false), Some(1));
+        assert_eq!(prove_from_children(2, [None, Some(-1)].into_iter(), false), Some(-1));
+
+        // Losses and draws only count once every move is proven
+        assert_eq!(prove_from_children(1, [Some(-1), None].into_iter(), true), None);
+        assert_eq!(prove_from_children(1, [Some(-1), Some(-1)].into_iter(), false), None);
+        assert_eq!(prove_from_children(1, [Some(-1), Some(-1)].into_iter(), true), Some(-1));
+        assert_eq!(prove_from_children(2, [Some(1), Some(0)].into_iter(), true), Some(0));
+    }
+
+    #[test]
+    fn test_proven_results_override_statistics() {
+        let children = vec![child(0, 1000, 900, Some(-1)), child(1, 10, -5, None), child(2, 5, 0, Some(1))];
+        assert_eq!(select_move(&children, 1, MoveSelection::MostVisits), Some(2));
+        assert_eq!(select_move(&children, 2, MoveSelection::MostVisits), Some(0));
+
+        let children = vec![child(0, 1000, 900, Some(-1)), child(1, 10, -5, None)];
+        assert_eq!(select_move(&children, 1, MoveSelection::MostVisits), Some(1));
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+use crate::games::Game;
+use super::node::{Node, RootNode};
+use super::shared::SharedNode;
+
+// How far below the previous root we look for the new position: our move plus the opponent's reply
+const REUSE_DEPTH: usize = 2;
+
+// Keeps the search trees alive between calls to `MCTS::search_with_session`
+pub struct SearchSession<G: Game> {
+    trees: Vec<Node<G>>,
+    shared_tree: Option<Arc<SharedNode<G>>>,
+}
+
+impl<G: Game> SearchSession<G> {
+    pub fn new() -> Self {
+        Self {
+            trees: Vec::new(),
+            shared_tree: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.trees.clear();
+        self.shared_tree = None;
+    }
+
+    // Returns one tree per thread rooted at `board`, reusing the stored subtrees where the position was found
+    pub(super) fn take_trees(&mut self, root: &RootNode<G>, board: &G, num_threads: usize) -> Vec<Node<G>> {
+        let mut trees: Vec<Node<G>> = std::mem::take(&mut self.trees)
+            .into_iter()
+            .filter_map(|tree| tree.take_descendant(board, REUSE_DEPTH))
+            .collect();
+
+        trees.truncate(num_threads);
+        while trees.len() < num_threads {
+            trees.push(root.create_thread_tree());
+        }
+        trees
+    }
+
+    pub(super) fn store_trees(&mut self, trees: Vec<Node<G>>) {
+        self.trees = trees;
+        self.shared_tree = None;
+    }
+
+    // Returns the shared tree rooted at `board`, reusing the stored one where the position was found
+    pub(super) fn take_shared_tree(&mut self, board: &G) -> Arc<SharedNode<G>> {
+        self.shared_tree
+            .take()
+            .and_then(|tree| SharedNode::find_descendant(&tree, board, REUSE_DEPTH))
+            .unwrap_or_else(|| Arc::new(SharedNode::new(board.clone(), None, None)))
+    }
+
+    pub(super) fn store_shared_tree(&mut self, tree: Arc<SharedNode<G>>) {
+        self.shared_tree = Some(tree);
+        self.trees.clear();
+    }
+}
+
+impl<G: Game> Default for SearchSession<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::connect4::bitboard::BitBoard;
+    use crate::mcts::mcts::{MCTS, ParallelMode};
+
+    #[test]
+    fn test_session_reuses_grandchild() {
+        let mcts = MCTS::new(1.414, 2, 200, 10);
+        let mut session = SearchSession::new();
+        let board = BitBoard::new();
+
+        let our_move = mcts.search_with_session(&mut session, board);
+        let mut next_board = board;
+        next_board.make_move(our_move);
+        next_board.make_move(3);
+
+        let root = RootNode::new(next_board, 1);
+        let trees = session.take_trees(&root, &next_board, 2);
+        assert_eq!(trees.len(), 2);
+        assert!(trees.iter().all(|tree| tree.board == next_board));
+        assert!(trees.iter().any(|tree| tree.get_visits() > 0), "Subtree was not reused");
+    }
+
+    #[test]
+    fn test_session_starts_fresh_for_unrelated_position() {
+        let mcts = MCTS::new(1.414, 2, 100, 10);
+        let mut session = SearchSession::new();
+        let mut board = BitBoard::new();
+        mcts.search_with_session(&mut session, board);
+
+        for col in [0, 1, 2, 3] {
+            board.make_move(col);
+        }
+        let root = RootNode::new(board, 1);
+        let trees = session.take_trees(&root, &board, 2);
+        assert!(trees.iter().all(|tree| tree.get_visits() == 0));
+    }
+
+    #[test]
+    fn test_session_reuses_shared_grandchild() {
+        let mcts = MCTS::new(1.414, 2, 200, 10).with_parallel_mode(ParallelMode::SharedTree);
+        let mut session = SearchSession::new();
+        let board = BitBoard::new();
+
+        let our_move = mcts.search_with_session(&mut session, board);
+        let mut next_board = board;
+        next_board.make_move(our_move);
+        next_board.make_move(3);
+
+        let tree = session.take_shared_tree(&next_board);
+        assert!(tree.board == next_board);
+        assert!(tree.get_visits() > 0, "Subtree was not reused");
+    }
+}
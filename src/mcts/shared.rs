@@ -0,0 +1,227 @@
+use std::sync::atomic::{AtomicI32, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::games::{Game, Outcome};
+use super::rave::{self, AmafUpdate};
+use super::selection::{perspective, prove_from_children, ChildStats};
+
+// Node of a search tree that all threads grow together.
+// Statistics are atomics, the children and untried moves sit behind a per-node lock.
+pub struct SharedNode<G: Game> {
+    pub board: G,
+    action: Option<G::Move>,
+    result: OnceLock<i32>,  // Proven result, set once by whichever thread proves it
+    visits: AtomicU32,
+    wins: AtomicI32,
+    virtual_losses: AtomicU32,  // Visits of threads that are still below this node
+    amaf_visits: AtomicU32,     // RAVE statistics of `action`, only filled in when RAVE is enabled
+    amaf_wins: AtomicI32,
+    expansion: Mutex<Expansion<G>>,
+}
+
+struct Expansion<G: Game> {
+    children: Vec<Arc<SharedNode<G>>>,
+    untried_moves: Vec<G::Move>,
+}
+
+// What a thread found when it stepped down from a node
+pub enum Step<G: Game> {
+    Selected(Arc<SharedNode<G>>),
+    Expanded(Arc<SharedNode<G>>),
+    Leaf,
+}
+
+impl<G: Game> SharedNode<G> {
+    pub fn new(board: G, action: Option<G::Move>, result: Option<i32>) -> Self {
+        let untried_moves = board.get_valid_moves();
+        let result = result.or(untried_moves.is_empty().then_some(0));  // Stuck without moves: a draw
+        let proven = OnceLock::new();
+        if let Some(result) = result {
+            proven.set(result).unwrap();
+        }
+        Self {
+            board,
+            action,
+            result: proven,
+            visits: AtomicU32::new(0),
+            wins: AtomicI32::new(0),
+            virtual_losses: AtomicU32::new(0),
+            amaf_visits: AtomicU32::new(0),
+            amaf_wins: AtomicI32::new(0),
+            expansion: Mutex::new(Expansion {
+                children: Vec::new(),
+                untried_moves,
+            }),
+        }
+    }
+
+    pub fn get_visits(&self) -> u32 {
+        self.visits.load(Ordering::Relaxed)
+    }
+
+    pub fn get_action(&self) -> Option<G::Move> {
+        self.action
+    }
+
+    pub fn get_result(&self) -> Option<i32> {
+        self.result.get().copied()
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        self.result.get().is_some()
+    }
+
+    // Expands an untried move if there is one, otherwise selects the best child by UCB1, skipping proven losses.
+    // The chosen child receives `virtual_loss` so that other threads prefer different branches.
+    pub fn step(&self, exploration_constant: f32, rave_equivalence: Option<f32>, virtual_loss: u32) -> Step<G> {
+        let mut expansion = self.expansion.lock().unwrap();
+
+        if !expansion.untried_moves.is_empty() {
+            let move_index = fastrand::usize(..expansion.untried_moves.len());
+            let action = expansion.untried_moves.swap_remove(move_index);
+
+            let mut new_board = self.board.clone();
+            let result = new_board.make_move(action).map(|outcome| outcome.reward());
+
+            let child = Arc::new(SharedNode::new(new_board, Some(action), result));
+            child.add_virtual_loss(virtual_loss);
+            expansion.children.push(Arc::clone(&child));
+            return Step::Expanded(child);
+        }
+
+        let parent_visits = (self.get_visits() + self.virtual_losses.load(Ordering::Relaxed)).max(1);
+        let best = expansion.children.iter()
+            .max_by(|a, b| {
+                self.ucb1(a, parent_visits, exploration_constant, rave_equivalence)
+                    .partial_cmp(&self.ucb1(b, parent_visits, exploration_constant, rave_equivalence))
+                    .unwrap()
+            });
+
+        match best {
+            Some(child) => {
+                child.add_virtual_loss(virtual_loss);
+                Step::Selected(Arc::clone(child))
+            }
+            None => Step::Leaf,
+        }
+    }
+
+    // UCB1 where in-flight visits of other threads count as losses for the player choosing the child
+    fn ucb1(&self, child: &SharedNode<G>, parent_visits: u32, exploration_constant: f32, rave_equivalence: Option<f32>) -> f32 {
+        if child.get_result().is_some_and(|result| perspective(result, self.board.get_current_player()) < 0) {
+            return f32::NEG_INFINITY;
+        }
+
+        let virtual_losses = child.virtual_losses.load(Ordering::Relaxed);
+        let visits = child.get_visits() + virtual_losses;
+        if visits == 0 {
+            return f32::INFINITY;
+        }
+
+        let player1_to_move = self.board.get_current_player() == 1;
+        let wins = child.wins.load(Ordering::Relaxed)
+            + if player1_to_move { -(virtual_losses as i32) } else { virtual_losses as i32 };
+
+        let mut win_ratio = wins as f32 / (visits + 1) as f32;
+        if let Some(equivalence) = rave_equivalence {
+            let amaf_wins = child.amaf_wins.load(Ordering::Relaxed);
+            let amaf_visits = child.amaf_visits.load(Ordering::Relaxed);
+            win_ratio = rave::blend(win_ratio, visits, amaf_wins, amaf_visits, equivalence);
+        }
+        let exploration = exploration_constant * ((parent_visits as f32).ln() / visits as f32).sqrt();
+
+        if player1_to_move {
+            win_ratio + exploration
+        } else {
+            -win_ratio + exploration
+        }
+    }
+
+    pub fn add_virtual_loss(&self, virtual_loss: u32) {
+        self.virtual_losses.fetch_add(virtual_loss, Ordering::Relaxed);
+    }
+
+    // Records a finished iteration and removes the virtual loss it added on the way down
+    pub fn update(&self, wins: i32, n_simulations: u32, virtual_loss: u32) {
+        self.visits.fetch_add(n_simulations, Ordering::Relaxed);
+        self.wins.fetch_add(wins, Ordering::Relaxed);
+        self.virtual_losses.fetch_sub(virtual_loss, Ordering::Relaxed);
+    }
+
+    // Updates the AMAF statistics of the children of this node, which lies `depth` steps down the iteration's path
+    pub fn update_amaf(&self, amaf: &AmafUpdate<G::Move>, depth: usize) {
+        let player = self.board.get_current_player();
+        let expansion = self.expansion.lock().unwrap();
+        for child in &expansion.children {
+            if let Some(action) = child.action {
+                amaf.for_move(depth, player, action, |visits, reward| {
+                    child.amaf_visits.fetch_add(visits, Ordering::Relaxed);
+                    child.amaf_wins.fetch_add(reward, Ordering::Relaxed);
+                });
+            }
+        }
+    }
+
+    // Tries to prove this node from the proven results of its children
+    pub fn update_proof(&self) {
+        if self.is_terminal() {
+            return;
+        }
+        let expansion = self.expansion.lock().unwrap();
+        let result = prove_from_children(
+            self.board.get_current_player(),
+            expansion.children.iter().map(|child| child.get_result()),
+            expansion.untried_moves.is_empty(),
+        );
+        if let Some(result) = result {
+            let _ = self.result.set(result);
+        }
+    }
+
+    pub fn get_child_stats(&self) -> Vec<ChildStats<G::Move>> {
+        let expansion = self.expansion.lock().unwrap();
+        expansion.children.iter()
+            .filter_map(|child| {
+                child.action.map(|action| ChildStats {
+                    action,
+                    visits: child.get_visits(),
+                    wins: child.wins.load(Ordering::Relaxed),
+                    result: child.get_result(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn get_child(&self, action: G::Move) -> Option<Arc<SharedNode<G>>> {
+        let expansion = self.expansion.lock().unwrap();
+        expansion.children.iter().find(|child| child.action == Some(action)).cloned()
+    }
+
+    // Moves along the most visited children below this node
+    pub fn get_principal_variation(&self) -> Vec<G::Move> {
+        let mut line = Vec::new();
+        let mut node = self.most_visited_child();
+        while let Some(child) = node {
+            line.extend(child.action);
+            node = child.most_visited_child();
+        }
+        line
+    }
+
+    fn most_visited_child(&self) -> Option<Arc<SharedNode<G>>> {
+        let expansion = self.expansion.lock().unwrap();
+        expansion.children.iter().max_by_key(|child| child.get_visits()).cloned()
+    }
+
+    // Finds the node for `board` at most `max_depth` plies below `node`
+    pub fn find_descendant(node: &Arc<SharedNode<G>>, board: &G, max_depth: usize) -> Option<Arc<SharedNode<G>>> {
+        if node.board == *board {
+            return Some(Arc::clone(node));
+        }
+        if max_depth == 0 {
+            return None;
+        }
+        let expansion = node.expansion.lock().unwrap();
+        expansion.children.iter()
+            .find_map(|child| SharedNode::find_descendant(child, board, max_depth - 1))
+    }
+}
@@ -1,12 +1,46 @@
-#[derive(Copy, Clone, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Player {
+    #[serde(rename = "human")]
     Human,
+    #[serde(rename = "random")]
     RandomBot,
+    #[serde(rename = "mcts")]
     MctsBot,
+    #[serde(rename = "perfect")]
+    PerfectBot,
 }
 
 impl Player {
+    // Every player type, in the order frontends offer them
+    pub const ALL: [Player; 4] = [Player::Human, Player::RandomBot, Player::MctsBot, Player::PerfectBot];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Player::Human => "Human",
+            Player::RandomBot => "Random Bot",
+            Player::MctsBot => "MCTS Bot",
+            Player::PerfectBot => "Perfect Bot",
+        }
+    }
+
+    // Short name used by the command line and saved games
+    pub fn name(&self) -> &'static str {
+        match self {
+            Player::Human => "human",
+            Player::RandomBot => "random",
+            Player::MctsBot => "mcts",
+            Player::PerfectBot => "perfect",
+        }
+    }
+
     pub fn is_bot(&self) -> bool {
-        matches!(self, Player::RandomBot | Player::MctsBot)
+        matches!(self, Player::RandomBot | Player::MctsBot | Player::PerfectBot)
+    }
+
+    // Whether the bot searches with MCTS, always or where the solver cannot help, and so has search settings
+    pub fn searches(&self) -> bool {
+        matches!(self, Player::MctsBot | Player::PerfectBot)
     }
 }
\ No newline at end of file
@@ -0,0 +1,398 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use crate::games::connect4::bitboard::BitBoard;
+use crate::games::connect4::board::Board;
+use crate::games::connect4::popout::{Move, PopOutBoard};
+use crate::games::connect4::solver::{best_column, Solution, Solver};
+use crate::mcts::mcts::MCTS;
+use crate::mcts::SearchSession;
+use crate::player::Player;
+use crate::saved_game::SavedGame;
+
+// Stepping through a finished or saved game, with engine evaluations of its positions
+
+// Estimated values must drop by more than this for a move to count as a mistake
+const MISTAKE_MARGIN: f32 = 0.3;
+
+// How positions are evaluated
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Evaluator {
+    Solver,                // Exact, but slow for positions with only a few discs
+    Mcts { time_ms: u64 },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Evaluation {
+    Solved(Solution),  // Exact, for the player to move
+    Estimate(f32),     // Average MCTS result for the player to move, between -1 and 1
+}
+
+impl Evaluation {
+    // Higher is better for the player to move
+    fn score(&self) -> f32 {
+        match self {
+            Evaluation::Solved(Solution::Win(_)) => 1.0,
+            Evaluation::Solved(Solution::Draw(_)) => 0.0,
+            Evaluation::Solved(Solution::Loss(_)) => -1.0,
+            Evaluation::Estimate(value) => *value,
+        }
+    }
+}
+
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Evaluation::Solved(Solution::Win(plies)) => write!(f, "win in {} plies", plies),
+            Evaluation::Solved(Solution::Loss(plies)) => write!(f, "loss in {} plies", plies),
+            Evaluation::Solved(Solution::Draw(_)) => write!(f, "draw"),
+            Evaluation::Estimate(value) => write!(f, "{:+.2}", value),
+        }
+    }
+}
+
+// What an evaluator thinks of one position of a game
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Annotation {
+    pub best_move: usize,
+    pub best: Evaluation,
+    pub played: Option<Evaluation>,  // The move played from this position, if there was one and it was evaluated
+}
+
+impl Annotation {
+    // Whether the move played was clearly worse than the best one: a solved result thrown away,
+    // or an estimate that drops by more than `MISTAKE_MARGIN`
+    pub fn is_mistake(&self) -> bool {
+        match (self.best, self.played) {
+            (Evaluation::Solved(_), Some(played)) => played.score() < self.best.score(),
+            (Evaluation::Estimate(best), Some(Evaluation::Estimate(played))) => best - played > MISTAKE_MARGIN,
+            _ => false,
+        }
+    }
+}
+
+// Evaluates positions one after another, keeping the solver's table between them
+pub struct Annotator {
+    solver: Option<Solver>,
+    mcts: Option<MCTS<BitBoard>>,
+}
+
+impl Annotator {
+    pub fn new(evaluator: Evaluator) -> Self {
+        match evaluator {
+            Evaluator::Solver => Self { solver: Some(Solver::new()), mcts: None },
+            Evaluator::Mcts { time_ms } => Self { solver: None, mcts: Some(MCTS::new(1.414, 4, time_ms, 10)) },
+        }
+    }
+
+    // Evaluates `board` and the move `played` from it. Returns None if `stop` was set first,
+    // or if the solver evaluates and cannot handle the board.
+    pub fn annotate(&mut self, board: &BitBoard, played: Option<usize>, stop: &AtomicBool) -> Option<Annotation> {
+        if let Some(solver) = &mut self.solver {
+            if !Solver::supports(&board.get_rules()) {
+                return None;
+            }
+            let solutions = solver.analyze_until(board, stop)?;
+            let best_move = best_column(&solutions)?;
+            return Some(Annotation {
+                best_move,
+                best: Evaluation::Solved(solutions[best_move]?),
+                played: played.and_then(|col| solutions[col]).map(Evaluation::Solved),
+            });
+        }
+
+        let mcts = self.mcts.as_ref()?;
+        let stats = mcts.analyze_until(&mut SearchSession::new(), *board, stop);
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        let player = board.get_current_player();
+        let value = |col: usize| stats.children.iter()
+            .find(|child| child.action == col)
+            .map(|child| Evaluation::Estimate(child.value(player)));
+        Some(Annotation {
+            best_move: stats.best_move,
+            best: value(stats.best_move)?,
+            played: played.and_then(value),
+        })
+    }
+}
+
+pub struct Replay {
+    players: [Player; 2],
+    start: BitBoard,             // The position before the first move
+    moves: Vec<Move>,
+    finished: bool,
+    position: usize,             // Number of moves shown
+    board: Board,                // The board after `position` moves
+    autoplay: Option<Duration>,  // Delay between moves while playing automatically
+    last_step: Instant,
+    annotations: Arc<Mutex<Vec<Option<Annotation>>>>,  // One per position, filled in by the annotation thread
+    annotation_thread: Option<(JoinHandle<()>, Arc<AtomicBool>)>,
+}
+
+impl Replay {
+    // Replays `moves`, which must be playable, starting from the empty board
+    pub fn new(players: [Player; 2], moves: &[Move]) -> Self {
+        Self::from_position(players, &BitBoard::new(), moves)
+    }
+
+    // Replays `moves` from a set-up position
+    pub fn from_position(players: [Player; 2], start: &BitBoard, moves: &[Move]) -> Self {
+        let mut game = PopOutBoard::new(*start);
+        let finished = moves.iter().any(|&mv| game.make_move(mv).is_some());
+        Self {
+            players,
+            start: *start,
+            moves: moves.to_vec(),
+            finished,
+            position: 0,
+            board: start.to_board(),
+            autoplay: None,
+            last_step: Instant::now(),
+            annotations: Arc::new(Mutex::new(vec![None; moves.len() + 1])),
+            annotation_thread: None,
+        }
+    }
+
+    pub fn from_saved_game(game: &SavedGame) -> Self {
+        let players = [game.players[0].player, game.players[1].player];
+        Self::from_position(players, &game.get_start_position(), &game.get_moves())
+    }
+
+    pub fn get_players(&self) -> [Player; 2] {
+        self.players
+    }
+
+    pub fn get_moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+
+    // The player to move in the position shown
+    pub fn get_current_player_number(&self) -> u8 {
+        let first = self.start.get_current_player();
+        if self.position.is_multiple_of(2) { first } else { 3 - first }
+    }
+
+    // Whether the position shown ends the game
+    pub fn is_final_position(&self) -> bool {
+        self.finished && self.position == self.moves.len()
+    }
+
+    // Shows the position after `position` moves
+    pub fn go_to(&mut self, position: usize) {
+        self.position = position.min(self.moves.len());
+        self.board = position_after(self.start, &self.moves[..self.position]).to_board();
+        self.last_step = Instant::now();
+    }
+
+    pub fn first(&mut self) {
+        self.go_to(0);
+    }
+
+    pub fn previous(&mut self) {
+        self.go_to(self.position.saturating_sub(1));
+    }
+
+    pub fn next(&mut self) {
+        self.go_to(self.position + 1);
+    }
+
+    pub fn last(&mut self) {
+        self.go_to(self.moves.len());
+    }
+
+    // Steps forward every `delay` once `update` is called, or stops with None
+    pub fn set_autoplay(&mut self, delay: Option<Duration>) {
+        self.autoplay = delay;
+        self.last_step = Instant::now();
+    }
+
+    pub fn get_autoplay(&self) -> Option<Duration> {
+        self.autoplay
+    }
+
+    // Advances autoplay; frontends call this every frame. Autoplay stops at the end of the game.
+    pub fn update(&mut self) {
+        let Some(delay) = self.autoplay else {
+            return;
+        };
+        if self.position == self.moves.len() {
+            self.autoplay = None;
+        } else if self.last_step.elapsed() >= delay {
+            self.next();
+        }
+    }
+
+    // Evaluates every position on a background thread, from the last to the first, since late
+    // positions are the quickest to solve and fill the solver's table for earlier ones.
+    // Replaces the evaluations of an earlier call.
+    pub fn annotate(&mut self, evaluator: Evaluator) {
+        self.stop_annotating();
+        let annotations = Arc::new(Mutex::new(vec![None; self.moves.len() + 1]));
+        self.annotations = Arc::clone(&annotations);
+        if !self.can_annotate(evaluator) {
+            return;
+        }
+
+        let start = self.start;
+        let moves = self.moves.clone();
+        let last = if self.finished { moves.len() - 1 } else { moves.len() };
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            let mut annotator = Annotator::new(evaluator);
+            for position in (0..=last).rev() {
+                let board = position_after(start, &moves[..position]);
+                let played = moves.get(position).map(|mv| mv.column());
+                let Some(annotation) = annotator.annotate(&board, played, &thread_stop) else {
+                    return;
+                };
+                annotations.lock().unwrap()[position] = Some(annotation);
+            }
+        });
+        self.annotation_thread = Some((handle, stop));
+    }
+
+    // Whether `evaluator` can evaluate the positions of this game: the solver handles boards of up to 64 bits,
+    // and neither evaluator knows PopOut's pops
+    pub fn can_annotate(&self, evaluator: Evaluator) -> bool {
+        let rules = self.start.get_rules();
+        match evaluator {
+            Evaluator::Solver => Solver::supports(&rules),
+            Evaluator::Mcts { .. } => !rules.popout,
+        }
+    }
+
+    // Evaluation of the position after `position` moves, once the annotation thread has reached it
+    pub fn get_annotation(&self, position: usize) -> Option<Annotation> {
+        self.annotations.lock().unwrap().get(position).copied().flatten()
+    }
+
+    // For each move, whether it was evaluated as a mistake
+    pub fn get_mistakes(&self) -> Vec<bool> {
+        let annotations = self.annotations.lock().unwrap();
+        (0..self.moves.len())
+            .map(|position| annotations[position].is_some_and(|annotation| annotation.is_mistake()))
+            .collect()
+    }
+
+    fn stop_annotating(&mut self) {
+        if let Some((handle, stop)) = self.annotation_thread.take() {
+            stop.store(true, Ordering::Relaxed);
+            let _ = handle.join();
+        }
+    }
+}
+
+// The position after playing `moves` from `start`
+fn position_after(start: BitBoard, moves: &[Move]) -> BitBoard {
+    let mut game = PopOutBoard::new(start);
+    for &mv in moves {
+        game.make_move(mv);
+    }
+    *game.get_position()
+}
+
+impl Drop for Replay {
+    fn drop(&mut self) {
+        self.stop_annotating();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::connect4::notation::{parse_grid, parse_moves};
+
+    #[test]
+    fn test_navigation_and_autoplay() {
+        let mut replay = Replay::new([Player::Human, Player::Human], &[0, 1, 0, 1, 0, 1, 0].map(Move::Drop));
+        assert!(!replay.is_final_position());
+        assert_eq!(replay.get_current_player_number(), 1);
+        replay.last();
+        assert_eq!(replay.get_position(), 7);
+        assert!(replay.is_final_position());
+        replay.next();
+        assert_eq!(replay.get_position(), 7);
+        replay.previous();
+        assert_eq!(replay.get_current_player_number(), 1);
+        assert_eq!(replay.get_board().get_player_number(3, 0), 0);
+        assert_eq!(replay.get_board().get_player_number(2, 1), 2);
+        replay.first();
+        assert_eq!(replay.get_board().get_player_number(0, 0), 0);
+
+        replay.set_autoplay(Some(Duration::ZERO));
+        for _ in 0..10 {
+            replay.update();
+        }
+        assert_eq!(replay.get_position(), 7);
+        assert_eq!(replay.get_autoplay(), None);
+    }
+
+    #[test]
+    fn test_replay_from_a_set_up_position() {
+        let start = parse_grid("7/7/7/7/7/3o3 x").unwrap();
+        let mut replay = Replay::from_position([Player::Human, Player::Human], &start, &[3, 4].map(Move::Drop));
+        assert_eq!(replay.get_board().get_player_number(0, 3), 2);
+        replay.last();
+        assert_eq!(replay.get_board().get_player_number(1, 3), 1);
+        assert_eq!(replay.get_board().get_player_number(0, 4), 2);
+        assert_eq!(replay.get_current_player_number(), 1);
+    }
+
+    #[test]
+    fn test_solver_finds_mistakes() {
+        let board = parse_moves("7422341735647741166133573473242566").unwrap();
+        let solutions = Solver::new().analyze(&board);
+        let losing = (0..7).find(|&col| solutions[col].is_some_and(|solution| !solution.is_win())).unwrap();
+
+        let mut annotator = Annotator::new(Evaluator::Solver);
+        let never = AtomicBool::new(false);
+        let best = annotator.annotate(&board, None, &never).unwrap();
+        assert_eq!(best.best, Evaluation::Solved(Solution::Win(7)));
+        assert!(!annotator.annotate(&board, Some(best.best_move), &never).unwrap().is_mistake());
+        assert!(annotator.annotate(&board, Some(losing), &never).unwrap().is_mistake());
+        assert_eq!(annotator.annotate(&board, None, &AtomicBool::new(true)), None);
+    }
+
+    #[test]
+    fn test_annotations_arrive_in_background() {
+        let moves: Vec<Move> = "7422341735647741166133573473242566".chars()
+            .map(|column| Move::Drop(column.to_digit(10).unwrap() as usize - 1))
+            .collect();
+        let mut replay = Replay::new([Player::MctsBot, Player::MctsBot], &moves);
+        replay.annotate(Evaluator::Solver);
+        let start = Instant::now();
+        while replay.get_annotation(moves.len() - 3).is_none() {
+            assert!(start.elapsed() < Duration::from_secs(10), "annotations did not arrive");
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(replay.get_annotation(moves.len()).is_some());
+        assert_eq!(replay.get_mistakes().len(), moves.len());
+    }
+
+    #[test]
+    fn test_popout_replay() {
+        let start = BitBoard::with_rules(crate::games::connect4::rules::Rules::STANDARD.with_popout(true));
+        let mut replay = Replay::from_position([Player::Human, Player::Human], &start, &[Move::Drop(3), Move::Drop(3), Move::Pop(3)]);
+        replay.last();
+        assert_eq!(replay.get_board().get_player_number(0, 3), 2);
+        assert_eq!(replay.get_board().get_player_number(1, 3), 0);
+        assert_eq!(replay.get_current_player_number(), 2);
+        assert!(!replay.can_annotate(Evaluator::Solver));
+        assert!(!replay.can_annotate(Evaluator::Mcts { time_ms: 100 }));
+        replay.annotate(Evaluator::Mcts { time_ms: 100 });
+        assert_eq!(replay.get_annotation(0), None);
+    }
+}
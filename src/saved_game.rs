@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::games::connect4::bitboard::BitBoard;
+use crate::games::connect4::notation::{format_grid, format_move_list, parse_grid_with, parse_move_list, NotationError};
+use crate::games::connect4::popout::{Move, PopOutBoard};
+use crate::games::connect4::rules::{Rules, RulesError};
+use crate::player::Player;
+
+// Games saved to files, readable by both the window and the command line.
+//
+// A saved game is a JSON object:
+//
+//   {
+//     "version": 1,
+//     "players": [
+//       { "player": "human", "settings": {} },
+//       { "player": "mcts", "settings": { "exploration": "1.414", "threads": "4", "time": "1000" } }
+//     ],
+//     "moves": "4453",
+//     "result": "unfinished",
+//     "started": 1760000000,
+//     "saved": 1760000100
+//   }
+//
+// `player` is one of human, random, mcts or perfect. `settings` holds the options a bot was created
+// with, named like the command-line search flags. `moves` uses move sequence notation, `result` is
+// player1, player2, draw or unfinished, and the timestamps are seconds since the Unix epoch.
+// Games started from a set-up position also have a `position` in grid notation, which `moves`
+// continues from, and games on another board than 7x6 connect 4 have `rules` such as
+// { "columns": 9, "rows": 7, "connect": 5 }, with "popout": true for PopOut games, whose moves
+// include pops such as "p4".
+
+pub const VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedPlayer {
+    pub player: Player,
+    #[serde(default)]
+    pub settings: BTreeMap<String, String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SavedResult {
+    Player1,
+    Player2,
+    Draw,
+    Unfinished,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub version: u32,
+    pub players: [SavedPlayer; 2],
+    #[serde(default, skip_serializing_if = "Rules::is_standard")]
+    pub rules: Rules,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<String>,  // Grid the game started from, if not the empty board
+    pub moves: String,
+    pub result: SavedResult,
+    pub started: u64,
+    pub saved: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadError {
+    Io(String),
+    Json(String),
+    Version(u32),                  // Written by a newer version of the program
+    Rules(RulesError),
+    Position(NotationError),
+    Moves(NotationError),
+    Result { recorded: SavedResult, actual: SavedResult },  // The moves end differently than recorded
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(f, "{}", error),
+            LoadError::Json(error) => write!(f, "not a saved game: {}", error),
+            LoadError::Version(version) => write!(f, "saved game version {} is not supported", version),
+            LoadError::Rules(error) => write!(f, "invalid rules: {}", error),
+            LoadError::Position(error) => write!(f, "invalid position: {}", error),
+            LoadError::Moves(error) => write!(f, "invalid moves: {}", error),
+            LoadError::Result { recorded, actual } =>
+                write!(f, "the result is recorded as {:?}, but the moves end as {:?}", recorded, actual),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl SavedGame {
+    // Records `moves` between `players`, saved now
+    pub fn new(players: [SavedPlayer; 2], moves: &[Move], started: u64) -> Self {
+        Self::from_position(players, &BitBoard::new(), moves, started)
+    }
+
+    // Records `moves` played from `position`, on the board of its rules
+    pub fn from_position(players: [SavedPlayer; 2], position: &BitBoard, moves: &[Move], started: u64) -> Self {
+        let rules = position.get_rules();
+        Self {
+            version: VERSION,
+            players,
+            rules,
+            position: (*position != BitBoard::with_rules(rules)).then(|| format_grid(position)),
+            moves: format_move_list(moves),
+            result: result_of(position, moves),
+            started,
+            saved: unix_time(),
+        }
+    }
+
+    // The position the moves start from
+    pub fn get_start_position(&self) -> BitBoard {
+        match &self.position {
+            Some(grid) => parse_grid_with(self.rules, grid).expect("Saved games are checked when they are created or read"),
+            None => BitBoard::with_rules(self.rules),
+        }
+    }
+
+    pub fn get_moves(&self) -> Vec<Move> {
+        parse_move_list(self.get_start_position(), &self.moves).expect("Saved games are checked when they are created or read")
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Failed to serialize the saved game")
+    }
+
+    // Reads a saved game, checking that its moves can be played and end as recorded
+    pub fn from_json(json: &str) -> Result<Self, LoadError> {
+        let game: SavedGame = serde_json::from_str(json).map_err(|error| LoadError::Json(error.to_string()))?;
+        if game.version > VERSION {
+            return Err(LoadError::Version(game.version));
+        }
+        let rules = game.rules;
+        Rules::new(rules.columns, rules.rows, rules.connect).map_err(LoadError::Rules)?;
+        let position = match &game.position {
+            Some(grid) => parse_grid_with(rules, grid).map_err(LoadError::Position)?,
+            None => BitBoard::with_rules(rules),
+        };
+        let moves = parse_move_list(position, &game.moves).map_err(LoadError::Moves)?;
+        let actual = result_of(&position, &moves);
+        if actual != game.result {
+            return Err(LoadError::Result { recorded: game.result, actual });
+        }
+        Ok(game)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), LoadError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|error| LoadError::Io(format!("{}: {}", parent.display(), error)))?;
+        }
+        fs::write(path, self.to_json() + "\n").map_err(|error| LoadError::Io(format!("{}: {}", path.display(), error)))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, LoadError> {
+        let json = fs::read_to_string(path).map_err(|error| LoadError::Io(format!("{}: {}", path.display(), error)))?;
+        Self::from_json(&json)
+    }
+}
+
+// How a game with these (playable) moves from `position` stands
+fn result_of(position: &BitBoard, moves: &[Move]) -> SavedResult {
+    let mut game = PopOutBoard::new(*position);
+    for &mv in moves {
+        match game.make_move(mv) {
+            Some(1) => return SavedResult::Player1,
+            Some(-1) => return SavedResult::Player2,
+            Some(_) => return SavedResult::Draw,
+            None => {}
+        }
+    }
+    SavedResult::Unfinished
+}
+
+// Seconds since the Unix epoch
+pub fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::connect4::notation::{parse_grid, parse_moves_with, play_moves};
+
+    fn players() -> [SavedPlayer; 2] {
+        let settings = BTreeMap::from([("time".to_string(), "200".to_string())]);
+        [
+            SavedPlayer { player: Player::Human, settings: BTreeMap::new() },
+            SavedPlayer { player: Player::MctsBot, settings },
+        ]
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let game = SavedGame::new(players(), &[3, 3, 4, 2].map(Move::Drop), 1_700_000_000);
+        assert_eq!(game.moves, "4453");
+        assert_eq!(game.result, SavedResult::Unfinished);
+        let json = game.to_json();
+        assert!(json.contains("\"player\": \"mcts\""));
+        assert_eq!(SavedGame::from_json(&json), Ok(game.clone()));
+        assert_eq!(game.get_moves(), [3, 3, 4, 2].map(Move::Drop));
+
+        let finished = SavedGame::new(players(), &[0, 1, 0, 1, 0, 1, 0].map(Move::Drop), 1_700_000_000);
+        assert_eq!(finished.result, SavedResult::Player1);
+    }
+
+    #[test]
+    fn test_invalid_games_are_rejected() {
+        let mut game = SavedGame::new(players(), &[0, 1, 0, 1, 0, 1, 0].map(Move::Drop), 1_700_000_000);
+        game.result = SavedResult::Unfinished;
+        assert!(matches!(SavedGame::from_json(&game.to_json()), Err(LoadError::Result { .. })));
+
+        game.moves = "44448".to_string();
+        assert!(matches!(SavedGame::from_json(&game.to_json()), Err(LoadError::Moves(_))));
+
+        game.version = VERSION + 1;
+        assert_eq!(SavedGame::from_json(&game.to_json()), Err(LoadError::Version(VERSION + 1)));
+        assert!(matches!(SavedGame::from_json("{}"), Err(LoadError::Json(_))));
+
+        game.version = VERSION;
+        game.position = Some("7/7/7/7/7/xx5 x".to_string());
+        assert!(matches!(SavedGame::from_json(&game.to_json()), Err(LoadError::Position(_))));
+    }
+
+    #[test]
+    fn test_game_from_a_set_up_position() {
+        let position = parse_grid("7/7/7/7/7/3o3 x").unwrap();
+        let game = SavedGame::from_position(players(), &position, &[3, 4, 3, 4, 3, 4, 3].map(Move::Drop), 1_700_000_000);
+        assert_eq!(game.position.as_deref(), Some("7/7/7/7/7/3o3 x"));
+        assert_eq!(game.result, SavedResult::Player1);
+        let loaded = SavedGame::from_json(&game.to_json()).unwrap();
+        assert_eq!(loaded.get_start_position(), position);
+
+        // Games from the empty board leave the position out
+        assert!(!SavedGame::new(players(), &[3].map(Move::Drop), 0).to_json().contains("position"));
+    }
+
+    #[test]
+    fn test_other_board_sizes() {
+        let rules = Rules::new(9, 7, 5).unwrap();
+        let game = SavedGame::from_position(players(), &BitBoard::with_rules(rules), &[8, 8].map(Move::Drop), 1_700_000_000);
+        assert_eq!(game.position, None);
+        let json = game.to_json();
+        assert!(json.contains("\"connect\": 5"));
+        assert!(!json.contains("popout"));
+        let loaded = SavedGame::from_json(&json).unwrap();
+        assert_eq!(loaded.get_start_position(), BitBoard::with_rules(rules));
+        assert_eq!(play_moves(loaded.get_start_position(), &loaded.moves), parse_moves_with(rules, "99"));
+
+        // Standard games leave the rules out
+        assert!(!SavedGame::new(players(), &[3].map(Move::Drop), 0).to_json().contains("rules"));
+
+        let mut invalid = game;
+        invalid.rules.columns = 10;
+        assert_eq!(SavedGame::from_json(&invalid.to_json()), Err(LoadError::Rules(RulesError::Columns(10))));
+    }
+
+    #[test]
+    fn test_popout_games() {
+        let rules = Rules::STANDARD.with_popout(true);
+        let moves = [Move::Drop(3), Move::Drop(3), Move::Pop(3)];
+        let game = SavedGame::from_position(players(), &BitBoard::with_rules(rules), &moves, 1_700_000_000);
+        assert_eq!(game.moves, "44p4");
+        assert!(game.to_json().contains("\"popout\": true"));
+        let loaded = SavedGame::from_json(&game.to_json()).unwrap();
+        assert_eq!(loaded.get_moves(), moves);
+
+        // The same moves are invalid without pops
+        let mut classic = loaded;
+        classic.rules.popout = false;
+        assert_eq!(SavedGame::from_json(&classic.to_json()), Err(LoadError::Moves(NotationError::CannotPop { index: 3, column: 4 })));
+    }
+}
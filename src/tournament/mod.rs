@@ -0,0 +1,447 @@
+// Matches between bots for measuring playing strength
+mod stats;
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use serde::Serialize;
+use crate::bot::Bot;
+use crate::games::connect4::bitboard::BitBoard;
+use crate::games::connect4::notation::format_move_list;
+use crate::games::connect4::popout::{Move, PopOutBoard};
+use crate::games::connect4::rules::Rules;
+
+pub use stats::{elo_estimate, elo_from_ratio, EloEstimate, Score, Sprt, SprtDecision};
+
+// Creates a fresh bot for every game under the tournament's rules, so games can run on different
+// threads without sharing search state
+pub type BotFactory = Box<dyn Fn(&Rules) -> Box<dyn Bot<PopOutBoard>> + Send + Sync>;
+
+pub struct Entrant {
+    pub name: String,
+    factory: BotFactory,
+}
+
+impl Entrant {
+    pub fn new(name: &str, factory: impl Fn(&Rules) -> Box<dyn Bot<PopOutBoard>> + Send + Sync + 'static) -> Self {
+        Self {
+            name: name.to_string(),
+            factory: Box::new(factory),
+        }
+    }
+}
+
+// Who plays whom
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub enum Format {
+    RoundRobin,  // Every entrant against every other
+    Gauntlet,    // The first entrant against each of the others
+}
+
+// One finished game. Entrants are indices into the entrant list.
+#[derive(Clone, Debug, Serialize)]
+pub struct GameRecord {
+    pub player1: usize,
+    pub player2: usize,
+    pub moves: String,           // Move sequence notation, including the opening
+    pub opening_moves: usize,    // Number of random moves played before the bots took over
+    pub result: i32,             // From player 1's point of view
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PairingReport {
+    pub entrant: usize,
+    pub opponent: usize,
+    pub score: Score,            // From `entrant`'s point of view
+    pub elo: Option<EloEstimate>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Standing {
+    pub entrant: usize,
+    pub name: String,
+    pub score: Score,
+    pub points: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SprtReport {
+    pub test: Sprt,
+    pub llr: f64,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    pub decision: Option<SprtDecision>,
+}
+
+// Everything a tournament produced, written out by `to_json`
+#[derive(Clone, Debug, Serialize)]
+pub struct TournamentReport {
+    pub entrants: Vec<String>,
+    pub format: Format,
+    pub rules: Rules,
+    pub games: Vec<GameRecord>,
+    pub pairings: Vec<PairingReport>,
+    pub standings: Vec<Standing>,      // Best first
+    pub sprt: Option<SprtReport>,      // Tests the first entrant against all of its opponents
+}
+
+impl TournamentReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Failed to serialize the tournament report")
+    }
+}
+
+// Why a tournament cannot be played
+#[derive(Clone, Debug, PartialEq)]
+pub enum TournamentError {
+    OpeningTooLong { moves: usize, cells: usize },  // The opening would fill the board
+    NoOpening { moves: usize },  // Every random opening tried ended the game
+}
+
+impl fmt::Display for TournamentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TournamentError::OpeningTooLong { moves, cells } =>
+                write!(f, "an opening of {} moves does not fit a board of {} cells", moves, cells),
+            TournamentError::NoOpening { moves } =>
+                write!(f, "found no opening of {} random moves that leaves the game going", moves),
+        }
+    }
+}
+
+impl std::error::Error for TournamentError {}
+
+// Random openings tried before giving up on finding one that does not end the game
+const OPENING_TRIES: usize = 1000;
+
+// A game waiting to be played
+struct Job {
+    player1: usize,
+    player2: usize,
+    opening: Vec<usize>,
+}
+
+pub struct Tournament {
+    entrants: Vec<Entrant>,
+    format: Format,
+    rules: Rules,
+    games_per_pairing: usize,
+    concurrency: usize,
+    opening_moves: usize,
+    sprt: Option<Sprt>,
+}
+
+impl Tournament {
+    pub fn new(entrants: Vec<Entrant>) -> Self {
+        Self {
+            entrants,
+            format: Format::RoundRobin,
+            rules: Rules::STANDARD,
+            games_per_pairing: 2,
+            concurrency: 1,
+            opening_moves: 2,
+            sprt: None,
+        }
+    }
+
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    // Board size and connect length of every game
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    // Games between every two entrants that meet. Each pair of games shares an opening with colors swapped.
+    pub fn with_games_per_pairing(mut self, games_per_pairing: usize) -> Self {
+        self.games_per_pairing = games_per_pairing;
+        self
+    }
+
+    // Number of games played at the same time
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    // Number of random moves that open every game, so deterministic bots do not repeat the same game
+    pub fn with_opening_moves(mut self, opening_moves: usize) -> Self {
+        self.opening_moves = opening_moves;
+        self
+    }
+
+    // Stops the tournament early once the first entrant's results decide `sprt`
+    pub fn with_sprt(mut self, sprt: Sprt) -> Self {
+        self.sprt = Some(sprt);
+        self
+    }
+
+    // Plays all games, calling `on_game` after each one. Fails before any game if the openings
+    // cannot be played on the board.
+    pub fn run(&self, on_game: impl Fn(&GameRecord) + Sync) -> Result<TournamentReport, TournamentError> {
+        let jobs = self.schedule()?;
+        let next_job = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+        let results: Mutex<Vec<GameRecord>> = Mutex::new(Vec::new());
+
+        thread::scope(|scope| {
+            for _ in 0..self.concurrency {
+                scope.spawn(|| {
+                    while !stop.load(Ordering::Relaxed) {
+                        let Some(job) = jobs.get(next_job.fetch_add(1, Ordering::Relaxed)) else {
+                            break;
+                        };
+                        let record = self.play(job);
+
+                        let mut results = results.lock().unwrap();
+                        on_game(&record);
+                        results.push(record);
+                        if let Some(sprt) = &self.sprt {
+                            if sprt.decide(&candidate_score(&results)).is_some() {
+                                stop.store(true, Ordering::Relaxed);
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(self.report(results.into_inner().unwrap()))
+    }
+
+    // Lists the games round by round, so that stopping early leaves every pairing with a similar number of games
+    fn schedule(&self) -> Result<Vec<Job>, TournamentError> {
+        let cells = self.rules.cells();
+        if self.opening_moves >= cells {
+            return Err(TournamentError::OpeningTooLong { moves: self.opening_moves, cells });
+        }
+        let count = self.entrants.len();
+        let pairings: Vec<(usize, usize)> = match self.format {
+            Format::RoundRobin => (0..count).flat_map(|a| (a + 1..count).map(move |b| (a, b))).collect(),
+            Format::Gauntlet => (1..count).map(|b| (0, b)).collect(),
+        };
+
+        let mut jobs = Vec::new();
+        let mut openings: Vec<Vec<usize>> = Vec::new();
+        for game in 0..self.games_per_pairing {
+            for (index, &(a, b)) in pairings.iter().enumerate() {
+                // Even games pick a new opening, odd games replay it with colors swapped
+                if game % 2 == 0 {
+                    let opening = random_opening(self.rules, self.opening_moves)
+                        .ok_or(TournamentError::NoOpening { moves: self.opening_moves })?;
+                    if index < openings.len() { openings[index] = opening } else { openings.push(opening) }
+                }
+                let (player1, player2) = if game % 2 == 0 { (a, b) } else { (b, a) };
+                jobs.push(Job { player1, player2, opening: openings[index].clone() });
+            }
+        }
+        Ok(jobs)
+    }
+
+    fn play(&self, job: &Job) -> GameRecord {
+        let mut bots = [(self.entrants[job.player1].factory)(&self.rules), (self.entrants[job.player2].factory)(&self.rules)];
+        let mut game = PopOutBoard::new(BitBoard::with_rules(self.rules));
+        let mut moves: Vec<Move> = job.opening.iter().map(|&col| Move::Drop(col)).collect();
+        for &mv in &moves {
+            game.make_move(mv);
+        }
+
+        let result = loop {
+            let player = game.get_current_player();
+            let mv = bots[player as usize - 1].make_move(&game)
+                .filter(|&mv| game.is_valid_move(mv))
+                .expect("Bot returned no valid move");
+            moves.push(mv);
+            if let Some(result) = game.make_move(mv) {
+                break result;
+            }
+        };
+
+        GameRecord {
+            player1: job.player1,
+            player2: job.player2,
+            moves: format_move_list(&moves),
+            opening_moves: job.opening.len(),
+            result,
+        }
+    }
+
+    fn report(&self, games: Vec<GameRecord>) -> TournamentReport {
+        let count = self.entrants.len();
+
+        // scores[a][b] is the score of a against b
+        let mut scores = vec![vec![Score::default(); count]; count];
+        for game in &games {
+            scores[game.player1][game.player2].add(game.result);
+            scores[game.player2][game.player1].add(-game.result);
+        }
+
+        let mut pairings = Vec::new();
+        for (entrant, row) in scores.iter().enumerate() {
+            for (opponent, &score) in row.iter().enumerate().skip(entrant + 1) {
+                if score.games() > 0 {
+                    pairings.push(PairingReport { entrant, opponent, score, elo: elo_estimate(&score) });
+                }
+            }
+        }
+
+        let mut standings: Vec<Standing> = (0..count)
+            .map(|entrant| {
+                let mut score = Score::default();
+                for opponent_score in &scores[entrant] {
+                    score.merge(opponent_score);
+                }
+                Standing {
+                    entrant,
+                    name: self.entrants[entrant].name.clone(),
+                    score,
+                    points: score.wins as f64 + 0.5 * score.draws as f64,
+                }
+            })
+            .collect();
+        standings.sort_by(|a, b| b.points.partial_cmp(&a.points).unwrap());
+
+        let sprt = self.sprt.map(|test| {
+            let score = candidate_score(&games);
+            let (lower_bound, upper_bound) = test.bounds();
+            SprtReport { test, llr: test.llr(&score), lower_bound, upper_bound, decision: test.decide(&score) }
+        });
+
+        TournamentReport {
+            entrants: self.entrants.iter().map(|entrant| entrant.name.clone()).collect(),
+            format: self.format,
+            rules: self.rules,
+            games,
+            pairings,
+            standings,
+            sprt,
+        }
+    }
+}
+
+// Score of the first entrant over all of its games
+fn candidate_score(games: &[GameRecord]) -> Score {
+    let mut score = Score::default();
+    for game in games {
+        if game.player1 == 0 {
+            score.add(game.result);
+        } else if game.player2 == 0 {
+            score.add(-game.result);
+        }
+    }
+    score
+}
+
+// Random moves from the empty board of `rules` that do not end the game, or None if none of
+// `OPENING_TRIES` tries found such moves
+fn random_opening(rules: Rules, length: usize) -> Option<Vec<usize>> {
+    'retry: for _ in 0..OPENING_TRIES {
+        let mut board = BitBoard::with_rules(rules);
+        let mut moves = Vec::with_capacity(length);
+        for _ in 0..length {
+            let valid_moves = board.get_valid_moves();
+            let col = valid_moves[fastrand::usize(..valid_moves.len())];
+            if board.make_move(col).is_some() {
+                continue 'retry;
+            }
+            moves.push(col);
+        }
+        return Some(moves);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{ClassicBot, MctsBot, RandomBot};
+    use crate::games::connect4::notation::parse_move_list;
+
+    #[test]
+    fn test_round_robin_alternates_colors() {
+        let entrants = vec![
+            Entrant::new("a", |_| Box::new(RandomBot)),
+            Entrant::new("b", |_| Box::new(RandomBot)),
+            Entrant::new("c", |_| Box::new(RandomBot)),
+        ];
+        let report = Tournament::new(entrants)
+            .with_games_per_pairing(4)
+            .with_concurrency(2)
+            .run(|_| {}).unwrap();
+
+        assert_eq!(report.games.len(), 12);
+        assert_eq!(report.pairings.len(), 3);
+        for pairing in &report.pairings {
+            assert_eq!(pairing.score.games(), 4);
+            let first_moves = report.games.iter()
+                .filter(|game| game.player1 == pairing.entrant && game.player2 == pairing.opponent)
+                .count();
+            assert_eq!(first_moves, 2);
+        }
+        let total: f64 = report.standings.iter().map(|standing| standing.points).sum();
+        assert_eq!(total, 12.0);
+        assert!(report.to_json().contains("\"standings\""));
+    }
+
+    #[test]
+    fn test_other_board_sizes() {
+        let rules = Rules::new(6, 5, 3).unwrap();
+        let entrants = vec![
+            Entrant::new("a", |_| Box::new(RandomBot)),
+            Entrant::new("b", |_| Box::new(RandomBot)),
+        ];
+        let report = Tournament::new(entrants).with_rules(rules).with_games_per_pairing(6).run(|_| {}).unwrap();
+
+        assert_eq!(report.rules, rules);
+        for game in &report.games {
+            assert!(game.moves.chars().all(|column| ('1'..='6').contains(&column)), "{}", game.moves);
+            assert!(game.moves.len() <= rules.cells());
+        }
+    }
+
+    #[test]
+    fn test_openings_that_fill_the_board_are_refused() {
+        let rules = Rules::new(4, 4, 3).unwrap();
+        let entrants = vec![
+            Entrant::new("a", |_| Box::new(RandomBot)),
+            Entrant::new("b", |_| Box::new(RandomBot)),
+        ];
+        let tournament = Tournament::new(entrants).with_rules(rules).with_opening_moves(16);
+        assert_eq!(tournament.run(|_| {}).err(), Some(TournamentError::OpeningTooLong { moves: 16, cells: 16 }));
+    }
+
+    #[test]
+    fn test_popout_games() {
+        let rules = Rules::new(6, 5, 4).unwrap().with_popout(true);
+        let entrants = vec![
+            Entrant::new("a", |_| Box::new(RandomBot)),
+            Entrant::new("b", |_| Box::new(RandomBot)),
+        ];
+        let report = Tournament::new(entrants).with_rules(rules).with_games_per_pairing(6).run(|_| {}).unwrap();
+        for game in &report.games {
+            assert!(parse_move_list(BitBoard::with_rules(rules), &game.moves).is_ok(), "{}", game.moves);
+        }
+    }
+
+    #[test]
+    fn test_sprt_stops_a_lopsided_gauntlet() {
+        let entrants = vec![
+            Entrant::new("mcts", |_| Box::new(ClassicBot(MctsBot::new(20)))),
+            Entrant::new("random", |_| Box::new(RandomBot)),
+        ];
+        let report = Tournament::new(entrants)
+            .with_format(Format::Gauntlet)
+            .with_games_per_pairing(200)
+            .with_sprt(Sprt::new(0.0, 100.0))
+            .run(|_| {}).unwrap();
+
+        let sprt = report.sprt.unwrap();
+        assert_eq!(sprt.decision, Some(SprtDecision::AcceptH1));
+        assert!(report.games.len() < 200);
+        assert_eq!(report.standings[0].name, "mcts");
+    }
+}
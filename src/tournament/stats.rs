@@ -0,0 +1,167 @@
+use serde::Serialize;
+
+// Wins, draws and losses of one side
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Score {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Score {
+    // Records a game result from this side's point of view: positive for a win, 0 for a draw
+    pub fn add(&mut self, result: i32) {
+        match result {
+            1.. => self.wins += 1,
+            0 => self.draws += 1,
+            _ => self.losses += 1,
+        }
+    }
+
+    pub fn merge(&mut self, other: &Score) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+
+    // The same games seen from the other side
+    pub fn reversed(&self) -> Score {
+        Score { wins: self.losses, draws: self.draws, losses: self.wins }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    // Points per game, a draw counting half
+    pub fn ratio(&self) -> f64 {
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64
+    }
+
+    // Variance of the points of a single game
+    fn variance(&self) -> f64 {
+        variance(self.wins as f64, self.draws as f64, self.losses as f64)
+    }
+}
+
+fn variance(wins: f64, draws: f64, losses: f64) -> f64 {
+    let games = wins + draws + losses;
+    let ratio = (wins + 0.5 * draws) / games;
+    (wins * (1.0 - ratio).powi(2) + draws * (0.5 - ratio).powi(2) + losses * ratio.powi(2)) / games
+}
+
+// Elo difference implied by a score, with the half-width of its 95% confidence interval.
+// Sweeps give an infinite difference, which the JSON report writes as null.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct EloEstimate {
+    pub elo: f64,
+    pub error: f64,
+}
+
+pub fn elo_from_ratio(ratio: f64) -> f64 {
+    -400.0 * (1.0 / ratio - 1.0).log10()
+}
+
+fn ratio_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+pub fn elo_estimate(score: &Score) -> Option<EloEstimate> {
+    if score.games() == 0 {
+        return None;
+    }
+    let ratio = score.ratio();
+    let margin = 1.96 * (score.variance() / score.games() as f64).sqrt();
+    let low = elo_from_ratio((ratio - margin).max(0.0));
+    let high = elo_from_ratio((ratio + margin).min(1.0));
+    let elo = elo_from_ratio(ratio);
+    Some(EloEstimate {
+        elo,
+        error: if elo.is_finite() { (high - low) / 2.0 } else { f64::INFINITY },
+    })
+}
+
+// Sequential probability ratio test of H0: elo = elo0 against H1: elo = elo1, stopping as soon as
+// the log-likelihood ratio leaves the bounds set by the false positive rate `alpha` and false negative rate `beta`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum SprtDecision {
+    AcceptH0,  // The results fit elo0 better
+    AcceptH1,  // The results fit elo1 better
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Self { elo0, elo1, alpha: 0.05, beta: 0.05 }
+    }
+
+    // Lower and upper bounds of the log-likelihood ratio
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    // Log-likelihood ratio of the games so far, using the normal approximation of the score
+    pub fn llr(&self, score: &Score) -> f64 {
+        if score.games() == 0 {
+            return 0.0;
+        }
+        let mut variance = score.variance();
+        if variance == 0.0 {
+            // Every game ended the same way, so estimate the variance with half a win and half a loss added
+            variance = self::variance(score.wins as f64 + 0.5, score.draws as f64, score.losses as f64 + 0.5);
+        }
+        let (ratio0, ratio1) = (ratio_from_elo(self.elo0), ratio_from_elo(self.elo1));
+        score.games() as f64 * (ratio1 - ratio0) * (2.0 * score.ratio() - ratio0 - ratio1) / (2.0 * variance)
+    }
+
+    pub fn decide(&self, score: &Score) -> Option<SprtDecision> {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            Some(SprtDecision::AcceptH1)
+        } else if llr <= lower {
+            Some(SprtDecision::AcceptH0)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elo_estimate() {
+        let even = Score { wins: 10, draws: 10, losses: 10 };
+        let estimate = elo_estimate(&even).unwrap();
+        assert!(estimate.elo.abs() < 1e-9);
+        assert!(estimate.error > 0.0);
+
+        // 75% is about 191 Elo
+        let strong = Score { wins: 150, draws: 0, losses: 50 };
+        assert!((elo_estimate(&strong).unwrap().elo - 190.8).abs() < 0.1);
+        assert!((elo_estimate(&strong.reversed()).unwrap().elo + 190.8).abs() < 0.1);
+
+        assert_eq!(elo_estimate(&Score::default()), None);
+        let sweep = elo_estimate(&Score { wins: 3, draws: 0, losses: 0 }).unwrap();
+        assert_eq!((sweep.elo, sweep.error), (f64::INFINITY, f64::INFINITY));
+    }
+
+    #[test]
+    fn test_sprt_decisions() {
+        let sprt = Sprt::new(0.0, 50.0);
+        assert_eq!(sprt.decide(&Score { wins: 3, draws: 2, losses: 3 }), None);
+        assert_eq!(sprt.decide(&Score { wins: 300, draws: 100, losses: 100 }), Some(SprtDecision::AcceptH1));
+        assert_eq!(sprt.decide(&Score { wins: 100, draws: 100, losses: 300 }), Some(SprtDecision::AcceptH0));
+        // A clean sweep still decides
+        assert_eq!(sprt.decide(&Score { wins: 10, draws: 0, losses: 0 }), Some(SprtDecision::AcceptH1));
+    }
+}
@@ -0,0 +1,106 @@
+// Fixed-size hash table from position keys to search results.
+// Every key maps to a single slot, so colliding positions compete for it according to the replacement policy.
+
+// Decides whether a new entry may overwrite an occupied slot
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Replacement {
+    Always,          // The newest entry wins
+    DepthPreferred,  // Keep the entry searched deeper, replacing same-key entries and equally deep ones
+}
+
+// Key of the slots that hold nothing, which real keys must differ from
+const EMPTY: u64 = u64::MAX;
+
+// Slots are kept in separate vectors rather than as `Option`s of a struct, which padding would
+// make several times larger
+pub struct TranspositionTable<V> {
+    keys: Vec<u64>,
+    values: Vec<V>,
+    depths: Vec<u8>,  // How much search each value is worth, only kept for DepthPreferred
+    replacement: Replacement,
+}
+
+impl<V: Copy + Default> TranspositionTable<V> {
+    // A table with `size` slots. A prime size spreads keys with regular structure more evenly.
+    pub fn new(size: usize, replacement: Replacement) -> Self {
+        assert!(size > 0, "transposition table needs at least one slot");
+        let depth_slots = if replacement == Replacement::DepthPreferred { size } else { 0 };
+        Self {
+            keys: vec![EMPTY; size],
+            values: vec![V::default(); size],
+            depths: vec![0; depth_slots],
+            replacement,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn get(&self, key: u64) -> Option<V> {
+        let index = self.index(key);
+        (key != EMPTY && self.keys[index] == key).then(|| self.values[index])
+    }
+
+    // Stores `value` for `key` unless the replacement policy keeps the current occupant
+    pub fn insert(&mut self, key: u64, value: V, depth: u8) {
+        debug_assert_ne!(key, EMPTY, "this key marks empty slots");
+        let index = self.index(key);
+        let replace = match self.replacement {
+            Replacement::Always => true,
+            Replacement::DepthPreferred =>
+                self.keys[index] == EMPTY || self.keys[index] == key || depth >= self.depths[index],
+        };
+        if replace {
+            self.keys[index] = key;
+            self.values[index] = value;
+            if let Some(slot) = self.depths.get_mut(index) {
+                *slot = depth;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.fill(EMPTY);
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.keys.len() as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_only_matches_the_stored_key() {
+        let mut table = TranspositionTable::new(7, Replacement::Always);
+        table.insert(3, 'a', 0);
+        assert_eq!(table.get(3), Some('a'));
+        assert_eq!(table.get(10), None);  // Same slot, different key
+        table.insert(10, 'b', 0);
+        assert_eq!(table.get(3), None);
+        assert_eq!(table.get(10), Some('b'));
+        table.clear();
+        assert_eq!(table.get(10), None);
+
+        // Zero is the key of the empty board
+        table.insert(0, 'c', 0);
+        assert_eq!(table.get(0), Some('c'));
+        assert_eq!(table.get(7), None);
+    }
+
+    #[test]
+    fn test_depth_preferred_keeps_deeper_entries() {
+        let mut table = TranspositionTable::new(7, Replacement::DepthPreferred);
+        table.insert(3, 'a', 5);
+        table.insert(10, 'b', 2);
+        assert_eq!(table.get(3), Some('a'));
+        table.insert(10, 'c', 5);
+        assert_eq!(table.get(10), Some('c'));
+        // The same position may always refresh its own entry
+        table.insert(10, 'd', 0);
+        assert_eq!(table.get(10), Some('d'));
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt::Display;
+use ggez::{Context, GameResult};
+use ggez::graphics::Canvas;
+use ggez::mint::Point2;
+
+use connect_four::board_game::BoardGame;
+use connect_four::games::Game;
+use connect_four::config::{SCREEN_WIDTH, BUTTON_WIDTH, BUTTON_HEIGHT};
+use crate::ui::{button::Button, drawing};
+
+// Top of the move list in the side panel, below the menu button
+const MOVE_LIST_TOP: f32 = 40.0 + BUTTON_HEIGHT;
+
+// How the positions of one game look, and which move a click on the board asks for
+pub trait BoardRenderer<G: Game> {
+    // Draws the position in the area left of the side panel
+    fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, game: &G) -> GameResult;
+
+    // The move a click at `point` names, whether or not it is valid
+    fn move_at(&self, game: &G, point: Point2<f32>) -> Option<G::Move>;
+
+    // A running score to show next to the turn, for games that count one
+    fn score(&self, _game: &G) -> Option<String> {
+        None
+    }
+}
+
+// What a click asks of the screen around the game
+#[derive(PartialEq, Clone, Copy)]
+pub enum BoardGameAction {
+    None,
+    Close,
+}
+
+// A game screen for any of the games besides connect four, whatever their moves are,
+// so that the window can keep one of them at a time
+pub trait BoardGameScreen {
+    // Lets bots move; call every frame
+    fn update(&mut self);
+
+    fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult;
+
+    fn handle_click(&mut self, point: Point2<f32>) -> BoardGameAction;
+
+    fn cancel_bot_move(&mut self);
+}
+
+// Plays a `BoardGame` with its renderer: the board on the left, the moves in the side panel
+pub struct BoardGameView<G: Game + 'static, R> {
+    game: BoardGame<G>,
+    renderer: R,
+    menu_button: Button,
+}
+
+impl<G: Game + 'static, R: BoardRenderer<G>> BoardGameView<G, R> {
+    pub fn new(game: BoardGame<G>, renderer: R) -> Self {
+        Self {
+            game,
+            renderer,
+            menu_button: Button::new(SCREEN_WIDTH + 20.0, 20.0, BUTTON_WIDTH, BUTTON_HEIGHT, "Menu"),
+        }
+    }
+
+    fn status(&self) -> String {
+        let mut status = self.turn();
+        if let Some(score) = self.renderer.score(self.game.get_game()) {
+            status.push_str(&format!(" ({})", score));
+        }
+        status
+    }
+
+    fn turn(&self) -> String {
+        if self.game.is_game_over() {
+            return match self.game.get_winner() {
+                Some(player_number) => format!("Player {} Wins!", player_number),
+                None => "Game Draw!".to_string(),
+            };
+        }
+        let mut status = format!(
+            "Current Turn: Player {} ({})",
+            self.game.get_current_player_number(),
+            self.game.get_current_player_type().label(),
+        );
+        if self.game.is_thinking() {
+            status.push_str(" is thinking...");
+        }
+        status
+    }
+}
+
+impl<G: Game + 'static, R: BoardRenderer<G>> BoardGameScreen for BoardGameView<G, R>
+where
+    G::Move: Display,
+{
+    fn update(&mut self) {
+        self.game.poll_bot_move();
+    }
+
+    fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        self.renderer.draw(ctx, canvas, self.game.get_game())?;
+        drawing::draw_centered_text(ctx, canvas, &self.status(), 10.0)?;
+        drawing::draw_button(ctx, canvas, &self.menu_button, false)?;
+        drawing::draw_move_list(canvas, self.game.get_moves(), MOVE_LIST_TOP, None, &[]);
+        Ok(())
+    }
+
+    fn handle_click(&mut self, point: Point2<f32>) -> BoardGameAction {
+        if self.menu_button.contains(point) {
+            self.game.cancel_bot_move();
+            return BoardGameAction::Close;
+        }
+        if self.game.is_human_turn() {
+            if let Some(mv) = self.renderer.move_at(self.game.get_game(), point) {
+                self.game.play_move(mv);
+            }
+        }
+        BoardGameAction::None
+    }
+
+    fn cancel_bot_move(&mut self) {
+        self.game.cancel_bot_move();
+    }
+}
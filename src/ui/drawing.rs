@@ -1,7 +1,10 @@
+use std::fmt::Display;
 use ggez::{Context, GameResult};
 use ggez::graphics::{self, Canvas, DrawParam, Text, TextFragment, Drawable, Color};
-use crate::games::connect4::board::Board;
-use crate::config::{CELL_SIZE, GRID_COLS, GRID_ROWS};
+use connect_four::controller::BotAnalysis;
+use connect_four::games::connect4::board::Board;
+use connect_four::games::connect4::rules::Rules;
+use connect_four::config::{SCREEN_HEIGHT, SCREEN_WIDTH};
 use crate::ui::button::Button;
 use ggez::mint::Point2;
 
@@ -20,26 +23,84 @@ pub fn draw_button(ctx: &mut Context, canvas: &mut Canvas, button: &Button, sele
     canvas.draw(
         &text,
         DrawParam::default().dest([
-            button.rect.x + (button.rect.w - text_dims.w as f32) / 2.0,
-            button.rect.y + (button.rect.h - text_dims.h as f32) / 2.0,
+            button.rect.x + (button.rect.w - text_dims.w) / 2.0,
+            button.rect.y + (button.rect.h - text_dims.h) / 2.0,
         ]),
     );
 
     Ok(())
 }
 
-pub fn draw_board(ctx: &mut Context, canvas: &mut Canvas, board: &Board) -> GameResult {
+// Draws a line of text centered over the board, such as a status line
+pub fn draw_centered_text(ctx: &mut Context, canvas: &mut Canvas, text: &str, y: f32) -> GameResult {
+    let text = Text::new(TextFragment::new(text).color(Color::BLACK));
+    let text_dims = text.dimensions(ctx).unwrap();
+    canvas.draw(&text, DrawParam::default().dest([(SCREEN_WIDTH - text_dims.w) / 2.0, y]));
+    Ok(())
+}
+
+// Lists the moves of the game in the panel right of the board, one line per pair of moves,
+// written as in move sequence notation. The line holding the last of the first `shown` moves
+// is highlighted, and moves marked in `mistakes` get a question mark.
+pub fn draw_move_list<M: Display>(canvas: &mut Canvas, moves: &[M], top: f32, shown: Option<usize>, mistakes: &[bool]) {
+    let title = Text::new(TextFragment::new("Moves").color(Color::BLACK).scale(36.0));
+    canvas.draw(&title, DrawParam::default().dest([SCREEN_WIDTH + 20.0, top]));
+
+    let label = |index: usize| {
+        let mark = if mistakes.get(index) == Some(&true) { "?" } else { "" };
+        format!("{}{}", moves[index], mark)
+    };
+    for line_index in 0..moves.len().div_ceil(2) {
+        let first = 2 * line_index;
+        let mut line = format!("{:>2}.   {}", line_index + 1, label(first));
+        if first + 1 < moves.len() {
+            line += &format!("   {}", label(first + 1));
+        }
+        let current = shown.is_some_and(|shown| shown > first && shown <= first + 2);
+        let color = if current { Color::BLUE } else { Color::BLACK };
+        let text = Text::new(TextFragment::new(line).color(color).scale(28.0));
+        canvas.draw(&text, DrawParam::default().dest([SCREEN_WIDTH + 20.0, top + 50.0 + line_index as f32 * 36.0]));
+    }
+}
+
+// Side of a cell: the largest that fits the whole board in the area left of the side panel
+pub fn cell_size(rules: &Rules) -> f32 {
+    (SCREEN_WIDTH / rules.columns as f32).min(SCREEN_HEIGHT / rules.rows as f32)
+}
+
+// The cell under `point`, as (row, col), if it is on the board
+pub fn cell_at(rules: &Rules, point: Point2<f32>) -> Option<(usize, usize)> {
+    let cell_size = cell_size(rules);
+    let (col, row_from_top) = ((point.x / cell_size) as usize, (point.y / cell_size) as usize);
+    (point.x >= 0.0 && point.y >= 0.0 && col < rules.columns && row_from_top < rules.rows)
+        .then(|| (rules.rows - 1 - row_from_top, col))
+}
+
+// Center of the cell at (row, col), with row 0 at the bottom
+fn cell_center(rules: &Rules, row: usize, col: usize) -> Point2<f32> {
+    let cell_size = cell_size(rules);
+    Point2 {
+        x: col as f32 * cell_size + cell_size / 2.0,
+        y: (rules.rows - (row + 1)) as f32 * cell_size + cell_size / 2.0,
+    }
+}
+
+// Draws the grid and the discs, with the statistics of a thinking bot on top when `analysis` is set
+pub fn draw_board(ctx: &mut Context, canvas: &mut Canvas, board: &Board, analysis: Option<&BotAnalysis>) -> GameResult {
+    let rules = board.get_rules();
+    let cell_size = cell_size(&rules);
+
     // Draw the grid
-    for row in 0..GRID_ROWS {
-        for col in 0..GRID_COLS {
+    for row in 0..rules.rows {
+        for col in 0..rules.columns {
             let rect = graphics::Mesh::new_rectangle(
                 ctx,
                 graphics::DrawMode::stroke(2.0),
                 graphics::Rect::new(
-                    col as f32 * CELL_SIZE,
-                    (GRID_ROWS - (row + 1)) as f32 * CELL_SIZE,
-                    CELL_SIZE,
-                    CELL_SIZE,
+                    col as f32 * cell_size,
+                    (rules.rows - (row + 1)) as f32 * cell_size,
+                    cell_size,
+                    cell_size,
                 ),
                 Color::BLACK,
             )?;
@@ -52,11 +113,8 @@ pub fn draw_board(ctx: &mut Context, canvas: &mut Canvas, board: &Board) -> Game
                 let circle = graphics::Mesh::new_circle(
                     ctx,
                     graphics::DrawMode::fill(),
-                    Point2 {
-                        x: col as f32 * CELL_SIZE + CELL_SIZE / 2.0,
-                        y: (GRID_ROWS - (row + 1)) as f32 * CELL_SIZE + CELL_SIZE / 2.0,
-                    },
-                    CELL_SIZE / 2.5,
+                    cell_center(&rules, row, col),
+                    cell_size / 2.5,
                     0.1,
                     color,
                 )?;
@@ -64,5 +122,57 @@ pub fn draw_board(ctx: &mut Context, canvas: &mut Canvas, board: &Board) -> Game
             }
         }
     }
+    if let Some(analysis) = analysis {
+        draw_analysis(ctx, canvas, board, analysis)?;
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+// Labels every column with its share of the visits and the estimated win rate for the bot,
+// and marks the cells of the best line with numbered rings in the order they would be played
+fn draw_analysis(ctx: &mut Context, canvas: &mut Canvas, board: &Board, analysis: &BotAnalysis) -> GameResult {
+    let rules = board.get_rules();
+    let cell_size = cell_size(&rules);
+    let stats = &analysis.stats;
+    for child in &stats.children {
+        let label = match child.result.map(|result| if analysis.player_number == 1 { result } else { -result }) {
+            Some(1..) => "proven win".to_string(),
+            Some(0) => "proven draw".to_string(),
+            Some(_) => "proven loss".to_string(),
+            None => format!(
+                "{:.0}% visits\n{:.0}% win",
+                100.0 * child.visits as f32 / stats.total_visits.max(1) as f32,
+                50.0 * (child.value(analysis.player_number) + 1.0),
+            ),
+        };
+        let color = if child.action == stats.best_move { Color::new(0.0, 0.5, 0.0, 1.0) } else { Color::BLACK };
+        let text = Text::new(TextFragment::new(label).color(color).scale(28.0));
+        let text_dims = text.dimensions(ctx).unwrap();
+        canvas.draw(
+            &text,
+            DrawParam::default().dest([
+                child.action as f32 * cell_size + (cell_size - text_dims.w) / 2.0,
+                50.0,
+            ]),
+        );
+    }
+
+    let mut line_board = board.clone();
+    let mut player_number = analysis.player_number;
+    for (index, &col) in stats.best_line.iter().enumerate() {
+        let Some((row, col)) = line_board.make_move(col, player_number) else {
+            break;
+        };
+        let color = if player_number == 1 { Color::new(1.0, 0.0, 0.0, 0.6) } else { Color::new(0.8, 0.8, 0.0, 0.6) };
+        let center = cell_center(&rules, row, col);
+        let ring = graphics::Mesh::new_circle(ctx, graphics::DrawMode::stroke(8.0), center, cell_size / 2.5, 0.1, color)?;
+        canvas.draw(&ring, DrawParam::default());
+
+        let number = Text::new(TextFragment::new((index + 1).to_string()).color(color).scale(64.0));
+        let number_dims = number.dimensions(ctx).unwrap();
+        canvas.draw(&number, DrawParam::default().dest([center.x - number_dims.w / 2.0, center.y - number_dims.h / 2.0]));
+
+        player_number = if player_number == 1 { 2 } else { 1 };
+    }
+    Ok(())
+}
@@ -49,12 +49,19 @@ impl<T: Clone> Dropdown<T> {
         }
 
         // Check each option rectangle
-        for i in 0..self.options.len() {
-            if self.get_option_rect(i).contains(point) {
-                return Some(i);
+        (0..self.options.len()).find(|&i| self.get_option_rect(i).contains(point))
+    }
+
+    // Opens the dropdown, or picks the option under `point` and closes it
+    pub fn click(&mut self, point: Point2<f32>) {
+        if self.is_open {
+            if let Some(index) = self.get_option_at(point) {
+                self.selected_index = index;
             }
+            self.is_open = false;
+        } else {
+            self.is_open = true;
         }
-        None
     }
 
     pub fn selected_value(&self) -> T {
@@ -94,7 +101,7 @@ impl<T: Clone> Dropdown<T> {
                 &text,
                 DrawParam::default().dest([
                     rect.x + 10.0,
-                    rect.y + (rect.h - text_dims.h as f32) / 2.0,
+                    rect.y + (rect.h - text_dims.h) / 2.0,
                 ]),
             );
         }
@@ -106,8 +113,8 @@ impl<T: Clone> Dropdown<T> {
         canvas.draw(
             &arrow_text,
             DrawParam::default().dest([
-                self.rect.x + self.rect.w - arrow_dims.w as f32 - 10.0,
-                self.rect.y + (self.rect.h - arrow_dims.h as f32) / 2.0,
+                self.rect.x + self.rect.w - arrow_dims.w - 10.0,
+                self.rect.y + (self.rect.h - arrow_dims.h) / 2.0,
             ]),
         );
 
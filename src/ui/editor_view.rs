@@ -0,0 +1,122 @@
+use ggez::{Context, GameResult};
+use ggez::graphics::Canvas;
+use ggez::mint::Point2;
+
+use connect_four::editor::PositionEditor;
+use connect_four::games::connect4::bitboard::BitBoard;
+use connect_four::games::connect4::notation::format_grid;
+use connect_four::config::{SCREEN_WIDTH, BUTTON_WIDTH, BUTTON_HEIGHT};
+use crate::ui::{button::Button, drawing};
+
+// What clicking on the board does
+#[derive(PartialEq, Clone, Copy)]
+enum Brush {
+    Disc(u8),  // Places a disc of this player, or recolors one
+    Erase,     // Removes the top disc of the column
+}
+
+// What a click asks of the screen around the editor
+#[derive(PartialEq, Clone, Copy)]
+pub enum EditorAction {
+    None,
+    Play(BitBoard),     // Start a game from the position
+    Analyze(BitBoard),  // Open the position on the replay screen to evaluate it
+    Close,
+}
+
+// Setup screen: discs are placed on the board with the brush picked in the side panel
+pub struct EditorView {
+    editor: PositionEditor,
+    brush: Brush,
+    red_button: Button,
+    yellow_button: Button,
+    erase_button: Button,
+    clear_button: Button,
+    red_to_move_button: Button,
+    yellow_to_move_button: Button,
+    play_button: Button,
+    analyze_button: Button,
+    back_button: Button,
+}
+
+impl EditorView {
+    pub fn new(position: &BitBoard) -> Self {
+        // Two columns of buttons at the top of the side panel
+        let button = |row: usize, column: usize, text: &str| Button::new(
+            SCREEN_WIDTH + 20.0 + column as f32 * (BUTTON_WIDTH + 20.0),
+            20.0 + row as f32 * (BUTTON_HEIGHT + 20.0),
+            BUTTON_WIDTH,
+            BUTTON_HEIGHT,
+            text,
+        );
+
+        Self {
+            editor: PositionEditor::from_position(position),
+            brush: Brush::Disc(1),
+            red_button: button(0, 0, "Red"),
+            yellow_button: button(0, 1, "Yellow"),
+            erase_button: button(1, 0, "Erase"),
+            clear_button: button(1, 1, "Clear"),
+            red_to_move_button: button(2, 0, "Red to move"),
+            yellow_to_move_button: button(2, 1, "Yellow to move"),
+            play_button: button(3, 0, "Play"),
+            analyze_button: button(3, 1, "Analyze"),
+            back_button: button(4, 0, "Back"),
+        }
+    }
+
+    pub fn handle_click(&mut self, point: Point2<f32>) -> EditorAction {
+        if let Some((row, col)) = drawing::cell_at(&self.editor.get_board().get_rules(), point) {
+            match self.brush {
+                Brush::Disc(player_number) => self.editor.place(row, col, player_number),
+                Brush::Erase => self.editor.remove(col),
+            };
+        } else if self.red_button.contains(point) {
+            self.brush = Brush::Disc(1);
+        } else if self.yellow_button.contains(point) {
+            self.brush = Brush::Disc(2);
+        } else if self.erase_button.contains(point) {
+            self.brush = Brush::Erase;
+        } else if self.clear_button.contains(point) {
+            self.editor.clear();
+        } else if self.red_to_move_button.contains(point) {
+            self.editor.set_side_to_move(1);
+        } else if self.yellow_to_move_button.contains(point) {
+            self.editor.set_side_to_move(2);
+        } else if self.play_button.contains(point) {
+            if let Ok(position) = self.editor.validate() {
+                return EditorAction::Play(position);
+            }
+        } else if self.analyze_button.contains(point) {
+            if let Ok(position) = self.editor.validate() {
+                return EditorAction::Analyze(position);
+            }
+        } else if self.back_button.contains(point) {
+            return EditorAction::Close;
+        }
+        EditorAction::None
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        drawing::draw_board(ctx, canvas, self.editor.get_board(), None)?;
+
+        // The grid notation can be passed to the command line, or what keeps the position from being played
+        let status = match self.editor.validate() {
+            Ok(position) => format!("Player {} to move: {}", position.get_current_player(), format_grid(&position)),
+            Err(error) => format!("Cannot play this position: {}", error),
+        };
+        drawing::draw_centered_text(ctx, canvas, &status, 10.0)?;
+
+        // The brush and the side to move are highlighted
+        let side_to_move = self.editor.get_side_to_move();
+        drawing::draw_button(ctx, canvas, &self.red_button, self.brush == Brush::Disc(1))?;
+        drawing::draw_button(ctx, canvas, &self.yellow_button, self.brush == Brush::Disc(2))?;
+        drawing::draw_button(ctx, canvas, &self.erase_button, self.brush == Brush::Erase)?;
+        drawing::draw_button(ctx, canvas, &self.red_to_move_button, side_to_move == 1)?;
+        drawing::draw_button(ctx, canvas, &self.yellow_to_move_button, side_to_move == 2)?;
+        for button in [&self.clear_button, &self.play_button, &self.analyze_button, &self.back_button] {
+            drawing::draw_button(ctx, canvas, button, false)?;
+        }
+        Ok(())
+    }
+}
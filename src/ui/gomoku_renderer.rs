@@ -0,0 +1,73 @@
+use ggez::{Context, GameResult};
+use ggez::graphics::{self, Canvas, Color, DrawParam};
+use ggez::mint::Point2;
+
+use connect_four::games::gomoku::{GomokuBoard, SIZE};
+use connect_four::games::grid::Cell;
+use connect_four::config::{SCREEN_WIDTH, SCREEN_HEIGHT};
+use crate::ui::board_game_view::BoardRenderer;
+
+// Draws gomoku as a go board: stones sit on the intersections of the lines, black for player 1
+// and white for player 2
+pub struct GomokuRenderer;
+
+// Star points of the 15x15 board
+const STAR_POINTS: [(usize, usize); 5] = [(3, 3), (3, 11), (7, 7), (11, 3), (11, 11)];
+
+// Distance between neighbouring lines. Intersection (row, col) is the center of the square
+// (row, col) of that side, so the outer lines keep half a square of wood around them.
+fn spacing() -> f32 {
+    SCREEN_WIDTH.min(SCREEN_HEIGHT) / SIZE as f32
+}
+
+fn intersection(row: usize, col: usize) -> Point2<f32> {
+    let spacing = spacing();
+    Point2 { x: (col as f32 + 0.5) * spacing, y: (row as f32 + 0.5) * spacing }
+}
+
+impl BoardRenderer<GomokuBoard> for GomokuRenderer {
+    fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, board: &GomokuBoard) -> GameResult {
+        let spacing = spacing();
+        let wood = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, SIZE as f32 * spacing, SIZE as f32 * spacing),
+            Color::from_rgb(220, 179, 92),
+        )?;
+        canvas.draw(&wood, DrawParam::default());
+
+        for i in 0..SIZE {
+            for (from, to) in [(intersection(i, 0), intersection(i, SIZE - 1)), (intersection(0, i), intersection(SIZE - 1, i))] {
+                let line = graphics::Mesh::new_line(ctx, &[from, to], 2.0, Color::BLACK)?;
+                canvas.draw(&line, DrawParam::default());
+            }
+        }
+        for (row, col) in STAR_POINTS {
+            let star = graphics::Mesh::new_circle(ctx, graphics::DrawMode::fill(), intersection(row, col), spacing / 12.0, 0.1, Color::BLACK)?;
+            canvas.draw(&star, DrawParam::default());
+        }
+
+        for row in 0..SIZE {
+            for col in 0..SIZE {
+                let color = match board.get_player_at(Cell::new(row, col)) {
+                    1 => Color::BLACK,
+                    2 => Color::WHITE,
+                    _ => continue,
+                };
+                let center = intersection(row, col);
+                let stone = graphics::Mesh::new_circle(ctx, graphics::DrawMode::fill(), center, spacing * 0.45, 0.1, color)?;
+                canvas.draw(&stone, DrawParam::default());
+                let outline = graphics::Mesh::new_circle(ctx, graphics::DrawMode::stroke(2.0), center, spacing * 0.45, 0.1, Color::BLACK)?;
+                canvas.draw(&outline, DrawParam::default());
+            }
+        }
+        Ok(())
+    }
+
+    // The nearest intersection
+    fn move_at(&self, _board: &GomokuBoard, point: Point2<f32>) -> Option<Cell> {
+        let spacing = spacing();
+        let cell = Cell::new((point.y / spacing) as usize, (point.x / spacing) as usize);
+        (point.x >= 0.0 && point.y >= 0.0 && cell.row < SIZE && cell.col < SIZE).then_some(cell)
+    }
+}
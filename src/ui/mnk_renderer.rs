@@ -0,0 +1,66 @@
+use ggez::{Context, GameResult};
+use ggez::graphics::{self, Canvas, Color, DrawParam};
+use ggez::mint::Point2;
+
+use connect_four::games::grid::Cell;
+use connect_four::games::mnk::MnkBoard;
+use connect_four::config::{SCREEN_WIDTH, SCREEN_HEIGHT};
+use crate::ui::board_game_view::BoardRenderer;
+
+// Draws m,n,k-games as a grid of cells with red crosses for player 1 and blue rings for player 2
+pub struct MnkRenderer;
+
+// Side of a cell: the largest that fits the whole board left of the side panel
+fn cell_size(board: &MnkBoard) -> f32 {
+    (SCREEN_WIDTH / board.get_columns() as f32).min(SCREEN_HEIGHT / board.get_rows() as f32)
+}
+
+impl BoardRenderer<MnkBoard> for MnkRenderer {
+    fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, board: &MnkBoard) -> GameResult {
+        let cell_size = cell_size(board);
+        for row in 0..board.get_rows() {
+            for col in 0..board.get_columns() {
+                let (x, y) = (col as f32 * cell_size, row as f32 * cell_size);
+                let rect = graphics::Mesh::new_rectangle(
+                    ctx,
+                    graphics::DrawMode::stroke(2.0),
+                    graphics::Rect::new(x, y, cell_size, cell_size),
+                    Color::BLACK,
+                )?;
+                canvas.draw(&rect, DrawParam::default());
+
+                let center = Point2 { x: x + cell_size / 2.0, y: y + cell_size / 2.0 };
+                let reach = cell_size / 3.0;
+                let width = cell_size / 12.0;
+                match board.get_player_at(Cell::new(row, col)) {
+                    1 => {
+                        for (dx, dy) in [(reach, reach), (reach, -reach)] {
+                            let stroke = graphics::Mesh::new_line(
+                                ctx,
+                                &[
+                                    Point2 { x: center.x - dx, y: center.y - dy },
+                                    Point2 { x: center.x + dx, y: center.y + dy },
+                                ],
+                                width,
+                                Color::RED,
+                            )?;
+                            canvas.draw(&stroke, DrawParam::default());
+                        }
+                    }
+                    2 => {
+                        let ring = graphics::Mesh::new_circle(ctx, graphics::DrawMode::stroke(width), center, reach, 0.1, Color::BLUE)?;
+                        canvas.draw(&ring, DrawParam::default());
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn move_at(&self, board: &MnkBoard, point: Point2<f32>) -> Option<Cell> {
+        let cell_size = cell_size(board);
+        let cell = Cell::new((point.y / cell_size) as usize, (point.x / cell_size) as usize);
+        (point.x >= 0.0 && point.y >= 0.0 && cell.row < board.get_rows() && cell.col < board.get_columns()).then_some(cell)
+    }
+}
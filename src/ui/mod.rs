@@ -1,4 +1,10 @@
 pub mod button;
 pub mod screen;
 pub mod drawing;
-pub mod dropdown;
\ No newline at end of file
+pub mod dropdown;
+pub mod editor_view;
+pub mod replay_view;
+pub mod board_game_view;
+pub mod mnk_renderer;
+pub mod gomoku_renderer;
+pub mod othello_renderer;
@@ -0,0 +1,65 @@
+use ggez::{Context, GameResult};
+use ggez::graphics::{self, Canvas, Color, DrawParam};
+use ggez::mint::Point2;
+
+use connect_four::games::Game;
+use connect_four::games::grid::Cell;
+use connect_four::games::othello::{Move, OthelloBoard, SIZE};
+use connect_four::config::{SCREEN_WIDTH, SCREEN_HEIGHT};
+use crate::ui::board_game_view::BoardRenderer;
+
+// Draws Othello as a green board with black discs for player 1, white discs for player 2 and
+// dots where the player to move may put a disc
+pub struct OthelloRenderer;
+
+fn cell_size() -> f32 {
+    SCREEN_WIDTH.min(SCREEN_HEIGHT) / SIZE as f32
+}
+
+impl BoardRenderer<OthelloBoard> for OthelloRenderer {
+    fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, board: &OthelloBoard) -> GameResult {
+        let cell_size = cell_size();
+        let placements = board.get_placements();
+        for row in 0..SIZE {
+            for col in 0..SIZE {
+                let rect = graphics::Rect::new(col as f32 * cell_size, row as f32 * cell_size, cell_size, cell_size);
+                let felt = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), rect, Color::from_rgb(0, 128, 64))?;
+                canvas.draw(&felt, DrawParam::default());
+                let border = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::stroke(2.0), rect, Color::BLACK)?;
+                canvas.draw(&border, DrawParam::default());
+
+                let cell = Cell::new(row, col);
+                let center = Point2 { x: rect.x + cell_size / 2.0, y: rect.y + cell_size / 2.0 };
+                let color = match board.get_player_at(cell) {
+                    1 => Color::BLACK,
+                    2 => Color::WHITE,
+                    _ => {
+                        if placements.contains(&cell) {
+                            let dot = graphics::Mesh::new_circle(ctx, graphics::DrawMode::fill(), center, cell_size / 10.0, 0.1, Color::from_rgb(0, 80, 40))?;
+                            canvas.draw(&dot, DrawParam::default());
+                        }
+                        continue;
+                    }
+                };
+                let disc = graphics::Mesh::new_circle(ctx, graphics::DrawMode::fill(), center, cell_size * 0.4, 0.1, color)?;
+                canvas.draw(&disc, DrawParam::default());
+            }
+        }
+        Ok(())
+    }
+
+    // The cell clicked, or a pass for any click on the board when there is nowhere to put a disc
+    fn move_at(&self, board: &OthelloBoard, point: Point2<f32>) -> Option<Move> {
+        let cell_size = cell_size();
+        let cell = Cell::new((point.y / cell_size) as usize, (point.x / cell_size) as usize);
+        if point.x < 0.0 || point.y < 0.0 || cell.row >= SIZE || cell.col >= SIZE {
+            return None;
+        }
+        if board.get_valid_moves() == [Move::Pass] { Some(Move::Pass) } else { Some(Move::Place(cell)) }
+    }
+
+    fn score(&self, board: &OthelloBoard) -> Option<String> {
+        let [black, white] = board.count_discs();
+        Some(format!("Black {} - White {}", black, white))
+    }
+}
@@ -0,0 +1,201 @@
+use std::time::Duration;
+use ggez::{Context, GameResult};
+use ggez::graphics::Canvas;
+use ggez::input::keyboard::KeyCode;
+use ggez::mint::Point2;
+
+use connect_four::replay::{Evaluator, Replay};
+use connect_four::config::{SCREEN_WIDTH, BUTTON_WIDTH, BUTTON_HEIGHT};
+use crate::ui::{button::Button, drawing};
+
+// Delays between moves offered for autoplay
+const AUTOPLAY_DELAYS: [Duration; 4] = [
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_millis(1000),
+    Duration::from_millis(2000),
+];
+
+// Thinking time per position when MCTS annotates a replay
+const MCTS_ANNOTATION_MS: u64 = 500;
+
+// Top of the move list in the side panel, below the five rows of buttons
+const MOVE_LIST_TOP: f32 = 120.0 + 5.0 * BUTTON_HEIGHT;
+
+// What a click or key asks of the screen around the replay
+#[derive(PartialEq, Clone, Copy)]
+pub enum ReplayAction {
+    None,
+    Close,
+}
+
+// Replay screen: the board of a finished or saved game with step-through controls in the side panel
+pub struct ReplayView {
+    replay: Replay,
+    delay_index: usize,  // Index into `AUTOPLAY_DELAYS`
+    evaluator: Option<Evaluator>,
+    first_button: Button,
+    last_button: Button,
+    previous_button: Button,
+    next_button: Button,
+    play_button: Button,
+    speed_button: Button,
+    solver_button: Button,
+    mcts_button: Button,
+    close_button: Button,
+}
+
+impl ReplayView {
+    pub fn new(replay: Replay) -> Self {
+        // Two columns of buttons at the top of the side panel
+        let button = |row: usize, column: usize, text: &str| Button::new(
+            SCREEN_WIDTH + 20.0 + column as f32 * (BUTTON_WIDTH + 20.0),
+            20.0 + row as f32 * (BUTTON_HEIGHT + 20.0),
+            BUTTON_WIDTH,
+            BUTTON_HEIGHT,
+            text,
+        );
+
+        let mut view = Self {
+            replay,
+            delay_index: 2,
+            evaluator: None,
+            first_button: button(0, 0, "|< First"),
+            last_button: button(0, 1, "Last >|"),
+            previous_button: button(1, 0, "< Previous"),
+            next_button: button(1, 1, "Next >"),
+            play_button: button(2, 0, "Play"),
+            speed_button: button(2, 1, ""),
+            solver_button: button(3, 0, "Solver"),
+            mcts_button: button(3, 1, "MCTS"),
+            close_button: button(4, 0, "Close"),
+        };
+        view.update_labels();
+        view
+    }
+
+    // Advances autoplay; call every frame
+    pub fn update(&mut self) {
+        self.replay.update();
+        self.update_labels();
+    }
+
+    pub fn handle_click(&mut self, point: Point2<f32>) -> ReplayAction {
+        if self.first_button.contains(point) {
+            self.replay.first();
+        } else if self.last_button.contains(point) {
+            self.replay.last();
+        } else if self.previous_button.contains(point) {
+            self.replay.previous();
+        } else if self.next_button.contains(point) {
+            self.replay.next();
+        } else if self.play_button.contains(point) {
+            self.toggle_autoplay();
+        } else if self.speed_button.contains(point) {
+            self.delay_index = (self.delay_index + 1) % AUTOPLAY_DELAYS.len();
+            if self.replay.get_autoplay().is_some() {
+                self.replay.set_autoplay(Some(AUTOPLAY_DELAYS[self.delay_index]));
+            }
+        } else if self.solver_button.contains(point) {
+            self.annotate(Evaluator::Solver);
+        } else if self.mcts_button.contains(point) {
+            self.annotate(Evaluator::Mcts { time_ms: MCTS_ANNOTATION_MS });
+        } else if self.close_button.contains(point) {
+            return ReplayAction::Close;
+        }
+        self.update_labels();
+        ReplayAction::None
+    }
+
+    // Arrow keys step, Home and End jump, Space starts and stops autoplay
+    pub fn handle_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Left => self.replay.previous(),
+            KeyCode::Right => self.replay.next(),
+            KeyCode::Home => self.replay.first(),
+            KeyCode::End => self.replay.last(),
+            KeyCode::Space => self.toggle_autoplay(),
+            _ => {}
+        }
+        self.update_labels();
+    }
+
+    fn toggle_autoplay(&mut self) {
+        if self.replay.get_autoplay().is_some() {
+            self.replay.set_autoplay(None);
+        } else {
+            // Start over when the game has already been played to the end
+            if self.replay.get_position() == self.replay.get_moves().len() {
+                self.replay.first();
+            }
+            self.replay.set_autoplay(Some(AUTOPLAY_DELAYS[self.delay_index]));
+        }
+    }
+
+    fn annotate(&mut self, evaluator: Evaluator) {
+        if self.evaluator != Some(evaluator) {
+            self.evaluator = Some(evaluator);
+            self.replay.annotate(evaluator);
+        }
+    }
+
+    fn update_labels(&mut self) {
+        self.play_button.text = if self.replay.get_autoplay().is_some() { "Pause" } else { "Play" }.to_string();
+        self.speed_button.text = format!("Speed: {:.2}s", AUTOPLAY_DELAYS[self.delay_index].as_secs_f32());
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        drawing::draw_board(ctx, canvas, self.replay.get_board(), None)?;
+
+        let position = self.replay.get_position();
+        let player_number = self.replay.get_current_player_number();
+        let status = if self.replay.is_final_position() {
+            format!("Move {} of {}: game over", position, self.replay.get_moves().len())
+        } else {
+            format!(
+                "Move {} of {}: Player {} ({}) to move",
+                position,
+                self.replay.get_moves().len(),
+                player_number,
+                self.replay.get_players()[player_number as usize - 1].label(),
+            )
+        };
+        drawing::draw_centered_text(ctx, canvas, &status, 10.0)?;
+
+        // What the evaluator thinks of the shown position and of the move that was played from it
+        if let Some(evaluator) = self.evaluator {
+            let name = match evaluator {
+                Evaluator::Solver => "Solver",
+                Evaluator::Mcts { .. } => "MCTS",
+            };
+            let text = match self.replay.get_annotation(position) {
+                _ if self.replay.is_final_position() => String::new(),
+                _ if !self.replay.can_annotate(evaluator) => format!("{}: not available for {}", name, self.replay.get_board().get_rules()),
+                Some(annotation) => {
+                    let mut text = format!("{}: {}, best move {}", name, annotation.best, annotation.best_move + 1);
+                    if let (Some(played), Some(&mv)) = (annotation.played, self.replay.get_moves().get(position)) {
+                        text += &format!(", played {}: {}", mv, played);
+                        if annotation.is_mistake() {
+                            text += " (mistake)";
+                        }
+                    }
+                    text
+                }
+                None => format!("{}: evaluating...", name),
+            };
+            drawing::draw_centered_text(ctx, canvas, &text, 40.0)?;
+        }
+
+        for button in [
+            &self.first_button, &self.last_button, &self.previous_button, &self.next_button,
+            &self.play_button, &self.speed_button, &self.close_button,
+        ] {
+            drawing::draw_button(ctx, canvas, button, false)?;
+        }
+        // The evaluator in use is highlighted
+        drawing::draw_button(ctx, canvas, &self.solver_button, self.evaluator == Some(Evaluator::Solver))?;
+        drawing::draw_button(ctx, canvas, &self.mcts_button, matches!(self.evaluator, Some(Evaluator::Mcts { .. })))?;
+        drawing::draw_move_list(canvas, self.replay.get_moves(), MOVE_LIST_TOP, Some(position), &self.replay.get_mistakes());
+        Ok(())
+    }
+}
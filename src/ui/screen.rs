@@ -3,4 +3,7 @@ pub enum GameScreen {
     Menu,
     Game,
     GameOver,
+    Replay,  // Stepping through a finished or saved game
+    Editor,  // Setting up a position to play or analyze
+    BoardGame,  // Playing one of the games besides connect four
 }
\ No newline at end of file